@@ -0,0 +1,194 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! An ink! contract splitting the assets it holds between its payees, in proportion to their
+//! shares. It reads balances and transfers assets through the runtime's chain extension.
+
+use ink_env::{DefaultEnvironment, Environment};
+use ink_lang as ink;
+
+/// Asset ID in the runtime's assets pallet.
+pub type AssetId = u32;
+
+type AccountId = <DefaultEnvironment as Environment>::AccountId;
+type Balance = <DefaultEnvironment as Environment>::Balance;
+
+/// The runtime's chain extension, see `runtime/src/chain_extension.rs`.
+#[ink::chain_extension]
+pub trait DefiExtension {
+	type ErrorCode = ExtensionError;
+
+	/// The free native balance of `who`.
+	#[ink(extension = 1, handle_status = false, returns_result = false)]
+	fn balance(who: AccountId) -> Balance;
+
+	/// The balance of `asset` held by `who`.
+	#[ink(extension = 2, handle_status = false, returns_result = false)]
+	fn asset_balance(asset: AssetId, who: AccountId) -> Balance;
+
+	/// Transfer `amount` of `asset` from the contract to `to`.
+	#[ink(extension = 3, returns_result = false)]
+	fn asset_transfer(asset: AssetId, to: AccountId, amount: Balance);
+}
+
+/// Failure status codes of the chain extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ExtensionError {
+	/// The asset transfer failed and left no changes behind.
+	TransferFailed,
+}
+
+impl ink_env::chain_extension::FromStatusCode for ExtensionError {
+	fn from_status_code(status_code: u32) -> Result<(), Self> {
+		match status_code {
+			0 => Ok(()),
+			1 => Err(Self::TransferFailed),
+			_ => panic!("Unknown status code of the chain extension."),
+		}
+	}
+}
+
+/// The default environment, with the runtime's chain extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RuntimeEnvironment {}
+
+impl Environment for RuntimeEnvironment {
+	const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+	type AccountId = AccountId;
+	type Balance = Balance;
+	type Hash = <DefaultEnvironment as Environment>::Hash;
+	type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+	type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+	type RentFraction = <DefaultEnvironment as Environment>::RentFraction;
+
+	type ChainExtension = DefiExtension;
+}
+
+#[ink::contract(env = crate::RuntimeEnvironment)]
+mod payment_splitter {
+	use crate::AssetId;
+	use ink_prelude::vec::Vec;
+
+	/// Payees and their shares.
+	#[ink(storage)]
+	pub struct PaymentSplitter {
+		payees: Vec<(AccountId, u32)>,
+		total_shares: Balance,
+	}
+
+	/// The contract's balance of `asset` was transferred to the payees.
+	#[ink(event)]
+	pub struct Released {
+		#[ink(topic)]
+		asset: AssetId,
+		amount: Balance,
+	}
+
+	impl PaymentSplitter {
+		/// Split assets between `payees`, in proportion to their shares.
+		///
+		/// Panics if no payee holds a share.
+		#[ink(constructor)]
+		pub fn new(payees: Vec<(AccountId, u32)>) -> Self {
+			let total_shares = payees.iter().map(|(_, shares)| *shares as Balance).sum();
+			assert!(total_shares > 0, "Payees must hold shares.");
+			Self { payees, total_shares }
+		}
+
+		/// Payees and their shares.
+		#[ink(message)]
+		pub fn payees(&self) -> Vec<(AccountId, u32)> {
+			self.payees.clone()
+		}
+
+		/// The contract's balance of `asset`, which `release` splits.
+		#[ink(message)]
+		pub fn pending(&self, asset: AssetId) -> Balance {
+			self.env().extension().asset_balance(asset, self.env().account_id())
+		}
+
+		/// Transfer the contract's balance of `asset` to the payees. Amounts are rounded down,
+		/// and what is left stays in the contract for the next release.
+		///
+		/// Panics, reverting all transfers, if a transfer fails.
+		#[ink(message)]
+		pub fn release(&mut self, asset: AssetId) {
+			let amount = self.pending(asset);
+			for (payee, part) in split(amount, &self.payees, self.total_shares) {
+				if part > 0 {
+					self.env()
+						.extension()
+						.asset_transfer(asset, payee, part)
+						.expect("Transfer to a payee failed.");
+				}
+			}
+			self.env().emit_event(Released { asset, amount });
+		}
+	}
+
+	/// Each payee's part of `amount`, rounded down.
+	fn split(
+		amount: Balance,
+		payees: &[(AccountId, u32)],
+		total_shares: Balance,
+	) -> Vec<(AccountId, Balance)> {
+		payees
+			.iter()
+			.map(|(payee, shares)| {
+				let shares = *shares as Balance;
+				// Split the quotient and remainder separately, so the product can't overflow.
+				let part =
+					amount / total_shares * shares + amount % total_shares * shares / total_shares;
+				(*payee, part)
+			})
+			.collect()
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+		use ink_lang as ink;
+
+		fn account(byte: u8) -> AccountId {
+			AccountId::from([byte; 32])
+		}
+
+		#[ink::test]
+		fn new_records_payees() {
+			let payees = vec![(account(1), 1), (account(2), 3)];
+			let splitter = PaymentSplitter::new(payees.clone());
+			assert_eq!(splitter.payees(), payees);
+			assert_eq!(splitter.total_shares, 4);
+		}
+
+		#[ink::test]
+		#[should_panic(expected = "Payees must hold shares.")]
+		fn new_rejects_payees_without_shares() {
+			PaymentSplitter::new(vec![(account(1), 0)]);
+		}
+
+		#[test]
+		fn split_is_proportional_to_shares() {
+			let payees = [(account(1), 1), (account(2), 3)];
+			assert_eq!(split(100, &payees, 4), vec![(account(1), 25), (account(2), 75)]);
+		}
+
+		#[test]
+		fn split_rounds_down() {
+			let payees = [(account(1), 1), (account(2), 1), (account(3), 1)];
+			let parts = split(10, &payees, 3);
+			assert!(parts.iter().all(|(_, part)| *part == 3));
+		}
+
+		#[test]
+		fn split_does_not_overflow() {
+			let payees = [(account(1), u32::MAX), (account(2), u32::MAX)];
+			let total = 2 * u32::MAX as Balance;
+			let parts = split(Balance::MAX, &payees, total);
+			assert_eq!(parts[0].1, Balance::MAX / 2);
+			assert_eq!(parts[1].1, Balance::MAX / 2);
+		}
+	}
+}
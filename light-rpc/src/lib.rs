@@ -0,0 +1,57 @@
+//! Runtime API calls for the RPC methods of light clients.
+//!
+//! A light client has no state to run the runtime API on, so its RPC methods have a full node
+//! execute the call through the `on_demand` fetcher, which checks the execution proof the full
+//! node returns.
+
+use codec::Decode;
+use futures::{future, Future, FutureExt, TryFutureExt};
+use jsonrpc_core::{Error as RpcError, ErrorCode};
+use sc_client_api::light::{future_header, Fetcher, RemoteBlockchain, RemoteCallRequest};
+use sp_blockchain::Error as ClientError;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+/// Error code returned when the runtime API call fails.
+pub const RUNTIME_ERROR: i64 = 1;
+
+/// Execute the runtime API function `method` with the encoded arguments `call_data` on a full
+/// node at block `at`, and decode its result. Failures are returned as `RUNTIME_ERROR` with
+/// `message`.
+pub fn remote_call<Block, F, R>(
+	remote_blockchain: &dyn RemoteBlockchain<Block>,
+	fetcher: Arc<F>,
+	at: Block::Hash,
+	method: &'static str,
+	call_data: Vec<u8>,
+	message: &'static str,
+) -> impl Future<Output = Result<R, RpcError>>
+where
+	Block: BlockT,
+	F: Fetcher<Block> + 'static,
+	R: Decode,
+{
+	future_header(remote_blockchain, &*fetcher, BlockId::hash(at))
+		.and_then(move |header| match header {
+			Some(header) => future::Either::Left(fetcher.remote_call(RemoteCallRequest {
+				block: at,
+				header,
+				method: method.into(),
+				call_data,
+				retry_count: None,
+			})),
+			None => future::Either::Right(future::ready(Err(ClientError::UnknownBlock(
+				at.to_string(),
+			)))),
+		})
+		.map(move |result| {
+			result.and_then(|bytes| {
+				R::decode(&mut &bytes[..]).map_err(|e| ClientError::CallResultDecode(method, e))
+			})
+		})
+		.map_err(move |e| RpcError {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: message.into(),
+			data: Some(e.to_string().into()),
+		})
+}
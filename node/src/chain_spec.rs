@@ -1,18 +1,119 @@
+use codec::Encode;
+use frame_support::traits::Get;
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	AccountId, BabeConfig, Balance, BalancesConfig, EVMConfig, EthereumConfig, ExistentialDeposit,
+	GenesisConfig, GrandpaConfig, MinimumPeriod, Options, Perpetuals, PredictionMarkets, Runtime,
+	SS58Prefix, Signature, SudoConfig, SystemConfig, VestingConfig, BABE_GENESIS_EPOCH_CONFIG,
+	EPOCH_DURATION_IN_MILLISECS, TOKEN_SYMBOL, WASM_BINARY,
 };
-use sc_service::ChainType;
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{sr25519, Pair, Public};
+use pallet_evm::AddressMapping;
+use sc_chain_spec::ChainSpecExtension;
+use sc_service::{ChainSpec as ChainSpecT, ChainType, Properties};
+use sc_telemetry::TelemetryEndpoints;
+use serde::{Deserialize, Serialize};
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::{crypto::Ss58Codec, sr25519, Pair, Public, H160};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	BuildStorage,
+};
+use std::collections::BTreeMap;
 
 // The URL for the telemetry server.
-// const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
+const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
+
+/// Environment variable giving the BABE and GRANDPA public keys of the staging validators, as
+/// comma-separated `<babe>:<grandpa>` pairs.
+///
+/// Each validator generates its keys offline with `subkey generate --scheme sr25519` (BABE) and
+/// `subkey generate --scheme ed25519` (GRANDPA), inserts the secrets into its keystore with
+/// `key insert`, and sends only the public keys to the operator building the spec.
+const STAGING_AUTHORITIES_VAR: &str = "STAGING_AUTHORITIES";
+
+/// Environment variable giving the staging sudo account, which also receives the initial token
+/// supply. It should be an account generated offline by the staging operators, ideally a
+/// multisig of theirs.
+const STAGING_SUDO_VAR: &str = "STAGING_SUDO";
+
+/// The first account of Hardhat's default mnemonic, funded on development chains so Ethereum
+/// tooling works without setup. Its private key is public; never fund it on a live chain.
+const DEV_EVM_ADDRESS: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+/// Token decimals. One token is `10^TOKEN_DECIMALS` units of `Balance`.
+const TOKEN_DECIMALS: u8 = 12;
+
+/// Consensus parameters a network can tune in its chain spec, without recompiling the node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ChainSpecExtension)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Extensions {
+	/// GRANDPA finality parameters.
+	pub grandpa: GrandpaParams,
+	/// Slot duration in milliseconds. It is written into the genesis state, so it can't change
+	/// once the chain has started. Unset keeps the runtime's `SLOT_DURATION`.
+	pub slot_duration: Option<u64>,
+}
+
+/// Parameters of the GRANDPA voter and observer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GrandpaParams {
+	/// Duration of a gossip round, in milliseconds.
+	pub gossip_duration: u64,
+	/// Number of blocks between the justifications a node stores and serves to syncing peers.
+	pub justification_period: u32,
+}
+
+impl Default for GrandpaParams {
+	fn default() -> Self {
+		Self { gossip_duration: 333, justification_period: 512 }
+	}
+}
+
+impl Extensions {
+	/// The extensions of `chain_spec`, or the defaults if it has none.
+	pub fn of(chain_spec: &dyn ChainSpecT) -> Self {
+		sc_chain_spec::get_extension::<Self>(chain_spec.extensions())
+			.cloned()
+			.unwrap_or_default()
+	}
+}
+
+/// Token symbol, decimals and address format, as wallets expect them in the chain properties.
+pub fn token_properties() -> Properties {
+	let mut properties = Properties::new();
+	properties.insert("tokenSymbol".into(), TOKEN_SYMBOL.into());
+	properties.insert("tokenDecimals".into(), TOKEN_DECIMALS.into());
+	properties.insert("ss58Format".into(), SS58Prefix::get().into());
+	properties
+}
 
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
-pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
+pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig, Extensions>;
+
+/// Check the extensions of `spec` and write its slot duration, if set, into the genesis state,
+/// where the runtime reads it through the `MinimumPeriod` storage parameter. BABE's epoch length
+/// and expected block time are derived from it.
+pub fn apply_extensions(mut spec: ChainSpec) -> Result<ChainSpec, String> {
+	let Extensions { grandpa, slot_duration } = spec.extensions().clone();
+	if grandpa.gossip_duration == 0 || grandpa.justification_period == 0 {
+		return Err("GRANDPA gossip duration and justification period must not be zero.".into())
+	}
+
+	if let Some(slot_duration) = slot_duration {
+		if slot_duration == 0 || slot_duration % 2 != 0 {
+			return Err("Slot duration must be a positive, even number of milliseconds.".into())
+		}
+		if slot_duration > EPOCH_DURATION_IN_MILLISECS {
+			return Err("Slot duration must not exceed the epoch duration, ten minutes.".into())
+		}
+		let mut storage = spec.build_storage()?;
+		storage.top.insert(MinimumPeriod::key().to_vec(), (slot_duration / 2).encode());
+		spec.set_storage(storage);
+	}
+
+	Ok(spec)
+}
 
 /// Generate a crypto pair from seed.
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
@@ -21,6 +122,25 @@ pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Pu
 		.public()
 }
 
+/// Parse a public key given in SS58 or as `0x`-prefixed hex.
+pub fn public_from_str<TPublic: Public>(s: &str) -> Result<TPublic, String> {
+	if s.starts_with("0x") {
+		let bytes =
+			sp_core::bytes::from_hex(s).map_err(|e| format!("Invalid key `{}`: {}", s, e))?;
+		if bytes.len() != TPublic::default().as_ref().len() {
+			return Err(format!("Invalid key `{}`: wrong length.", s))
+		}
+		Ok(TPublic::from_slice(&bytes))
+	} else {
+		TPublic::from_ss58check(s).map_err(|e| format!("Invalid key `{}`: {:?}", s, e))
+	}
+}
+
+/// Parse an account ID given in SS58 or as `0x`-prefixed hex.
+pub fn account_from_str(s: &str) -> Result<AccountId, String> {
+	s.parse().map_err(|e| format!("Invalid account `{}`: {}", s, e))
+}
+
 type AccountPublic = <Signature as Verify>::Signer;
 
 /// Generate an account ID from seed.
@@ -31,9 +151,16 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
-	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
+/// The account holding the balance of an EVM address.
+pub fn evm_account_id(address: &str) -> Result<AccountId, String> {
+	let address: H160 =
+		address.parse().map_err(|e| format!("Invalid address `{}`: {:?}", address, e))?;
+	Ok(<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address))
+}
+
+/// Generate the BABE and GRANDPA keys of an authority.
+pub fn authority_keys_from_seed(s: &str) -> (BabeId, GrandpaId) {
+	(get_from_seed::<BabeId>(s), get_from_seed::<GrandpaId>(s))
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
@@ -58,6 +185,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Bob"),
 					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
+					evm_account_id(DEV_EVM_ADDRESS).expect("static values are valid; qed"),
 				],
 				true,
 			)
@@ -69,9 +197,9 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		// Protocol ID
 		None,
 		// Properties
-		None,
+		Some(token_properties()),
 		// Extensions
-		None,
+		Default::default(),
 	))
 }
 
@@ -105,6 +233,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
+					evm_account_id(DEV_EVM_ADDRESS).expect("static values are valid; qed"),
 				],
 				true,
 			)
@@ -116,32 +245,115 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		// Protocol ID
 		None,
 		// Properties
-		None,
+		Some(token_properties()),
 		// Extensions
-		None,
+		Default::default(),
 	))
 }
 
-/// Configure initial storage state for FRAME modules.
+/// The staging network.
+///
+/// Its authorities and sudo account are read from the `STAGING_AUTHORITIES` and `STAGING_SUDO`
+/// environment variables when the spec is built, and building it fails if they are unset, so no
+/// keys are compiled into the node.
+///
+/// No bootnodes are compiled in, since their addresses depend on where operators deploy them.
+/// Operators either pass `--bootnodes /dns/<host>/tcp/30333/p2p/<peer id>` to every node, or export
+/// this spec with `build-spec --chain staging --raw`, add the addresses to its `bootNodes` list and
+/// distribute that file for nodes to start with `--chain <file>`.
+pub fn staging_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Staging wasm not available".to_string())?;
+	let initial_authorities = staging_authorities(&staging_var(STAGING_AUTHORITIES_VAR)?)?;
+	let root_key = account_from_str(&staging_var(STAGING_SUDO_VAR)?)?;
+
+	Ok(ChainSpec::from_genesis(
+		// Name
+		"Staging",
+		// ID
+		"staging",
+		ChainType::Live,
+		move || {
+			genesis_config(
+				wasm_binary,
+				initial_authorities.clone(),
+				root_key.clone(),
+				// One million tokens for the sudo account to distribute.
+				vec![(root_key.clone(), 1_000_000 * 10u128.pow(TOKEN_DECIMALS.into()))],
+			)
+		},
+		// Bootnodes, supplied by operators, see above
+		vec![],
+		// Telemetry
+		Some(
+			TelemetryEndpoints::new(vec![(STAGING_TELEMETRY_URL.to_string(), 0)])
+				.expect("Staging telemetry url is valid; qed"),
+		),
+		// Protocol ID
+		Some("defi-staging"),
+		// Properties
+		Some(token_properties()),
+		// Extensions
+		Default::default(),
+	))
+}
+
+/// The value of the environment variable `name`, required to build the staging spec.
+fn staging_var(name: &str) -> Result<String, String> {
+	std::env::var(name).map_err(|e| format!("{} must give the staging network's keys: {}", name, e))
+}
+
+/// Parse staging authorities given as comma-separated `<babe>:<grandpa>` public key pairs.
+fn staging_authorities(s: &str) -> Result<Vec<(BabeId, GrandpaId)>, String> {
+	s.split(',')
+		.map(|pair| match pair.trim().split_once(':') {
+			Some((babe, grandpa)) => Ok((public_from_str(babe)?, public_from_str(grandpa)?)),
+			None => Err(format!("Expected `<babe>:<grandpa>` keys, got `{}`", pair)),
+		})
+		.collect()
+}
+
+/// Configure initial storage state for FRAME modules, for development chains.
 fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(BabeId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
 ) -> GenesisConfig {
+	genesis_config(
+		wasm_binary,
+		initial_authorities,
+		root_key,
+		// Configure endowed accounts with initial balance of 1 << 60.
+		endowed_accounts.into_iter().map(|k| (k, 1 << 60)).collect(),
+	)
+}
+
+/// Accounts of the pallets holding collateral, endowed with the existential deposit so they are
+/// never reaped with the dust of what they hold.
+fn pallet_accounts() -> Vec<AccountId> {
+	vec![PredictionMarkets::account_id(), Options::account_id(), Perpetuals::account_id()]
+}
+
+/// Configure initial storage state for FRAME modules.
+pub fn genesis_config(
+	wasm_binary: &[u8],
+	initial_authorities: Vec<(BabeId, GrandpaId)>,
+	root_key: AccountId,
+	mut balances: Vec<(AccountId, Balance)>,
+) -> GenesisConfig {
+	let deposit = ExistentialDeposit::get();
+	balances.extend(pallet_accounts().into_iter().map(|account| (account, deposit)));
 	GenesisConfig {
 		system: SystemConfig {
 			// Add Wasm runtime to storage.
 			code: wasm_binary.to_vec(),
 			changes_trie_config: Default::default(),
 		},
-		balances: BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
-		},
-		aura: AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
+		balances: BalancesConfig { balances },
+		babe: BabeConfig {
+			authorities: initial_authorities.iter().map(|x| (x.0.clone(), 1)).collect(),
+			epoch_config: Some(BABE_GENESIS_EPOCH_CONFIG),
 		},
 		grandpa: GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
@@ -150,5 +362,8 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		},
+		vesting: VestingConfig { vesting: vec![] },
+		evm: EVMConfig { accounts: BTreeMap::new() },
+		ethereum: EthereumConfig {},
 	}
 }
@@ -1,4 +1,5 @@
 use sc_cli::RunCmd;
+use std::{path::PathBuf, str::FromStr};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -8,6 +9,54 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Replace BABE/GRANDPA with manual sealing: `instant`, `manual` or an interval in
+	/// milliseconds. Passing `--sealing` without a value selects `instant`.
+	#[structopt(long)]
+	pub sealing: Option<Option<Sealing>>,
+
+	/// Index finalized blocks into the SQLite database at this path and serve `indexer_*` RPCs.
+	#[structopt(long, parse(from_os_str))]
+	pub indexer_db: Option<PathBuf>,
+
+	/// File holding the token to send to the signing service at `--keystore-uri`.
+	#[structopt(long, parse(from_os_str))]
+	pub keystore_token_file: Option<PathBuf>,
+}
+
+impl Cli {
+	/// The sealing mode requested on the command line, if any.
+	pub fn sealing(&self) -> Option<Sealing> {
+		self.sealing.map(|sealing| sealing.unwrap_or(Sealing::Instant))
+	}
+}
+
+/// How blocks are authored when the node runs with `--sealing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal a block as soon as a transaction enters the pool.
+	Instant,
+	/// Seal blocks only when requested through the `engine_createBlock` RPC.
+	Manual,
+	/// Seal a block every given number of milliseconds, even if it is empty.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(match s {
+			"instant" => Self::Instant,
+			"manual" => Self::Manual,
+			millis => Self::Interval(millis.parse().map_err(|_| {
+				format!(
+					"Invalid sealing mode `{}`: expected `instant`, `manual` or milliseconds.",
+					millis
+				)
+			})?),
+		})
+	}
 }
 
 #[derive(Debug, StructOpt)]
@@ -17,6 +66,9 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate plain and raw chain specifications from a genesis file.
+	GenerateSpec(crate::genesis::GenerateSpecCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -26,6 +78,9 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Turn a chain spec written by `export-state` into a local development chain.
+	ForkOff(crate::fork_off::ForkOffCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -0,0 +1,700 @@
+//! Decoding of runtime events and calls with names taken from the runtime metadata.
+//!
+//! The `System::Events` storage value of a block is walked with the pallet names, event names and
+//! argument types found in the metadata of the runtime that emitted it, so consumers see readable
+//! JSON instead of SCALE bytes, and blocks of earlier runtimes decode with their own metadata.
+//! Argument type names are parsed into decoders once per runtime. An argument of a type this
+//! module does not know can't be skipped, as its size is unknown, so the events of its block fail
+//! to decode with an error naming the type.
+
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	sync::{Arc, Mutex},
+};
+
+use codec::{Compact, Decode};
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use frame_system::Phase;
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash, ProxyType};
+use pallet_evm::ExitReason;
+use sc_client_api::{Backend, StateBackend};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, hexdisplay::HexDisplay, U256};
+use sp_runtime::{generic::BlockId, DispatchError, FixedI128, FixedPointNumber, FixedU128};
+
+/// The phase of block execution an event was emitted in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventPhase {
+	/// Emitted while applying the extrinsic at the given index.
+	ApplyExtrinsic(u32),
+	/// Emitted while finalizing the block.
+	Finalization,
+	/// Emitted while initializing the block.
+	Initialization,
+}
+
+impl From<Phase> for EventPhase {
+	fn from(phase: Phase) -> Self {
+		match phase {
+			Phase::ApplyExtrinsic(index) => Self::ApplyExtrinsic(index),
+			Phase::Finalization => Self::Finalization,
+			Phase::Initialization => Self::Initialization,
+		}
+	}
+}
+
+/// A decoded event argument.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventArg {
+	/// The argument type as named in the runtime metadata.
+	#[serde(rename = "type")]
+	pub type_name: String,
+	/// The decoded value.
+	pub value: Value,
+}
+
+/// A runtime event with names resolved from the metadata.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedEvent {
+	/// When the event was emitted.
+	pub phase: EventPhase,
+	/// Name of the emitting pallet.
+	pub pallet: String,
+	/// Name of the event.
+	pub event: String,
+	/// Decoded arguments, in declaration order.
+	pub args: Vec<EventArg>,
+	/// Accounts appearing among the arguments.
+	pub accounts: Vec<AccountId>,
+	/// Topics the event was deposited with.
+	pub topics: Vec<Hash>,
+}
+
+/// The events of one block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockEvents {
+	/// Hash of the block.
+	pub block_hash: Hash,
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Events of the block.
+	pub events: Vec<DecodedEvent>,
+}
+
+/// How an event argument of one metadata type is decoded and rendered.
+#[derive(Clone)]
+enum ArgType {
+	/// An account, rendered as an SS58 address and listed among the accounts of the event.
+	AccountId,
+	Bool,
+	U8,
+	U16,
+	U32,
+	U64,
+	/// Rendered as a string, as it does not fit in a JSON number.
+	U128,
+	/// A byte array of the given length, rendered as hex.
+	Bytes(usize),
+	/// A vector of bytes, rendered as hex.
+	ByteVec,
+	Vec(Box<ArgType>),
+	/// Rendered as the value or `null`.
+	Option(Box<ArgType>),
+	/// A tuple or array, rendered as an array.
+	Tuple(Vec<ArgType>),
+	/// A struct, rendered as an object with the given field names.
+	Struct(Vec<(&'static str, ArgType)>),
+	/// An enum without fields, rendered as the name of its variant.
+	Enum(&'static [&'static str]),
+	/// Rendered as a decimal string, exactly.
+	FixedU128,
+	/// Rendered as a decimal string, exactly.
+	FixedI128,
+	/// Rendered with module errors resolved to pallet and error names.
+	DispatchError,
+	/// Rendered as `{"ok": null}` or `{"err": <dispatch error>}`.
+	DispatchResult,
+	/// A type decoded and rendered by the given function.
+	Custom(fn(&mut &[u8]) -> Option<Value>),
+}
+
+impl ArgType {
+	/// Parse a type name of the metadata, or `None` if the type is not known.
+	///
+	/// The metadata names types as written in the pallets, so aliases such as `MarketId` are
+	/// resolved by name, while vectors, options, tuples and arrays are parsed into their items.
+	fn parse(name: &str) -> Option<Self> {
+		let name = name.trim();
+		let name = name
+			.strip_prefix("<T as frame_system::Config>::")
+			.or_else(|| name.strip_prefix("T::"))
+			.unwrap_or(name);
+
+		if let Some(item) = generic(name, "Vec") {
+			return match Self::parse(item)? {
+				Self::U8 => Some(Self::ByteVec),
+				item => Some(Self::Vec(Box::new(item))),
+			}
+		}
+		if let Some(item) = generic(name, "Option") {
+			return Some(Self::Option(Box::new(Self::parse(item)?)))
+		}
+		if let Some(items) = name.strip_prefix('(').and_then(|name| name.strip_suffix(')')) {
+			let items = split_types(items).into_iter().map(Self::parse).collect::<Option<_>>()?;
+			return Some(Self::Tuple(items))
+		}
+		if let Some(array) = name.strip_prefix('[').and_then(|name| name.strip_suffix(']')) {
+			let (item, len) = array.split_once(';')?;
+			let len = len.trim().parse().ok()?;
+			return match Self::parse(item)? {
+				Self::U8 => Some(Self::Bytes(len)),
+				item => Some(Self::Tuple(vec![item; len])),
+			}
+		}
+
+		let arg_type = match name {
+			"AccountId" => Self::AccountId,
+			"bool" => Self::Bool,
+			// Rendered as a number of percent.
+			"u8" | "Percent" => Self::U8,
+			"u16" | "Outcome" => Self::U16,
+			"u32" | "BlockNumber" | "Index" | "RegistrarIndex" | "CollectionId" | "ItemId" |
+			"AssetId" | "AssetIdOf<T>" | "MarketId" | "SeriesId" | "AirdropId" => Self::U32,
+			"u64" | "Moment" | "Weight" | "DepositNonce" | "OfferId" => Self::U64,
+			"u128" | "Balance" | "BalanceOf<T>" => Self::U128,
+			"Hash" | "H256" | "CallHash" => Self::Bytes(32),
+			"H160" | "ExternalAddress" => Self::Bytes(20),
+			"U256" =>
+				Self::Custom(|input| Some(Value::String(U256::decode(input).ok()?.to_string()))),
+			"FixedU128" => Self::FixedU128,
+			"FixedI128" => Self::FixedI128,
+			"OptionKind" => Self::Custom(option_kind),
+			"Side" => Self::Enum(&["Long", "Short"]),
+			"Status" | "BalanceStatus" => Self::Enum(&["Free", "Reserved"]),
+			"ProxyType" => Self::Custom(debug::<ProxyType>),
+			"ExitReason" => Self::Custom(debug::<ExitReason>),
+			"DispatchError" => Self::DispatchError,
+			"DispatchResult" => Self::DispatchResult,
+			"DispatchInfo" => Self::Struct(vec![
+				("weight", Self::U64),
+				("class", Self::Enum(&["Normal", "Operational", "Mandatory"])),
+				("paysFee", Self::Enum(&["Yes", "No"])),
+			]),
+			"Timepoint<BlockNumber>" | "Timepoint<T::BlockNumber>" =>
+				Self::Struct(vec![("height", Self::U32), ("index", Self::U32)]),
+			"TaskAddress<BlockNumber>" | "TaskAddress<T::BlockNumber>" =>
+				Self::Tuple(vec![Self::U32, Self::U32]),
+			"AuthorityList" => Self::parse("Vec<([u8; 32], u64)>")?,
+			"Log" => Self::Struct(vec![
+				("address", Self::Bytes(20)),
+				("topics", Self::Vec(Box::new(Self::Bytes(32)))),
+				("data", Self::ByteVec),
+			]),
+			_ => return None,
+		};
+		Some(arg_type)
+	}
+
+	/// Decode a value of this type, or `None` if the input does not encode one.
+	fn decode(
+		&self,
+		metadata: &DecodingMetadata,
+		input: &mut &[u8],
+		accounts: &mut Vec<AccountId>,
+	) -> Option<Value> {
+		let value = match self {
+			Self::AccountId => {
+				let who = AccountId::decode(input).ok()?;
+				let value = Value::String(who.to_ss58check());
+				accounts.push(who);
+				value
+			},
+			Self::Bool => bool::decode(input).ok()?.into(),
+			Self::U8 => u8::decode(input).ok()?.into(),
+			Self::U16 => u16::decode(input).ok()?.into(),
+			Self::U32 => u32::decode(input).ok()?.into(),
+			Self::U64 => u64::decode(input).ok()?.into(),
+			Self::U128 => Value::String(u128::decode(input).ok()?.to_string()),
+			Self::Bytes(len) => {
+				let bytes: &[u8] = *input;
+				if bytes.len() < *len {
+					return None
+				}
+				let (bytes, rest) = bytes.split_at(*len);
+				*input = rest;
+				hex(bytes)
+			},
+			Self::ByteVec => hex(&Vec::<u8>::decode(input).ok()?),
+			Self::Vec(item) => {
+				let len = Compact::<u32>::decode(input).ok()?.0;
+				let items = (0..len).map(|_| item.decode(metadata, input, accounts));
+				Value::Array(items.collect::<Option<_>>()?)
+			},
+			Self::Option(item) => match u8::decode(input).ok()? {
+				0 => Value::Null,
+				1 => item.decode(metadata, input, accounts)?,
+				_ => return None,
+			},
+			Self::Tuple(items) => {
+				let items = items.iter().map(|item| item.decode(metadata, input, accounts));
+				Value::Array(items.collect::<Option<_>>()?)
+			},
+			Self::Struct(fields) => {
+				let mut object = Map::new();
+				for (name, field) in fields {
+					object.insert(name.to_string(), field.decode(metadata, input, accounts)?);
+				}
+				Value::Object(object)
+			},
+			Self::Enum(variants) =>
+				Value::String(variants.get(u8::decode(input).ok()? as usize)?.to_string()),
+			Self::FixedU128 => fixed_u128(FixedU128::decode(input).ok()?),
+			Self::FixedI128 => {
+				let inner = FixedI128::decode(input).ok()?.into_inner();
+				let (sign, div) = (if inner < 0 { "-" } else { "" }, FixedI128::DIV);
+				let (whole, fraction) = ((inner / div).abs(), (inner % div).abs());
+				Value::String(format!("{}{}.{:018}", sign, whole, fraction))
+			},
+			Self::DispatchError => metadata.dispatch_error(DispatchError::decode(input).ok()?),
+			Self::DispatchResult => match Result::<(), DispatchError>::decode(input).ok()? {
+				Ok(()) => json!({ "ok": null }),
+				Err(error) => json!({ "err": metadata.dispatch_error(error) }),
+			},
+			Self::Custom(decode) => decode(input)?,
+		};
+		Some(value)
+	}
+}
+
+/// The parameter of `name` if it is the generic type `outer<parameter>`.
+fn generic<'a>(name: &'a str, outer: &str) -> Option<&'a str> {
+	name.strip_prefix(outer)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// Split a list of type names at the commas outside of brackets.
+fn split_types(list: &str) -> Vec<&str> {
+	let (mut names, mut depth, mut start) = (Vec::new(), 0, 0);
+	for (index, c) in list.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				names.push(&list[start..index]);
+				start = index + 1;
+			},
+			_ => {},
+		}
+	}
+	names.push(&list[start..]);
+	names.into_iter().map(str::trim).filter(|name| !name.is_empty()).collect()
+}
+
+fn hex(bytes: &[u8]) -> Value {
+	Value::String(format!("0x{}", HexDisplay::from(&bytes)))
+}
+
+/// Decode a type of the runtime with its own codec, rendered as its debug representation.
+fn debug<T: Decode + Debug>(input: &mut &[u8]) -> Option<Value> {
+	Some(Value::String(format!("{:?}", T::decode(input).ok()?)))
+}
+
+fn fixed_u128(value: FixedU128) -> Value {
+	let (inner, div) = (value.into_inner(), FixedU128::DIV);
+	Value::String(format!("{}.{:018}", inner / div, inner % div))
+}
+
+/// Decode the kind of an option series, a call with its cap or a put.
+fn option_kind(input: &mut &[u8]) -> Option<Value> {
+	match u8::decode(input).ok()? {
+		0 => Some(json!({ "Call": { "cap": fixed_u128(FixedU128::decode(input).ok()?) } })),
+		1 => Some(json!("Put")),
+		_ => None,
+	}
+}
+
+/// An event with its argument types, parsed into decoders unless they are not known.
+struct EventMetadata {
+	name: String,
+	args: Vec<(String, Option<ArgType>)>,
+}
+
+/// Names of the calls, events and errors of one pallet, with the argument types of events.
+struct PalletMetadata {
+	name: String,
+	calls: Vec<String>,
+	events: Vec<EventMetadata>,
+	errors: Vec<String>,
+}
+
+/// The parts of the runtime metadata needed to decode events and name calls.
+pub struct DecodingMetadata {
+	pallets: HashMap<u8, PalletMetadata>,
+}
+
+fn decoded<B: 'static, O: 'static>(value: DecodeDifferent<B, O>) -> Result<O, String> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err("Metadata is not in decoded form.".into()),
+	}
+}
+
+impl DecodingMetadata {
+	fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+			.map_err(|e| format!("Invalid runtime metadata: {}", e))?;
+		let modules = match metadata.1 {
+			RuntimeMetadata::V13(metadata) => decoded(metadata.modules)?,
+			_ => return Err("Unsupported runtime metadata version.".into()),
+		};
+
+		let mut pallets = HashMap::new();
+		for module in modules {
+			let calls = match module.calls {
+				Some(calls) => decoded(calls)?
+					.into_iter()
+					.map(|call| decoded(call.name))
+					.collect::<Result<_, _>>()?,
+				None => Vec::new(),
+			};
+			let events = match module.event {
+				Some(events) => decoded(events)?
+					.into_iter()
+					.map(|event| {
+						let args = decoded(event.arguments)?
+							.into_iter()
+							.map(|name| {
+								let arg_type = ArgType::parse(&name);
+								(name, arg_type)
+							})
+							.collect();
+						Ok(EventMetadata { name: decoded(event.name)?, args })
+					})
+					.collect::<Result<_, String>>()?,
+				None => Vec::new(),
+			};
+			let errors = decoded(module.errors)?
+				.into_iter()
+				.map(|error| decoded(error.name))
+				.collect::<Result<_, _>>()?;
+
+			let name = decoded(module.name)?;
+			pallets.insert(module.index, PalletMetadata { name, calls, events, errors });
+		}
+
+		Ok(Self { pallets })
+	}
+
+	/// Pallet and call name of an encoded call, which starts with both indices.
+	pub fn call_name(&self, encoded_call: &[u8]) -> Option<(&str, &str)> {
+		let pallet = self.pallets.get(encoded_call.get(0)?)?;
+		let call = pallet.calls.get(*encoded_call.get(1)? as usize)?;
+		Some((&pallet.name, call))
+	}
+
+	/// Decode the encoded `System::Events` storage value, a vector of event records.
+	pub fn decode_events(&self, bytes: &[u8]) -> Result<Vec<DecodedEvent>, String> {
+		let input = &mut &bytes[..];
+		let count = Compact::<u32>::decode(input).map_err(|e| e.to_string())?.0;
+		let mut events = Vec::new();
+		for index in 0..count {
+			let event = self.decode_record(input).map_err(|e| format!("Event {}: {}", index, e))?;
+			events.push(event);
+		}
+		if !input.is_empty() {
+			return Err(format!("{} bytes left after the last event.", input.len()))
+		}
+		Ok(events)
+	}
+
+	/// Decode one event record, naming pallet, event and argument types from the metadata.
+	fn decode_record(&self, input: &mut &[u8]) -> Result<DecodedEvent, String> {
+		let phase = Phase::decode(input).map_err(|e| e.to_string())?;
+		let (pallet_index, event_index) = <(u8, u8)>::decode(input).map_err(|e| e.to_string())?;
+		let pallet = self
+			.pallets
+			.get(&pallet_index)
+			.ok_or_else(|| format!("No pallet with index {} in metadata.", pallet_index))?;
+		let event = pallet.events.get(event_index as usize).ok_or_else(|| {
+			format!("No event with index {} in pallet {}.", event_index, pallet.name)
+		})?;
+
+		let mut args = Vec::with_capacity(event.args.len());
+		let mut accounts = Vec::new();
+		for (type_name, arg_type) in &event.args {
+			let arg_type = arg_type.as_ref().ok_or_else(|| {
+				format!("Unknown argument type {} of {}::{}.", type_name, pallet.name, event.name)
+			})?;
+			let value = arg_type.decode(self, input, &mut accounts).ok_or_else(|| {
+				format!("Invalid {} argument of {}::{}.", type_name, pallet.name, event.name)
+			})?;
+			args.push(EventArg { type_name: type_name.clone(), value });
+		}
+		let topics = Vec::<Hash>::decode(input).map_err(|e| e.to_string())?;
+
+		Ok(DecodedEvent {
+			phase: phase.into(),
+			pallet: pallet.name.clone(),
+			event: event.name.clone(),
+			args,
+			accounts,
+			topics,
+		})
+	}
+
+	/// Render a dispatch error, resolving module errors to pallet and error names.
+	fn dispatch_error(&self, error: DispatchError) -> Value {
+		match error {
+			DispatchError::Module { index, error, .. } => {
+				let pallet = self.pallets.get(&index);
+				let name = pallet.and_then(|pallet| pallet.errors.get(error as usize).cloned());
+				json!({
+					"module": {
+						"pallet": pallet.map(|pallet| pallet.name.clone()),
+						"error": name,
+						"index": index,
+						"errorIndex": error,
+					}
+				})
+			},
+			other => Value::String(format!("{:?}", other)),
+		}
+	}
+}
+
+/// Storage key of `System::Events`.
+fn events_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"Events")].concat()
+}
+
+/// Reads and decodes the events of canonical blocks, caching metadata per runtime version.
+pub struct BlockDecoder<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	metadata: Mutex<Option<(u32, Arc<DecodingMetadata>)>>,
+}
+
+impl<C, B> BlockDecoder<C, B> {
+	/// Create a decoder reading state from `backend`.
+	pub fn new(client: Arc<C>, backend: Arc<B>) -> Self {
+		Self { client, backend, metadata: Mutex::new(None) }
+	}
+
+	/// The client blocks are read from.
+	pub fn client(&self) -> &Arc<C> {
+		&self.client
+	}
+}
+
+impl<C, B> BlockDecoder<C, B>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	/// The decoding metadata of the runtime at the given block.
+	pub fn metadata_at(&self, at: &BlockId<Block>) -> Result<Arc<DecodingMetadata>, String> {
+		let api = self.client.runtime_api();
+		let spec_version = api.version(at).map_err(|e| e.to_string())?.spec_version;
+
+		let mut cache = self.metadata.lock().expect("Metadata cache lock poisoned");
+		match &*cache {
+			Some((version, metadata)) if *version == spec_version => Ok(metadata.clone()),
+			_ => {
+				let bytes = api.metadata(at).map_err(|e| e.to_string())?;
+				let metadata = Arc::new(DecodingMetadata::from_bytes(&bytes)?);
+				*cache = Some((spec_version, metadata.clone()));
+				Ok(metadata)
+			},
+		}
+	}
+
+	/// Raw storage value under `key` at the given block.
+	pub fn storage(&self, at: Hash, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+		self.backend
+			.state_at(BlockId::Hash(at))
+			.map_err(|e| e.to_string())?
+			.storage(key)
+			.map_err(|e| e.to_string())
+	}
+
+	/// Hash of the canonical block with the given number.
+	pub fn canonical_hash(&self, number: BlockNumber) -> Result<Hash, String> {
+		self.client
+			.hash(number)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block #{} not found.", number))
+	}
+
+	/// Decoded events of the canonical block with the given number.
+	pub fn block_events(&self, number: BlockNumber) -> Result<BlockEvents, String> {
+		let block_hash = self.canonical_hash(number)?;
+		let metadata = self.metadata_at(&BlockId::Hash(block_hash))?;
+		let events = match self.storage(block_hash, &events_key())? {
+			Some(bytes) => metadata.decode_events(&bytes),
+			None => Ok(Vec::new()),
+		}
+		.map_err(|e| format!("Failed to decode events of block #{}: {}", number, e))?;
+
+		Ok(BlockEvents { block_hash, block_number: number, events })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_support::weights::DispatchInfo;
+	use frame_system::EventRecord;
+	use node_template_runtime::{Event, Runtime};
+	use sp_core::H256;
+
+	fn metadata() -> DecodingMetadata {
+		DecodingMetadata::from_bytes(&Runtime::metadata().encode()).unwrap()
+	}
+
+	fn decode(type_name: &str, bytes: &[u8]) -> Option<(Value, Vec<AccountId>)> {
+		let mut accounts = Vec::new();
+		let value =
+			ArgType::parse(type_name)?.decode(&metadata(), &mut &bytes[..], &mut accounts)?;
+		Some((value, accounts))
+	}
+
+	#[test]
+	fn every_event_argument_type_of_the_runtime_is_known() {
+		let metadata = metadata();
+		let unknown: Vec<_> = metadata
+			.pallets
+			.values()
+			.flat_map(|pallet| pallet.events.iter())
+			.flat_map(|event| event.args.iter())
+			.filter(|(_, arg_type)| arg_type.is_none())
+			.map(|(type_name, _)| type_name.clone())
+			.collect();
+		assert!(unknown.is_empty(), "Unknown event argument types: {:?}", unknown);
+	}
+
+	#[test]
+	fn compound_types_are_parsed_into_their_items() {
+		let alice = AccountId::from([1; 32]);
+		let bytes = (vec![alice.clone()], Some(vec![1u8, 2]), (3u32, 4u32)).encode();
+		let (value, accounts) =
+			decode("(Vec<T::AccountId>, Option<Vec<u8>>, TaskAddress<BlockNumber>)", &bytes)
+				.unwrap();
+		assert_eq!(value, json!([[alice.to_ss58check()], "0x0102", [3, 4]]));
+		assert_eq!(accounts, vec![alice]);
+
+		let (value, _) = decode("[u8; 4]", &[0xde, 0xad, 0xbe, 0xef]).unwrap();
+		assert_eq!(value, json!("0xdeadbeef"));
+		let (value, _) = decode("Option<T::Balance>", &None::<u128>.encode()).unwrap();
+		assert_eq!(value, Value::Null);
+		let (value, _) = decode("u128", &u128::MAX.encode()).unwrap();
+		assert_eq!(value, json!(u128::MAX.to_string()));
+
+		assert!(ArgType::parse("Unknown").is_none());
+		assert!(ArgType::parse("Vec<Unknown>").is_none());
+		assert!(ArgType::parse("(u32, Unknown)").is_none());
+		// The input must hold the whole value.
+		assert!(decode("[u8; 4]", &[0xde, 0xad]).is_none());
+		assert!(decode("Side", &[2]).is_none());
+	}
+
+	#[test]
+	fn fixed_point_numbers_are_rendered_exactly() {
+		let price = FixedU128::saturating_from_rational(5, 4);
+		assert_eq!(decode("FixedU128", &price.encode()).unwrap().0, json!("1.250000000000000000"));
+		let rate = FixedI128::saturating_from_rational(-3, 2);
+		assert_eq!(decode("FixedI128", &rate.encode()).unwrap().0, json!("-1.500000000000000000"));
+		let call = (0u8, price).encode();
+		assert_eq!(
+			decode("OptionKind", &call).unwrap().0,
+			json!({ "Call": { "cap": "1.250000000000000000" } })
+		);
+		assert_eq!(decode("OptionKind", &[1]).unwrap().0, json!("Put"));
+	}
+
+	#[test]
+	fn events_are_decoded_from_raw_storage() {
+		let (alice, bob) = (AccountId::from([1; 32]), AccountId::from([2; 32]));
+		let records = vec![
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(1),
+				event: Event::Balances(pallet_balances::Event::Transfer(
+					alice.clone(),
+					bob.clone(),
+					5,
+				)),
+				topics: vec![],
+			},
+			EventRecord {
+				phase: Phase::Finalization,
+				event: Event::System(frame_system::Event::ExtrinsicFailed(
+					DispatchError::Module { index: 4, error: 2, message: None },
+					DispatchInfo::default(),
+				)),
+				topics: vec![H256::repeat_byte(7)],
+			},
+		];
+		let events = metadata().decode_events(&records.encode()).unwrap();
+
+		assert_eq!(events.len(), 2);
+		assert_eq!(events[0].phase, EventPhase::ApplyExtrinsic(1));
+		assert_eq!((&*events[0].pallet, &*events[0].event), ("Balances", "Transfer"));
+		let args: Vec<_> = events[0]
+			.args
+			.iter()
+			.map(|arg| (arg.type_name.as_str(), arg.value.clone()))
+			.collect();
+		assert_eq!(
+			args,
+			vec![
+				("AccountId", json!(alice.to_ss58check())),
+				("AccountId", json!(bob.to_ss58check())),
+				("Balance", json!("5")),
+			]
+		);
+		assert_eq!(events[0].accounts, vec![alice, bob]);
+
+		assert_eq!(events[1].phase, EventPhase::Finalization);
+		assert_eq!((&*events[1].pallet, &*events[1].event), ("System", "ExtrinsicFailed"));
+		assert_eq!(
+			events[1].args[0].value,
+			json!({ "module": {
+				"pallet": "Balances",
+				"error": "InsufficientBalance",
+				"index": 4,
+				"errorIndex": 2,
+			} })
+		);
+		assert_eq!(
+			events[1].args[1].value,
+			json!({ "weight": 0, "class": "Normal", "paysFee": "Yes" })
+		);
+		assert_eq!(events[1].topics, vec![H256::repeat_byte(7)]);
+	}
+
+	#[test]
+	fn malformed_events_are_rejected() {
+		let metadata = metadata();
+		let record = EventRecord {
+			phase: Phase::Initialization,
+			event: Event::System(frame_system::Event::CodeUpdated),
+			topics: vec![],
+		};
+		let bytes = vec![record].encode();
+		assert_eq!(metadata.decode_events(&bytes).unwrap().len(), 1);
+
+		assert!(metadata.decode_events(&bytes[..bytes.len() - 1]).is_err());
+		assert!(metadata.decode_events(&[bytes.clone(), vec![0]].concat()).is_err());
+		// An event index beyond the events of the pallet.
+		let mut unknown = bytes.clone();
+		unknown[3] = u8::MAX;
+		assert!(metadata.decode_events(&unknown).is_err());
+	}
+}
@@ -0,0 +1,264 @@
+//! `fork-off`: turn a state exported with `export-state` into a local development chain.
+//!
+//! The exported genesis keeps every balance and pallet's storage, but its authorities and sudo
+//! key belong to the live network. This command hands them to Alice, endows her so she can pay
+//! fees, and optionally swaps in a new runtime, so upgrades can be rehearsed against real state:
+//!
+//! ```bash
+//! node-template export-state --chain live.json > state.json
+//! node-template fork-off state.json --output fork.json --runtime new_runtime.wasm
+//! node-template --dev --tmp --chain fork.json
+//! ```
+
+use std::{fs, path::PathBuf};
+
+use codec::{Decode, Encode};
+use node_template_runtime::{AccountId, Balance, Index, BABE_GENESIS_EPOCH_CONFIG};
+use sc_service::ChainSpec as ChainSpecT;
+use serde_json::Value;
+use sp_core::{
+	hashing::{blake2_128, twox_128},
+	sr25519,
+	storage::{well_known_keys, Storage},
+};
+use sp_finality_grandpa::{AuthorityList, GRANDPA_AUTHORITIES_KEY};
+use sp_runtime::BuildStorage;
+use structopt::StructOpt;
+
+use crate::chain_spec::{self, ChainSpec};
+
+/// Balance added to Alice's account, as on development chains.
+const ALICE_ENDOWMENT: Balance = 1 << 60;
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// The `fork-off` command.
+#[derive(Debug, StructOpt)]
+pub struct ForkOffCmd {
+	/// Chain spec written by `export-state`.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	/// Where to write the chain spec of the fork.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: PathBuf,
+
+	/// Replace the runtime with this Wasm blob.
+	#[structopt(long, parse(from_os_str))]
+	pub runtime: Option<PathBuf>,
+}
+
+impl ForkOffCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), String> {
+		let spec = ChainSpec::from_json_file(self.input.clone())?;
+		let runtime = match &self.runtime {
+			Some(path) => Some(
+				fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+			),
+			None => None,
+		};
+
+		let json = fork(spec, runtime)?;
+		fs::write(&self.output, json)
+			.map_err(|e| format!("Failed to write {}: {}", self.output.display(), e))
+	}
+}
+
+/// The chain spec, as JSON, of a fork of `spec` with `runtime` as its code if given.
+fn fork(mut spec: ChainSpec, runtime: Option<Vec<u8>>) -> Result<String, String> {
+	let mut storage = spec.build_storage()?;
+
+	fork_off(&mut storage)?;
+	if let Some(code) = runtime {
+		storage.top.insert(well_known_keys::CODE.to_vec(), code);
+	}
+	spec.set_storage(storage);
+
+	// Give the fork its own identity, so it can't be mistaken for or connect to the network.
+	let mut json: Value = serde_json::from_str(&spec.as_json(true)?)
+		.map_err(|e| format!("Invalid chain spec: {}", e))?;
+	json["name"] = format!("{} Fork", spec.name()).into();
+	json["id"] = format!("{}_fork", spec.id()).into();
+	json["chainType"] = "Development".into();
+	json["bootNodes"] = Value::Array(Vec::new());
+	json["telemetryEndpoints"] = Value::Null;
+	json["protocolId"] = Value::Null;
+
+	serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+}
+
+fn storage_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+/// The key of the `System::Account` entry of `who`.
+fn account_key(who: &AccountId) -> Vec<u8> {
+	[storage_key("System", "Account"), blake2_128(who.as_ref()).to_vec(), who.encode()].concat()
+}
+
+/// Make Alice the only authority and the sudo key, and endow her.
+fn fork_off(storage: &mut Storage) -> Result<(), String> {
+	let (babe, grandpa) = chain_spec::authority_keys_from_seed("Alice");
+	let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+	let top = &mut storage.top;
+
+	// Drop the epochs and randomness of the live network, so the fork's first block starts BABE's
+	// genesis epoch again, as the client expects of a new chain.
+	let babe_prefix = twox_128(b"Babe");
+	top.retain(|key, _| !key.starts_with(&babe_prefix));
+	let babe_authorities = vec![(babe, 1u64)];
+	top.insert(storage_key("Babe", "Authorities"), babe_authorities.encode());
+	top.insert(storage_key("Babe", "NextAuthorities"), babe_authorities.encode());
+	top.insert(storage_key("Babe", "EpochConfig"), BABE_GENESIS_EPOCH_CONFIG.encode());
+
+	let authorities: AuthorityList = vec![(grandpa, 1)];
+	// `VersionedAuthorityList` encodes as a version byte followed by the list.
+	top.insert(GRANDPA_AUTHORITIES_KEY.to_vec(), (1u8, authorities).encode());
+	top.insert(storage_key("Grandpa", "CurrentSetId"), 0u64.encode());
+	top.remove(&storage_key("Grandpa", "PendingChange"));
+	top.remove(&storage_key("Grandpa", "Stalled"));
+
+	top.insert(storage_key("Sudo", "Key"), alice.encode());
+
+	let account_key = account_key(&alice);
+	let mut account = match top.get(&account_key) {
+		Some(encoded) => AccountInfo::decode(&mut &encoded[..])
+			.map_err(|e| format!("Invalid account info for Alice: {}", e))?,
+		None => AccountInfo { providers: 1, ..Default::default() },
+	};
+	account.data.free = account.data.free.saturating_add(ALICE_ENDOWMENT);
+	top.insert(account_key, account.encode());
+
+	let issuance_key = storage_key("Balances", "TotalIssuance");
+	let issuance = match top.get(&issuance_key) {
+		Some(encoded) => Balance::decode(&mut &encoded[..])
+			.map_err(|e| format!("Invalid total issuance: {}", e))?,
+		None => 0,
+	};
+	top.insert(issuance_key, issuance.saturating_add(ALICE_ENDOWMENT).encode());
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::ChainType;
+
+	// A live network's chain spec, whose raw genesis the tests fill in.
+	const LIVE: &str = r#"{
+		"name": "Live",
+		"id": "live",
+		"chainType": "Live",
+		"bootNodes": [
+			"/dns/boot.example.com/tcp/30333/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp"
+		],
+		"telemetryEndpoints": [["wss://telemetry.polkadot.io/submit/", 0]],
+		"protocolId": "live",
+		"properties": null,
+		"genesis": { "raw": { "top": {}, "childrenDefault": {} } }
+	}"#;
+
+	const ISSUANCE: Balance = 1_000_000;
+
+	fn live_spec() -> ChainSpec {
+		let bob = chain_spec::get_account_id_from_seed::<sr25519::Public>("Bob");
+		let (babe, grandpa) = chain_spec::authority_keys_from_seed("Bob");
+		let mut storage = Storage::default();
+		let top = &mut storage.top;
+		top.insert(storage_key("Babe", "Authorities"), vec![(babe, 1u64)].encode());
+		top.insert(storage_key("Babe", "EpochIndex"), 42u64.encode());
+		top.insert(storage_key("Babe", "Randomness"), [7u8; 32].encode());
+		top.insert(GRANDPA_AUTHORITIES_KEY.to_vec(), (1u8, vec![(grandpa, 1u64)]).encode());
+		top.insert(storage_key("Grandpa", "CurrentSetId"), 9u64.encode());
+		top.insert(storage_key("Sudo", "Key"), bob.encode());
+		top.insert(storage_key("Balances", "TotalIssuance"), ISSUANCE.encode());
+		top.insert(storage_key("Timestamp", "Now"), 1_000u64.encode());
+		top.insert(well_known_keys::CODE.to_vec(), vec![1, 2, 3]);
+
+		let mut spec = ChainSpec::from_json_bytes(LIVE.as_bytes().to_vec()).unwrap();
+		spec.set_storage(storage);
+		spec
+	}
+
+	fn fork_of(runtime: Option<Vec<u8>>) -> (ChainSpec, Storage) {
+		let json = fork(live_spec(), runtime).unwrap();
+		let spec = ChainSpec::from_json_bytes(json.into_bytes()).unwrap();
+		let storage = spec.build_storage().unwrap();
+		(spec, storage)
+	}
+
+	#[test]
+	fn babe_state_is_wiped_and_restarted_for_alice() {
+		let (_, storage) = fork_of(None);
+		let (babe, _) = chain_spec::authority_keys_from_seed("Alice");
+
+		let babe_keys: Vec<_> =
+			storage.top.keys().filter(|key| key.starts_with(&twox_128(b"Babe"))).collect();
+		assert_eq!(babe_keys.len(), 3);
+		assert_eq!(storage.top.get(&storage_key("Babe", "EpochIndex")), None);
+		assert_eq!(storage.top.get(&storage_key("Babe", "Randomness")), None);
+		assert_eq!(
+			storage.top[&storage_key("Babe", "Authorities")],
+			vec![(babe.clone(), 1u64)].encode()
+		);
+		assert_eq!(
+			storage.top[&storage_key("Babe", "NextAuthorities")],
+			vec![(babe, 1u64)].encode()
+		);
+		assert_eq!(
+			storage.top[&storage_key("Babe", "EpochConfig")],
+			BABE_GENESIS_EPOCH_CONFIG.encode()
+		);
+	}
+
+	#[test]
+	fn alice_replaces_the_authorities_and_sudo_key() {
+		let (_, storage) = fork_of(None);
+		let (_, grandpa) = chain_spec::authority_keys_from_seed("Alice");
+		let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+
+		let authorities: AuthorityList = vec![(grandpa, 1)];
+		assert_eq!(storage.top[GRANDPA_AUTHORITIES_KEY], (1u8, authorities).encode());
+		assert_eq!(storage.top[&storage_key("Grandpa", "CurrentSetId")], 0u64.encode());
+		assert_eq!(storage.top[&storage_key("Sudo", "Key")], alice.encode());
+		// Other pallets' storage is kept.
+		assert_eq!(storage.top[&storage_key("Timestamp", "Now")], 1_000u64.encode());
+	}
+
+	#[test]
+	fn alice_is_endowed_and_the_total_issuance_follows() {
+		let (_, storage) = fork_of(None);
+		let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+
+		let account = AccountInfo::decode(&mut &storage.top[&account_key(&alice)][..]).unwrap();
+		assert_eq!(account.data.free, ALICE_ENDOWMENT);
+		assert_eq!(account.providers, 1);
+		assert_eq!(
+			storage.top[&storage_key("Balances", "TotalIssuance")],
+			(ISSUANCE + ALICE_ENDOWMENT).encode()
+		);
+	}
+
+	#[test]
+	fn the_fork_is_renamed_and_cut_off_from_the_network() {
+		let (spec, _) = fork_of(None);
+
+		assert_eq!(spec.name(), "Live Fork");
+		assert_eq!(spec.id(), "live_fork");
+		assert_eq!(spec.chain_type(), ChainType::Development);
+		assert!(spec.boot_nodes().is_empty());
+		assert!(spec.telemetry_endpoints().is_none());
+		assert_eq!(spec.protocol_id(), None);
+	}
+
+	#[test]
+	fn the_runtime_is_replaced_if_given() {
+		let (_, storage) = fork_of(None);
+		assert_eq!(storage.top[well_known_keys::CODE], vec![1, 2, 3]);
+
+		let (_, storage) = fork_of(Some(vec![4, 5]));
+		assert_eq!(storage.top[well_known_keys::CODE], vec![4, 5]);
+	}
+}
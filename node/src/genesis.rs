@@ -0,0 +1,483 @@
+//! `generate-spec`: build chain specs from a declarative genesis file.
+//!
+//! The file lists the accounts, authorities and token properties of a network, in TOML or, with a
+//! `.json` extension, JSON:
+//!
+//! ```toml
+//! name = "Ops Testnet"
+//! id = "ops_testnet"
+//! chain_type = "Live"
+//! protocol_id = "ops"
+//! sudo = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//!
+//! [properties]
+//! token_symbol = "DEFI"
+//! token_decimals = 12
+//! ss58_format = 42
+//!
+//! [[authorities]]
+//! babe = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! grandpa = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+//!
+//! [[accounts]]
+//! account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! balance = "1000000000000000"
+//!
+//! # Merged into the genesis config of the named pallet, after the sections above. Here Alice's
+//! # balance vests over 1000 blocks from block 100, apart from 100 units.
+//! [pallets.vesting]
+//! vesting = [["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", 100, 1000, 100000000000000]]
+//!
+//! # Chain spec extensions, with their JSON field names.
+//! [extensions]
+//! slotDuration = 2000
+//! ```
+//!
+//! Keys and accounts are given in SS58 or as `0x`-prefixed hex.
+
+use std::{
+	collections::BTreeSet,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use frame_support::traits::Get;
+use node_template_runtime::{AccountId, Balance, ExistentialDeposit, SS58Prefix, WASM_BINARY};
+use sc_service::{ChainSpec as ChainSpecT, ChainType, Properties};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use structopt::StructOpt;
+
+use crate::chain_spec::{self, ChainSpec, Extensions};
+
+/// The `generate-spec` command.
+#[derive(Debug, StructOpt)]
+pub struct GenerateSpecCmd {
+	/// The genesis file, in TOML or, with a `.json` extension, JSON.
+	#[structopt(long, parse(from_os_str))]
+	pub from: PathBuf,
+
+	/// Directory to write `<id>.json` and `<id>-raw.json` to.
+	#[structopt(long, parse(from_os_str), default_value = ".")]
+	pub output_dir: PathBuf,
+}
+
+/// Contents of a genesis file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GenesisFile {
+	name: String,
+	id: String,
+	#[serde(default = "default_chain_type")]
+	chain_type: ChainType,
+	protocol_id: Option<String>,
+	#[serde(default)]
+	boot_nodes: Vec<String>,
+	sudo: String,
+	#[serde(default)]
+	properties: TokenProperties,
+	authorities: Vec<Authority>,
+	#[serde(default)]
+	accounts: Vec<Account>,
+	#[serde(default)]
+	pallets: Map<String, Value>,
+	#[serde(default)]
+	extensions: Extensions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenProperties {
+	token_symbol: Option<String>,
+	token_decimals: Option<u8>,
+	ss58_format: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Authority {
+	babe: String,
+	grandpa: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Account {
+	account: String,
+	#[serde(deserialize_with = "deserialize_balance")]
+	balance: Balance,
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+/// TOML integers don't reach `u128`, so balances may also be given as strings.
+fn deserialize_balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Amount {
+		Number(u64),
+		Text(String),
+	}
+
+	match Amount::deserialize(deserializer)? {
+		Amount::Number(number) => Ok(number.into()),
+		Amount::Text(text) => text.parse().map_err(serde::de::Error::custom),
+	}
+}
+
+impl GenerateSpecCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), String> {
+		let file = read_genesis_file(&self.from)?;
+		let id = file.id.clone();
+		let spec = build_spec(file)?;
+
+		let plain = spec.as_json(false)?;
+		let raw = chain_spec::apply_extensions(spec)?.as_json(true)?;
+
+		let outputs = vec![(format!("{}.json", id), plain), (format!("{}-raw.json", id), raw)];
+		for (name, json) in outputs {
+			let path = self.output_dir.join(name);
+			fs::write(&path, json)
+				.map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+			println!("Wrote {}", path.display());
+		}
+
+		Ok(())
+	}
+}
+
+fn read_genesis_file(path: &Path) -> Result<GenesisFile, String> {
+	let contents = fs::read_to_string(path)
+		.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+	if path.extension().map_or(false, |extension| extension == "json") {
+		serde_json::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+	} else {
+		toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+	}
+}
+
+fn build_spec(file: GenesisFile) -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+	if file.name.is_empty() || file.id.is_empty() {
+		return Err("`name` and `id` must not be empty.".into())
+	}
+
+	let sudo = chain_spec::account_from_str(&file.sudo)?;
+	let authorities = parse_authorities(&file.authorities)?;
+	let balances = parse_balances(&file.accounts)?;
+	let properties = token_properties(&file.properties)?;
+
+	let mut genesis =
+		serde_json::to_value(chain_spec::genesis_config(wasm_binary, authorities, sudo, balances))
+			.map_err(|e| e.to_string())?;
+	for (pallet, section) in file.pallets {
+		let target = genesis
+			.get_mut(&pallet)
+			.ok_or_else(|| format!("Unknown pallet genesis section `{}`.", pallet))?;
+		merge(target, section);
+	}
+	let genesis: node_template_runtime::GenesisConfig = serde_json::from_value(genesis)
+		.map_err(|e| format!("Invalid pallet genesis section: {}", e))?;
+
+	let spec = ChainSpec::from_genesis(
+		&file.name,
+		&file.id,
+		file.chain_type,
+		move || genesis.clone(),
+		file.boot_nodes
+			.iter()
+			.map(|node| node.parse().map_err(|e| format!("Invalid boot node `{}`: {}", node, e)))
+			.collect::<Result<_, _>>()?,
+		None,
+		file.protocol_id.as_deref(),
+		Some(properties),
+		file.extensions,
+	);
+	// Reject invalid extensions now rather than when the spec is loaded.
+	chain_spec::apply_extensions(spec.clone())?;
+
+	Ok(spec)
+}
+
+fn parse_authorities(authorities: &[Authority]) -> Result<Vec<(BabeId, GrandpaId)>, String> {
+	if authorities.is_empty() {
+		return Err("At least one authority is required.".into())
+	}
+
+	let mut babe_keys = BTreeSet::new();
+	let mut grandpa_keys = BTreeSet::new();
+	authorities
+		.iter()
+		.map(|authority| {
+			let babe = chain_spec::public_from_str::<BabeId>(&authority.babe)?;
+			let grandpa = chain_spec::public_from_str::<GrandpaId>(&authority.grandpa)?;
+			if !babe_keys.insert(babe.clone()) {
+				return Err(format!("BABE key {} is listed twice.", authority.babe))
+			}
+			if !grandpa_keys.insert(grandpa.clone()) {
+				return Err(format!("GRANDPA key {} is listed twice.", authority.grandpa))
+			}
+			Ok((babe, grandpa))
+		})
+		.collect()
+}
+
+fn parse_balances(accounts: &[Account]) -> Result<Vec<(AccountId, Balance)>, String> {
+	let existential_deposit = ExistentialDeposit::get();
+	let mut seen = BTreeSet::new();
+	let mut total_issuance: Balance = 0;
+
+	accounts
+		.iter()
+		.map(|Account { account, balance }| {
+			let id = chain_spec::account_from_str(account)?;
+			if !seen.insert(id.clone()) {
+				return Err(format!("Account {} is listed twice.", account))
+			}
+			if *balance < existential_deposit {
+				return Err(format!(
+					"Balance {} of account {} is below the existential deposit of {}.",
+					balance, account, existential_deposit
+				))
+			}
+			total_issuance = total_issuance
+				.checked_add(*balance)
+				.ok_or_else(|| "Total issuance overflows.".to_string())?;
+			Ok((id, *balance))
+		})
+		.collect()
+}
+
+fn token_properties(token: &TokenProperties) -> Result<Properties, String> {
+	let ss58_format = token.ss58_format.unwrap_or_else(SS58Prefix::get);
+	if ss58_format != SS58Prefix::get() {
+		return Err(format!(
+			"`ss58_format` {} doesn't match the runtime's `SS58Prefix` {}.",
+			ss58_format,
+			SS58Prefix::get()
+		))
+	}
+
+	let mut properties = Properties::new();
+	if let Some(symbol) = &token.token_symbol {
+		properties.insert("tokenSymbol".into(), symbol.as_str().into());
+	}
+	if let Some(decimals) = token.token_decimals {
+		properties.insert("tokenDecimals".into(), decimals.into());
+	}
+	properties.insert("ss58Format".into(), ss58_format.into());
+
+	Ok(properties)
+}
+
+/// Merge `patch` into `target`: objects are merged key by key, anything else replaces `target`.
+fn merge(target: &mut Value, patch: Value) {
+	match (target, patch) {
+		(Value::Object(target), Value::Object(patch)) =>
+			for (key, value) in patch {
+				merge(target.entry(key).or_insert(Value::Null), value);
+			},
+		(target, patch) => *target = patch,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+	const ALICE_ED25519: &str = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu";
+	const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+	const BOB_ED25519: &str = "5GoNkf6WdbxCFnPdAnYYQyCjAKPJgLNxXwPjwTh6DGg6gN3E";
+
+	// The example of the module documentation.
+	const EXAMPLE: &str = r#"
+		name = "Ops Testnet"
+		id = "ops_testnet"
+		chain_type = "Live"
+		protocol_id = "ops"
+		sudo = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+
+		[properties]
+		token_symbol = "DEFI"
+		token_decimals = 12
+		ss58_format = 42
+
+		[[authorities]]
+		babe = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+		grandpa = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+
+		[[accounts]]
+		account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+		balance = "1000000000000000"
+
+		[pallets.vesting]
+		vesting = [["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", 100, 1000, 100000000000000]]
+
+		[extensions]
+		slotDuration = 2000
+	"#;
+
+	fn authority(babe: &str, grandpa: &str) -> Authority {
+		Authority { babe: babe.into(), grandpa: grandpa.into() }
+	}
+
+	fn account(account: &str, balance: Balance) -> Account {
+		Account { account: account.into(), balance }
+	}
+
+	fn grandpa_hex(ss58: &str) -> String {
+		let public = chain_spec::public_from_str::<GrandpaId>(ss58).unwrap();
+		format!("{}", sp_core::hexdisplay::HexDisplay::from(&public.as_ref()))
+	}
+
+	#[test]
+	fn the_documented_example_builds() {
+		let file: GenesisFile = toml::from_str(EXAMPLE).unwrap();
+		let spec = build_spec(file).unwrap();
+		assert_eq!(spec.id(), "ops_testnet");
+
+		let genesis: Value = serde_json::from_str(&spec.as_json(false).unwrap()).unwrap();
+		let runtime = &genesis["genesis"]["runtime"];
+		assert_eq!(
+			runtime["vesting"]["vesting"],
+			json!([[ALICE, 100, 1000, 100_000_000_000_000u64]])
+		);
+		assert_eq!(runtime["sudo"]["key"], json!(ALICE));
+		assert!(spec.as_json(true).is_ok());
+	}
+
+	#[test]
+	fn unknown_pallet_sections_are_rejected() {
+		let mut file: GenesisFile = toml::from_str(EXAMPLE).unwrap();
+		file.pallets.insert("templateModule".into(), json!({}));
+		assert_eq!(
+			build_spec(file).unwrap_err(),
+			"Unknown pallet genesis section `templateModule`."
+		);
+	}
+
+	#[test]
+	fn authorities_are_parsed_and_unique() {
+		let authorities = parse_authorities(&[
+			authority(ALICE, ALICE_ED25519),
+			authority(BOB, &format!("0x{}", grandpa_hex(BOB_ED25519))),
+		])
+		.unwrap();
+		assert_eq!(authorities.len(), 2);
+		let bob = chain_spec::public_from_str::<GrandpaId>(BOB_ED25519).unwrap();
+		assert_eq!(authorities[1].1, bob);
+
+		assert_eq!(parse_authorities(&[]).unwrap_err(), "At least one authority is required.");
+		assert_eq!(
+			parse_authorities(&[authority(ALICE, ALICE_ED25519), authority(ALICE, BOB_ED25519)])
+				.unwrap_err(),
+			format!("BABE key {} is listed twice.", ALICE)
+		);
+		assert_eq!(
+			parse_authorities(&[authority(ALICE, ALICE_ED25519), authority(BOB, ALICE_ED25519)])
+				.unwrap_err(),
+			format!("GRANDPA key {} is listed twice.", ALICE_ED25519)
+		);
+		assert!(parse_authorities(&[authority("0x01", ALICE_ED25519)]).is_err());
+	}
+
+	#[test]
+	fn balances_are_parsed_and_checked() {
+		let ed = ExistentialDeposit::get();
+		let balances = parse_balances(&[account(ALICE, ed), account(BOB, 1_000)]).unwrap();
+		assert_eq!(
+			balances,
+			vec![
+				(chain_spec::account_from_str(ALICE).unwrap(), ed),
+				(chain_spec::account_from_str(BOB).unwrap(), 1_000),
+			]
+		);
+
+		assert_eq!(
+			parse_balances(&[account(ALICE, ed), account(ALICE, ed)]).unwrap_err(),
+			format!("Account {} is listed twice.", ALICE)
+		);
+		assert_eq!(
+			parse_balances(&[account(ALICE, ed - 1)]).unwrap_err(),
+			format!(
+				"Balance {} of account {} is below the existential deposit of {}.",
+				ed - 1,
+				ALICE,
+				ed
+			)
+		);
+		assert_eq!(
+			parse_balances(&[account(ALICE, Balance::MAX), account(BOB, ed)]).unwrap_err(),
+			"Total issuance overflows."
+		);
+	}
+
+	#[test]
+	fn balances_may_be_numbers_or_strings() {
+		let accounts: Vec<Account> = serde_json::from_value(json!([
+			{ "account": ALICE, "balance": 1_000 },
+			{ "account": BOB, "balance": u128::MAX.to_string() },
+		]))
+		.unwrap();
+		assert_eq!(accounts[0].balance, 1_000);
+		assert_eq!(accounts[1].balance, u128::MAX);
+	}
+
+	#[test]
+	fn token_properties_default_to_the_runtime_prefix() {
+		let properties = token_properties(&TokenProperties::default()).unwrap();
+		assert_eq!(Value::Object(properties), json!({ "ss58Format": SS58Prefix::get() }));
+
+		let token = TokenProperties {
+			token_symbol: Some("UNIT".into()),
+			token_decimals: Some(12),
+			ss58_format: Some(SS58Prefix::get()),
+		};
+		assert_eq!(
+			Value::Object(token_properties(&token).unwrap()),
+			json!({ "tokenSymbol": "UNIT", "tokenDecimals": 12, "ss58Format": SS58Prefix::get() })
+		);
+
+		let other = TokenProperties { ss58_format: Some(SS58Prefix::get() + 1), ..token };
+		assert_eq!(
+			token_properties(&other).unwrap_err(),
+			format!(
+				"`ss58_format` {} doesn't match the runtime's `SS58Prefix` {}.",
+				SS58Prefix::get() + 1,
+				SS58Prefix::get()
+			)
+		);
+	}
+
+	#[test]
+	fn merging_patches_objects_key_by_key() {
+		let mut target = json!({
+			"balances": { "balances": [[ALICE, 1]] },
+			"sudo": { "key": ALICE },
+		});
+		merge(
+			&mut target,
+			json!({
+				"balances": { "balances": [[BOB, 2]] },
+				"sudo": { "other": null },
+				"vesting": { "vesting": [] },
+			}),
+		);
+		assert_eq!(
+			target,
+			json!({
+				"balances": { "balances": [[BOB, 2]] },
+				"sudo": { "key": ALICE, "other": null },
+				"vesting": { "vesting": [] },
+			})
+		);
+	}
+}
@@ -0,0 +1,768 @@
+//! Optional block and event indexer backed by an embedded SQLite database.
+//!
+//! Started with `--indexer-db <path>`. Finalized blocks are indexed in order: their extrinsics,
+//! their events decoded as described in [`crate::decode`], and the free and reserved balance of
+//! every account they touch. Balances are read from the state of each block and its parent, so
+//! the indexer must keep up within the pruning window or run on an archive node.
+
+use std::{
+	collections::{BTreeSet, HashSet},
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use node_template_runtime::{
+	opaque::Block, AccountId, Balance, BlockNumber, Hash, Index, UncheckedExtrinsic,
+};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents};
+use serde::{Deserialize, Serialize};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	crypto::Ss58Codec,
+	hashing::{blake2_128, twox_128},
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Hash as HashT},
+	MultiAddress,
+};
+
+use crate::decode::{BlockDecoder, EventPhase};
+
+/// Page size used when a query does not specify one.
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+/// Largest page a query may request.
+pub const MAX_PAGE_SIZE: u32 = 100;
+/// The first block indexed. Genesis is not: it has no extrinsics or events, and its balances are
+/// set by the chain spec rather than changed.
+const FIRST_BLOCK: BlockNumber = 1;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+	CREATE TABLE IF NOT EXISTS blocks (number INTEGER PRIMARY KEY, hash TEXT NOT NULL);
+	CREATE TABLE IF NOT EXISTS extrinsics (
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		hash TEXT NOT NULL,
+		signer TEXT,
+		pallet TEXT,
+		call TEXT,
+		success INTEGER NOT NULL,
+		PRIMARY KEY (block_number, idx)
+	);
+	CREATE INDEX IF NOT EXISTS extrinsics_by_signer ON extrinsics (signer, block_number);
+	CREATE INDEX IF NOT EXISTS extrinsics_by_pallet ON extrinsics (pallet, block_number);
+	CREATE TABLE IF NOT EXISTS events (
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		pallet TEXT NOT NULL,
+		event TEXT NOT NULL,
+		args TEXT NOT NULL,
+		PRIMARY KEY (block_number, idx)
+	);
+	CREATE INDEX IF NOT EXISTS events_by_pallet ON events (pallet, block_number);
+	CREATE TABLE IF NOT EXISTS event_accounts (
+		block_number INTEGER NOT NULL,
+		event_idx INTEGER NOT NULL,
+		account TEXT NOT NULL,
+		PRIMARY KEY (block_number, event_idx, account)
+	);
+	CREATE INDEX IF NOT EXISTS event_accounts_by_account ON event_accounts (account, block_number);
+	CREATE TABLE IF NOT EXISTS balance_changes (
+		block_number INTEGER NOT NULL,
+		account TEXT NOT NULL,
+		free_before TEXT NOT NULL,
+		free_after TEXT NOT NULL,
+		reserved_before TEXT NOT NULL,
+		reserved_after TEXT NOT NULL,
+		PRIMARY KEY (block_number, account)
+	);
+	CREATE INDEX IF NOT EXISTS balance_changes_by_account
+		ON balance_changes (account, block_number);
+";
+
+/// Filters and pagination shared by all indexer queries. Unset filters match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IndexerQuery {
+	/// Signer of extrinsics, account involved in events, or owner of balances.
+	pub account: Option<AccountId>,
+	/// Pallet of extrinsic calls or events.
+	pub pallet: Option<String>,
+	/// First block to include.
+	pub from_block: Option<BlockNumber>,
+	/// Last block to include.
+	pub to_block: Option<BlockNumber>,
+	/// Number of results to skip.
+	pub offset: Option<u32>,
+	/// Maximum number of results, at most [`MAX_PAGE_SIZE`].
+	pub limit: Option<u32>,
+}
+
+impl IndexerQuery {
+	fn limit(&self) -> u32 {
+		self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+	}
+
+	fn offset(&self) -> u32 {
+		self.offset.unwrap_or_default()
+	}
+
+	fn account(&self) -> Option<String> {
+		self.account.as_ref().map(|who| who.to_ss58check())
+	}
+}
+
+/// One page of query results, in chain order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+	/// The results.
+	pub items: Vec<T>,
+	/// Offset of the next page, if there are more results.
+	pub next_offset: Option<u32>,
+}
+
+impl<T> Page<T> {
+	/// Build a page from up to `limit + 1` rows, the extra row signalling a next page.
+	fn new(query: &IndexerQuery, mut items: Vec<T>) -> Self {
+		let limit = query.limit();
+		let next_offset = if items.len() > limit as usize {
+			items.truncate(limit as usize);
+			Some(query.offset() + limit)
+		} else {
+			None
+		};
+		Self { items, next_offset }
+	}
+}
+
+/// An indexed extrinsic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedExtrinsic {
+	/// Block the extrinsic is included in.
+	pub block_number: BlockNumber,
+	/// Position of the extrinsic in the block.
+	pub index: u32,
+	/// Hash of the encoded extrinsic.
+	pub hash: String,
+	/// SS58 address of the signer, for signed extrinsics.
+	pub signer: Option<String>,
+	/// Pallet of the call, unless the extrinsic could not be decoded.
+	pub pallet: Option<String>,
+	/// Name of the call, unless the extrinsic could not be decoded.
+	pub call: Option<String>,
+	/// Whether the call was dispatched successfully.
+	pub success: bool,
+}
+
+/// An indexed event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvent {
+	/// Block the event was emitted in.
+	pub block_number: BlockNumber,
+	/// Position of the event in the block.
+	pub index: u32,
+	/// The extrinsic that emitted the event, if any.
+	pub extrinsic_index: Option<u32>,
+	/// Pallet emitting the event.
+	pub pallet: String,
+	/// Name of the event.
+	pub event: String,
+	/// Decoded arguments.
+	pub args: serde_json::Value,
+}
+
+/// A change of an account's balance within one block. Balances are decimal strings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceChange {
+	/// Block the change happened in.
+	pub block_number: BlockNumber,
+	/// SS58 address of the account.
+	pub account: String,
+	/// Free balance before the block.
+	pub free_before: String,
+	/// Free balance after the block.
+	pub free_after: String,
+	/// Reserved balance before the block.
+	pub reserved_before: String,
+	/// Reserved balance after the block.
+	pub reserved_after: String,
+}
+
+/// Everything indexed for one block, written in a single transaction.
+struct IndexedBlock {
+	number: BlockNumber,
+	hash: String,
+	extrinsics: Vec<IndexedExtrinsic>,
+	events: Vec<(IndexedEvent, Vec<String>)>,
+	balance_changes: Vec<BalanceChange>,
+}
+
+/// The indexer database.
+pub struct IndexerDb {
+	connection: Mutex<Connection>,
+}
+
+impl IndexerDb {
+	/// Open or create the database at `path`.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		Self::with_connection(Connection::open(path)?)
+	}
+
+	fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+		connection.execute_batch(SCHEMA)?;
+		Ok(Self { connection: Mutex::new(connection) })
+	}
+
+	fn connection(&self) -> std::sync::MutexGuard<Connection> {
+		self.connection.lock().expect("Indexer database lock poisoned")
+	}
+
+	/// Record the chain's genesis hash, or check it against the recorded one, so a database is
+	/// never mixed between chains.
+	fn check_genesis(&self, genesis: Hash) -> Result<(), String> {
+		let genesis = format!("{:?}", genesis);
+		let connection = self.connection();
+		let stored: Option<String> = connection
+			.query_row("SELECT value FROM meta WHERE key = 'genesis'", params![], |row| row.get(0))
+			.optional()
+			.map_err(|e| e.to_string())?;
+
+		match stored {
+			Some(stored) if stored != genesis =>
+				Err(format!("Database indexes another chain with genesis {}.", stored)),
+			Some(_) => Ok(()),
+			None => connection
+				.execute("INSERT INTO meta (key, value) VALUES ('genesis', ?1)", params![genesis])
+				.map(|_| ())
+				.map_err(|e| e.to_string()),
+		}
+	}
+
+	/// Number of the last indexed block.
+	pub fn last_indexed(&self) -> rusqlite::Result<Option<BlockNumber>> {
+		self.connection()
+			.query_row("SELECT MAX(number) FROM blocks", params![], |row| row.get(0))
+	}
+
+	/// Number of the block to index next, resuming after the last indexed one.
+	fn next_to_index(&self) -> rusqlite::Result<BlockNumber> {
+		Ok(self.last_indexed()?.map_or(FIRST_BLOCK, |last| last + 1))
+	}
+
+	fn insert_block(&self, block: &IndexedBlock) -> rusqlite::Result<()> {
+		let mut connection = self.connection();
+		let tx = connection.transaction()?;
+
+		tx.execute(
+			"INSERT INTO blocks (number, hash) VALUES (?1, ?2)",
+			params![block.number, block.hash],
+		)?;
+		for xt in &block.extrinsics {
+			tx.execute(
+				"INSERT INTO extrinsics (block_number, idx, hash, signer, pallet, call, success)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				params![
+					xt.block_number,
+					xt.index,
+					xt.hash,
+					xt.signer,
+					xt.pallet,
+					xt.call,
+					xt.success
+				],
+			)?;
+		}
+		for (event, accounts) in &block.events {
+			tx.execute(
+				"INSERT INTO events (block_number, idx, extrinsic_idx, pallet, event, args)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+				params![
+					event.block_number,
+					event.index,
+					event.extrinsic_index,
+					event.pallet,
+					event.event,
+					event.args.to_string()
+				],
+			)?;
+			for account in accounts {
+				tx.execute(
+					"INSERT OR IGNORE INTO event_accounts (block_number, event_idx, account)
+					VALUES (?1, ?2, ?3)",
+					params![event.block_number, event.index, account],
+				)?;
+			}
+		}
+		for change in &block.balance_changes {
+			tx.execute(
+				"INSERT INTO balance_changes
+				(block_number, account, free_before, free_after, reserved_before, reserved_after)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+				params![
+					change.block_number,
+					change.account,
+					change.free_before,
+					change.free_after,
+					change.reserved_before,
+					change.reserved_after
+				],
+			)?;
+		}
+
+		tx.commit()
+	}
+
+	/// Extrinsics signed by `account` calling into `pallet`.
+	pub fn extrinsics(&self, query: &IndexerQuery) -> rusqlite::Result<Page<IndexedExtrinsic>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT block_number, idx, hash, signer, pallet, call, success FROM extrinsics
+			WHERE (?1 IS NULL OR signer = ?1) AND (?2 IS NULL OR pallet = ?2)
+				AND (?3 IS NULL OR block_number >= ?3) AND (?4 IS NULL OR block_number <= ?4)
+			ORDER BY block_number, idx LIMIT ?5 OFFSET ?6",
+		)?;
+		let rows = statement
+			.query_map(params_from_iter(Self::query_params(query)), |row: &Row| {
+				Ok(IndexedExtrinsic {
+					block_number: row.get(0)?,
+					index: row.get(1)?,
+					hash: row.get(2)?,
+					signer: row.get(3)?,
+					pallet: row.get(4)?,
+					call: row.get(5)?,
+					success: row.get(6)?,
+				})
+			})?
+			.collect::<rusqlite::Result<_>>()?;
+
+		Ok(Page::new(query, rows))
+	}
+
+	/// Events of `pallet` with `account` among their arguments.
+	pub fn events(&self, query: &IndexerQuery) -> rusqlite::Result<Page<IndexedEvent>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT e.block_number, e.idx, e.extrinsic_idx, e.pallet, e.event, e.args FROM events e
+			WHERE (?1 IS NULL OR EXISTS (
+					SELECT 1 FROM event_accounts a
+					WHERE a.block_number = e.block_number AND a.event_idx = e.idx
+						AND a.account = ?1
+				))
+				AND (?2 IS NULL OR e.pallet = ?2)
+				AND (?3 IS NULL OR e.block_number >= ?3) AND (?4 IS NULL OR e.block_number <= ?4)
+			ORDER BY e.block_number, e.idx LIMIT ?5 OFFSET ?6",
+		)?;
+		let rows = statement
+			.query_map(params_from_iter(Self::query_params(query)), |row: &Row| {
+				let args: String = row.get(5)?;
+				Ok(IndexedEvent {
+					block_number: row.get(0)?,
+					index: row.get(1)?,
+					extrinsic_index: row.get(2)?,
+					pallet: row.get(3)?,
+					event: row.get(4)?,
+					args: serde_json::from_str(&args).unwrap_or(serde_json::Value::String(args)),
+				})
+			})?
+			.collect::<rusqlite::Result<_>>()?;
+
+		Ok(Page::new(query, rows))
+	}
+
+	/// Balance changes of `account`. Balance changes have no pallet, so `pallet` must be unset.
+	pub fn balance_changes(&self, query: &IndexerQuery) -> rusqlite::Result<Page<BalanceChange>> {
+		let connection = self.connection();
+		let mut statement = connection.prepare_cached(
+			"SELECT block_number, account, free_before, free_after, reserved_before, reserved_after
+			FROM balance_changes
+			WHERE (?1 IS NULL OR account = ?1) AND ?2 IS NULL
+				AND (?3 IS NULL OR block_number >= ?3) AND (?4 IS NULL OR block_number <= ?4)
+			ORDER BY block_number, account LIMIT ?5 OFFSET ?6",
+		)?;
+		let rows = statement
+			.query_map(params_from_iter(Self::query_params(query)), |row: &Row| {
+				Ok(BalanceChange {
+					block_number: row.get(0)?,
+					account: row.get(1)?,
+					free_before: row.get(2)?,
+					free_after: row.get(3)?,
+					reserved_before: row.get(4)?,
+					reserved_after: row.get(5)?,
+				})
+			})?
+			.collect::<rusqlite::Result<_>>()?;
+
+		Ok(Page::new(query, rows))
+	}
+
+	/// Positional parameters `?1` to `?6` shared by all queries. One row more than the page size
+	/// is requested to find out whether there is a next page.
+	fn query_params(query: &IndexerQuery) -> Vec<Box<dyn rusqlite::ToSql>> {
+		vec![
+			Box::new(query.account()),
+			Box::new(query.pallet.clone()),
+			Box::new(query.from_block),
+			Box::new(query.to_block),
+			Box::new(query.limit() + 1),
+			Box::new(query.offset()),
+		]
+	}
+}
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// Storage key of `System::Account` for `who`.
+fn account_key(who: &AccountId) -> Vec<u8> {
+	let mut key = [twox_128(b"System"), twox_128(b"Account")].concat();
+	key.extend_from_slice(&blake2_128(who.as_ref()));
+	key.extend_from_slice(who.as_ref());
+	key
+}
+
+fn index_block<C, B>(
+	decoder: &BlockDecoder<C, B>,
+	number: BlockNumber,
+) -> Result<IndexedBlock, String>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	if number < FIRST_BLOCK {
+		return Err(format!("Block #{} is not indexed.", number))
+	}
+	let block_events = decoder.block_events(number)?;
+	let hash = block_events.block_hash;
+	let metadata = decoder.metadata_at(&BlockId::Hash(hash))?;
+
+	let mut failed = HashSet::new();
+	let mut touched = BTreeSet::new();
+	let mut events = Vec::with_capacity(block_events.events.len());
+	for (index, event) in block_events.events.into_iter().enumerate() {
+		let extrinsic_index = match event.phase {
+			EventPhase::ApplyExtrinsic(index) => Some(index),
+			_ => None,
+		};
+		if event.pallet == "System" && event.event == "ExtrinsicFailed" {
+			failed.extend(extrinsic_index);
+		}
+		touched.extend(event.accounts.iter().cloned());
+
+		let accounts = event.accounts.iter().map(|who| who.to_ss58check()).collect();
+		let indexed = IndexedEvent {
+			block_number: number,
+			index: index as u32,
+			extrinsic_index,
+			pallet: event.pallet,
+			event: event.event,
+			args: serde_json::to_value(&event.args).map_err(|e| e.to_string())?,
+		};
+		events.push((indexed, accounts));
+	}
+
+	let body = decoder
+		.client()
+		.block_body(&BlockId::Hash(hash))
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Body of block #{} not found.", number))?;
+	let mut extrinsics = Vec::with_capacity(body.len());
+	for (index, opaque) in body.iter().enumerate() {
+		let encoded = opaque.encode();
+		// Extrinsics that can't be decoded are recorded without signer and call rather than
+		// stopping the indexer, e.g. those of a runtime newer than the node's.
+		let xt = UncheckedExtrinsic::decode(&mut &encoded[..])
+			.map_err(|e| {
+				log::warn!(
+					target: "indexer",
+					"Failed to decode extrinsic {}-{}: {}", number, index, e,
+				)
+			})
+			.ok();
+		let signer = xt.as_ref().and_then(|xt| match &xt.signature {
+			Some((MultiAddress::Id(who), _, _)) => Some(who.clone()),
+			_ => None,
+		});
+		let call = xt.as_ref().and_then(|xt| {
+			let call = metadata.call_name(&xt.function.encode());
+			if call.is_none() {
+				log::warn!(target: "indexer", "Unknown call in extrinsic {}-{}.", number, index);
+			}
+			call
+		});
+
+		touched.extend(signer.clone());
+		extrinsics.push(IndexedExtrinsic {
+			block_number: number,
+			index: index as u32,
+			hash: format!("{:?}", BlakeTwo256::hash(&encoded)),
+			signer: signer.map(|who| who.to_ss58check()),
+			pallet: call.map(|(pallet, _)| pallet.into()),
+			call: call.map(|(_, call)| call.into()),
+			success: !failed.contains(&(index as u32)),
+		});
+	}
+
+	let parent = decoder.canonical_hash(number - 1)?;
+	let account_at = |at: Hash, who: &AccountId| -> Result<AccountInfo, String> {
+		decoder
+			.storage(at, &account_key(who))?
+			.map(|bytes| AccountInfo::decode(&mut &bytes[..]))
+			.transpose()
+			.map(Option::unwrap_or_default)
+			.map_err(|e| e.to_string())
+	};
+	let mut balance_changes = Vec::new();
+	for who in touched {
+		let before = account_at(parent, &who)?.data;
+		let after = account_at(hash, &who)?.data;
+		if before.free != after.free || before.reserved != after.reserved {
+			balance_changes.push(BalanceChange {
+				block_number: number,
+				account: who.to_ss58check(),
+				free_before: before.free.to_string(),
+				free_after: after.free.to_string(),
+				reserved_before: before.reserved.to_string(),
+				reserved_after: after.reserved.to_string(),
+			});
+		}
+	}
+
+	Ok(IndexedBlock { number, hash: format!("{:?}", hash), extrinsics, events, balance_changes })
+}
+
+/// Index finalized blocks in order, resuming after the last indexed block. Finality is checked
+/// whenever the client imports or finalizes a block.
+async fn follow<C, B>(client: Arc<C>, backend: Arc<B>, db: Arc<IndexerDb>) -> Result<(), String>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: BlockchainEvents<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	db.check_genesis(client.info().genesis_hash)?;
+	let decoder = BlockDecoder::new(client.clone(), backend);
+	let mut next = db.next_to_index().map_err(|e| e.to_string())?;
+	let mut notifications = futures::stream::select(
+		client.import_notification_stream().map(|_| ()),
+		client.finality_notification_stream().map(|_| ()),
+	);
+
+	loop {
+		let finalized = client.info().finalized_number;
+		while next <= finalized {
+			let block = index_block(&decoder, next)?;
+			db.insert_block(&block)
+				.map_err(|e| format!("Failed to store block #{}: {}", next, e))?;
+			log::debug!(target: "indexer", "Indexed block #{}", next);
+			next += 1;
+		}
+
+		if notifications.next().await.is_none() {
+			return Ok(())
+		}
+	}
+}
+
+/// The indexer task. Indexing stops with an error log if a block can't be indexed, e.g.
+/// because its state has been pruned.
+pub async fn run<C, B>(client: Arc<C>, backend: Arc<B>, db: Arc<IndexerDb>)
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: BlockchainEvents<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	if let Err(e) = follow(client, backend, db).await {
+		log::error!(target: "indexer", "Indexer stopped: {}", e);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rusqlite::OpenFlags;
+
+	fn account(byte: u8) -> AccountId {
+		AccountId::from([byte; 32])
+	}
+
+	fn address(byte: u8) -> String {
+		account(byte).to_ss58check()
+	}
+
+	fn extrinsic(number: BlockNumber, index: u32, signer: u8, pallet: &str) -> IndexedExtrinsic {
+		IndexedExtrinsic {
+			block_number: number,
+			index,
+			hash: format!("0x{:064x}", number * 100 + index),
+			signer: Some(address(signer)),
+			pallet: Some(pallet.into()),
+			call: Some("transfer".into()),
+			success: true,
+		}
+	}
+
+	fn event(number: BlockNumber, index: u32, pallet: &str) -> IndexedEvent {
+		IndexedEvent {
+			block_number: number,
+			index,
+			extrinsic_index: Some(index),
+			pallet: pallet.into(),
+			event: "Transfer".into(),
+			args: serde_json::json!([{ "type": "Balance", "value": "5" }]),
+		}
+	}
+
+	fn change(number: BlockNumber, who: u8) -> BalanceChange {
+		BalanceChange {
+			block_number: number,
+			account: address(who),
+			free_before: "10".into(),
+			free_after: "5".into(),
+			reserved_before: "0".into(),
+			reserved_after: "0".into(),
+		}
+	}
+
+	// Block `number` with a balance transfer signed by 1 to 2 and an extrinsic that could not be
+	// decoded.
+	fn block(number: BlockNumber) -> IndexedBlock {
+		let undecodable = IndexedExtrinsic {
+			signer: None,
+			pallet: None,
+			call: None,
+			..extrinsic(number, 1, 3, "System")
+		};
+		// Balance changes are returned ordered by account.
+		let mut balance_changes = vec![change(number, 1), change(number, 2)];
+		balance_changes.sort_by(|a, b| a.account.cmp(&b.account));
+		IndexedBlock {
+			number,
+			hash: format!("0x{:064x}", number),
+			extrinsics: vec![extrinsic(number, 0, 1, "Balances"), undecodable],
+			events: vec![
+				(event(number, 0, "Balances"), vec![address(1), address(2)]),
+				(event(number, 1, "System"), vec![]),
+			],
+			balance_changes,
+		}
+	}
+
+	fn memory_db(name: &str) -> IndexerDb {
+		let flags = OpenFlags::SQLITE_OPEN_READ_WRITE |
+			OpenFlags::SQLITE_OPEN_CREATE |
+			OpenFlags::SQLITE_OPEN_URI;
+		let uri = format!("file:{}?mode=memory&cache=shared", name);
+		IndexerDb::with_connection(Connection::open_with_flags(uri, flags).unwrap()).unwrap()
+	}
+
+	fn query() -> IndexerQuery {
+		IndexerQuery::default()
+	}
+
+	#[test]
+	fn blocks_are_stored_as_indexed() {
+		let db = memory_db("stored");
+		assert_eq!(db.last_indexed().unwrap(), None);
+		let block = block(1);
+		db.insert_block(&block).unwrap();
+
+		assert_eq!(db.last_indexed().unwrap(), Some(1));
+		assert_eq!(db.extrinsics(&query()).unwrap().items, block.extrinsics);
+		let events: Vec<_> = block.events.iter().map(|(event, _)| event.clone()).collect();
+		assert_eq!(db.events(&query()).unwrap().items, events);
+		assert_eq!(db.balance_changes(&query()).unwrap().items, block.balance_changes);
+
+		// A block is indexed once.
+		assert!(db.insert_block(&block).is_err());
+		assert_eq!(db.extrinsics(&query()).unwrap().items.len(), 2);
+	}
+
+	#[test]
+	fn queries_filter_by_account_pallet_and_blocks() {
+		let db = memory_db("filters");
+		for number in 1..=3 {
+			db.insert_block(&block(number)).unwrap();
+		}
+
+		let signed_by_1 = IndexerQuery { account: Some(account(1)), ..query() };
+		let expected: Vec<_> = (1..=3).map(|number| extrinsic(number, 0, 1, "Balances")).collect();
+		assert_eq!(db.extrinsics(&signed_by_1).unwrap().items, expected);
+		let balances = IndexerQuery { pallet: Some("Balances".into()), ..query() };
+		assert_eq!(db.extrinsics(&balances).unwrap().items.len(), 3);
+		let unknown = IndexerQuery { pallet: Some("Unknown".into()), ..query() };
+		assert!(db.extrinsics(&unknown).unwrap().items.is_empty());
+
+		// Events match the accounts among their arguments.
+		let involving_2 = IndexerQuery {
+			account: Some(account(2)),
+			from_block: Some(2),
+			to_block: Some(3),
+			..query()
+		};
+		let items = db.events(&involving_2).unwrap().items;
+		assert_eq!(items, vec![event(2, 0, "Balances"), event(3, 0, "Balances")]);
+		let system = IndexerQuery { pallet: Some("System".into()), to_block: Some(1), ..query() };
+		assert_eq!(db.events(&system).unwrap().items, vec![event(1, 1, "System")]);
+		let involving_3 = IndexerQuery { account: Some(account(3)), ..query() };
+		assert!(db.events(&involving_3).unwrap().items.is_empty());
+
+		let owned_by_2 = IndexerQuery { account: Some(account(2)), from_block: Some(3), ..query() };
+		assert_eq!(db.balance_changes(&owned_by_2).unwrap().items, vec![change(3, 2)]);
+		assert!(db.balance_changes(&balances).unwrap().items.is_empty());
+	}
+
+	#[test]
+	fn pages_hold_at_most_the_limit() {
+		let db = memory_db("pages");
+		for number in 1..=3 {
+			db.insert_block(&block(number)).unwrap();
+		}
+
+		// Six extrinsics in pages of four.
+		let first = IndexerQuery { limit: Some(4), ..query() };
+		let page = db.extrinsics(&first).unwrap();
+		assert_eq!(page.items.len(), 4);
+		assert_eq!(page.next_offset, Some(4));
+		let second = IndexerQuery { offset: page.next_offset, ..first.clone() };
+		let page = db.extrinsics(&second).unwrap();
+		assert_eq!(page.items, db.extrinsics(&query()).unwrap().items[4..]);
+		assert_eq!(page.next_offset, None);
+
+		// A page that ends with the last result has no next page.
+		let exact = IndexerQuery { limit: Some(6), ..query() };
+		assert_eq!(db.extrinsics(&exact).unwrap().next_offset, None);
+
+		assert_eq!(query().limit(), DEFAULT_PAGE_SIZE);
+		assert_eq!(IndexerQuery { limit: Some(1_000), ..query() }.limit(), MAX_PAGE_SIZE);
+	}
+
+	#[test]
+	fn indexing_resumes_after_a_restart() {
+		let db = memory_db("restart");
+		let genesis = Hash::repeat_byte(1);
+		db.check_genesis(genesis).unwrap();
+		// Genesis is not indexed.
+		assert_eq!(db.next_to_index().unwrap(), 1);
+		db.insert_block(&block(1)).unwrap();
+		db.insert_block(&block(2)).unwrap();
+
+		// The in-memory database outlives the first connection while the second is open.
+		let restarted = memory_db("restart");
+		drop(db);
+		assert_eq!(restarted.next_to_index().unwrap(), 3);
+		restarted.check_genesis(genesis).unwrap();
+		assert!(restarted.check_genesis(Hash::repeat_byte(2)).is_err());
+		assert_eq!(restarted.extrinsics(&query()).unwrap().items.len(), 4);
+	}
+}
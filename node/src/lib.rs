@@ -1,3 +1,8 @@
 pub mod chain_spec;
+pub mod cli;
+pub mod decode;
+pub mod fork_off;
+pub mod genesis;
+pub mod indexer;
 pub mod rpc;
 pub mod service;
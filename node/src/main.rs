@@ -6,6 +6,10 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod decode;
+mod fork_off;
+mod genesis;
+mod indexer;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
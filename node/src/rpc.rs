@@ -5,50 +5,214 @@
 
 #![warn(missing_docs)]
 
-use std::sync::Arc;
+pub mod events;
+pub mod indexer;
+pub mod light;
 
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::channel::mpsc::Sender;
+use jsonrpc_pubsub::manager::SubscriptionManager;
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use sc_client_api::{
+	light::{Fetcher, RemoteBlockchain},
+	Backend, BlockchainEvents, StateBackend, StorageProvider,
+};
+use sc_consensus_manual_seal::{
+	rpc::{ManualSeal, ManualSealApi},
+	EngineCommand,
+};
+use sc_network::NetworkService;
+pub use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_runtime::traits::BlakeTwo256;
 use sp_transaction_pool::TransactionPool;
 
 /// Full client dependencies.
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// The backend, used to read state of past blocks.
+	pub backend: Arc<B>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor for RPC subscriptions.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// The indexer database, present when the node runs with `--indexer-db`.
+	pub indexer: Option<Arc<crate::indexer::IndexerDb>>,
+	/// Channel for `engine_*` requests, present when the node runs with `--sealing`.
+	pub command_sink: Option<Sender<EngineCommand<Hash>>>,
+	/// The network service, for `net_*` and the syncing status `eth_syncing` reports.
+	pub network: Arc<NetworkService<Block, Hash>>,
+	/// Frontier's database, mapping Ethereum block and transaction hashes to substrate blocks.
+	pub frontier_backend: Arc<fc_db::Backend<Block>>,
+	/// Whether the node authors blocks, as `eth_mining` reports.
+	pub is_authority: bool,
+}
+
+/// Most logs a single `eth_getLogs` request may return.
+const MAX_PAST_LOGS: u32 = 10_000;
+
+/// Light client dependencies.
+pub struct LightDeps<C, F, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Remote access to the blockchain, used to fetch headers.
+	pub remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	/// Fetcher requesting state and call results, with proofs, from full nodes.
+	pub fetcher: Arc<F>,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+pub fn create_full<C, P, B>(deps: FullDeps<C, P, B>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
 where
-	C: ProvideRuntimeApi<Block>,
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, B>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block>,
 	C: Send + Sync + 'static,
+	C::Api: sp_api::Core<Block> + sp_api::Metadata<Block>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_template_rpc::TemplateRuntimeApi<Block>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + 'static,
+	P: TransactionPool<Block = Block> + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+	B::State: StateBackend<BlakeTwo256>,
 {
+	use events::{Events, EventsApi};
+	use fc_rpc::{
+		EthApi, EthApiServer, NetApi, NetApiServer, OverrideHandle, RuntimeApiStorageOverride,
+		SchemaV1Override, StorageOverride, Web3Api, Web3ApiServer,
+	};
+	use fp_storage::EthereumStorageSchema;
+	use indexer::{Indexer, IndexerApi};
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
+	use pallet_template_rpc::{Template, TemplateApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps {
+		client,
+		pool,
+		backend,
+		deny_unsafe,
+		subscription_executor,
+		indexer,
+		command_sink,
+		network,
+		frontier_backend,
+		is_authority,
+	} = deps;
 
-	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
+	io.extend_with(SystemApi::to_delegate(FullSystem::new(
+		client.clone(),
+		pool.clone(),
+		deny_unsafe,
+	)));
 
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 
-	// Extend this RPC with a custom API by using the following syntax.
-	// `YourRpcStruct` should have a reference to a client, which is needed
-	// to call into the runtime.
-	// `io.extend_with(YourRpcTrait::to_delegate(YourRpcStruct::new(ReferenceToClient, ...)));`
+	// `contracts_call` and `contracts_instantiate` dry-run contracts against the best block.
+	io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
+
+	// Custom pallets expose their storage through a runtime API and an RPC crate following
+	// `pallet-template-rpc`, and are mounted here in the same way.
+	io.extend_with(TemplateApi::to_delegate(Template::new(client.clone())));
+
+	io.extend_with(EventsApi::to_delegate(Events::new(
+		client.clone(),
+		backend,
+		SubscriptionManager::new(Arc::new(subscription_executor)),
+		deny_unsafe,
+	)));
+
+	// Frontier reads Ethereum blocks and receipts straight from storage when it knows their
+	// layout, and through the runtime API otherwise.
+	let mut schemas = BTreeMap::new();
+	schemas.insert(
+		EthereumStorageSchema::V1,
+		Box::new(SchemaV1Override::new(client.clone()))
+			as Box<dyn StorageOverride<_> + Send + Sync>,
+	);
+	let overrides = Arc::new(OverrideHandle {
+		schemas,
+		fallback: Box::new(RuntimeApiStorageOverride::new(client.clone())),
+	});
+
+	io.extend_with(EthApiServer::to_delegate(EthApi::new(
+		client.clone(),
+		pool,
+		node_template_runtime::TransactionConverter,
+		network.clone(),
+		// No pending transaction cache and no signers: transactions are signed by the client and
+		// submitted with `eth_sendRawTransaction`.
+		None,
+		Vec::new(),
+		overrides,
+		frontier_backend,
+		is_authority,
+		MAX_PAST_LOGS,
+	)));
+
+	// Ethereum tooling also expects `net_version` and `web3_clientVersion`.
+	io.extend_with(NetApiServer::to_delegate(NetApi::new(client.clone(), network, true)));
+
+	io.extend_with(Web3ApiServer::to_delegate(Web3Api::new(client.clone())));
+
+	if let Some(db) = indexer {
+		io.extend_with(IndexerApi::to_delegate(Indexer::new(db)));
+	}
+
+	if let Some(command_sink) = command_sink {
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	}
+
+	io
+}
+
+/// Instantiate all light RPC extensions.
+pub fn create_light<C, F, P>(deps: LightDeps<C, F, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+	P: TransactionPool + 'static,
+{
+	use light::{LightTransactionPayment, LightTransactionPaymentApi};
+	use pallet_template_rpc::{LightTemplate, LightTemplateApi};
+	use substrate_frame_rpc_system::{LightSystem, SystemApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let LightDeps { client, pool, remote_blockchain, fetcher } = deps;
+
+	io.extend_with(SystemApi::<Hash, AccountId, Index>::to_delegate(LightSystem::new(
+		client.clone(),
+		remote_blockchain.clone(),
+		fetcher.clone(),
+		pool,
+	)));
+
+	io.extend_with(LightTransactionPaymentApi::to_delegate(LightTransactionPayment::new(
+		client.clone(),
+		remote_blockchain.clone(),
+		fetcher.clone(),
+	)));
+
+	// Light variants of custom pallet RPCs are mounted here, next to their full counterparts.
+	io.extend_with(LightTemplateApi::to_delegate(LightTemplate::new(
+		client,
+		remote_blockchain,
+		fetcher,
+	)));
 
 	io
 }
@@ -0,0 +1,205 @@
+//! `events_subscribe`: a stream of decoded runtime events.
+//!
+//! `System::Events` is read at every finalized block and decoded as described in
+//! [`crate::decode`]. Replaying events from an older block reads historical state, so on a pruned
+//! node `from` must be within the pruning window. Replays are limited to [`MAX_REPLAY`] blocks
+//! behind finality unless unsafe RPCs are allowed.
+
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+use jsonrpc_core::{
+	futures::{Future as _, Sink as _},
+	Error as RpcError, ErrorCode, Result as RpcResult,
+};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockchainEvents};
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Header as HeaderT;
+
+use crate::decode::{BlockDecoder, BlockEvents, DecodedEvent};
+
+/// Error code returned when events of a block cannot be read or decoded.
+const DECODE_ERROR: i64 = 2;
+/// Error code returned when the block to resume from is unknown or not finalized.
+const INVALID_BLOCK: i64 = 3;
+
+/// The most blocks behind finality events can be replayed from when unsafe RPCs are denied,
+/// about seven hours with 6 second blocks. Every replayed block is read and decoded, so longer
+/// replays are left to trusted clients.
+pub const MAX_REPLAY: BlockNumber = 4096;
+
+/// Restricts a subscription to some of the events. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventFilter {
+	/// Name of the pallet emitting the event, as in `construct_runtime!`, e.g. `Balances`.
+	pub pallet: Option<String>,
+	/// Name of the event, e.g. `Transfer`.
+	pub event: Option<String>,
+	/// An account that must appear among the event's arguments.
+	pub account: Option<AccountId>,
+}
+
+impl EventFilter {
+	fn matches(&self, event: &DecodedEvent) -> bool {
+		self.pallet.as_ref().map_or(true, |pallet| *pallet == event.pallet) &&
+			self.event.as_ref().map_or(true, |name| *name == event.event) &&
+			self.account.as_ref().map_or(true, |who| event.accounts.contains(who))
+	}
+}
+
+/// Decoded event subscriptions.
+#[rpc(server)]
+pub trait EventsApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Subscribe to decoded events of finalized blocks accepted by `filter`. When `from` is
+	/// given, events of the finalized blocks after it are replayed first; unless unsafe RPCs are
+	/// allowed, it must be at most [`MAX_REPLAY`] blocks behind finality. A notification is sent
+	/// for every block with at least one matching event; pass its `blockHash` as `from` to resume
+	/// after it.
+	#[pubsub(subscription = "events", subscribe, name = "events_subscribe")]
+	fn subscribe(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<BlockEvents>,
+		filter: Option<EventFilter>,
+		from: Option<Hash>,
+	);
+
+	/// Unsubscribe from decoded events.
+	#[pubsub(subscription = "events", unsubscribe, name = "events_unsubscribe")]
+	fn unsubscribe(&self, metadata: Option<Self::Metadata>, id: SubscriptionId)
+		-> RpcResult<bool>;
+}
+
+/// Implements the [`EventsApi`] RPC trait.
+pub struct Events<C, B> {
+	decoder: Arc<BlockDecoder<C, B>>,
+	manager: SubscriptionManager,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C, B> Events<C, B> {
+	/// Create a new `Events` handler reading state from `backend`.
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<B>,
+		manager: SubscriptionManager,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self { decoder: Arc::new(BlockDecoder::new(client, backend)), manager, deny_unsafe }
+	}
+}
+
+impl<C, B> Events<C, B>
+where
+	C: HeaderBackend<Block>,
+{
+	/// The first block to send events for when resuming after `from`.
+	fn resume_after(&self, from: Hash) -> Result<BlockNumber, RpcError> {
+		let client = self.decoder.client();
+		let invalid = |message: &str| RpcError {
+			code: ErrorCode::ServerError(INVALID_BLOCK),
+			message: message.into(),
+			data: None,
+		};
+
+		let number = client
+			.number(from)
+			.map_err(|e| invalid(&e.to_string()))?
+			.ok_or_else(|| invalid("Unknown block to resume from."))?;
+		let finalized = client.info().finalized_number;
+		if number > finalized || client.hash(number).ok().flatten() != Some(from) {
+			return Err(invalid("Block to resume from is not finalized."))
+		}
+		if finalized - number > MAX_REPLAY && self.deny_unsafe.check_if_safe().is_err() {
+			return Err(invalid("Block to resume from is too far behind finality."))
+		}
+
+		Ok(number + 1)
+	}
+}
+
+impl<C, B> EventsApi for Events<C, B>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<BlockEvents>,
+		filter: Option<EventFilter>,
+		from: Option<Hash>,
+	) {
+		let finalized = self.decoder.client().info().finalized_number;
+		let mut next = match from.map(|from| self.resume_after(from)).transpose() {
+			Ok(next) => next.unwrap_or(finalized + 1),
+			Err(e) => {
+				let _ = subscriber.reject(e);
+				return
+			},
+		};
+		let filter = filter.unwrap_or_default();
+		let decoder = self.decoder.clone();
+
+		// Catch up to the current finalized block first, then follow finality. Finality may
+		// advance several blocks at once, so every notification covers all blocks since the
+		// previous one.
+		let targets = futures::stream::once(futures::future::ready(finalized)).chain(
+			self.decoder.client().finality_notification_stream().map(|n| *n.header.number()),
+		);
+		let stream = targets
+			.flat_map(move |target| {
+				let decoder = decoder.clone();
+				let first = next;
+				next = next.max(target + 1);
+				futures::stream::iter(first..=target)
+					.map(move |number| decoder.block_events(number))
+			})
+			.filter_map(move |block| {
+				futures::future::ready(match block {
+					Ok(mut block) => {
+						block.events.retain(|event| filter.matches(event));
+						if block.events.is_empty() {
+							None
+						} else {
+							Some(Ok(block))
+						}
+					},
+					Err(e) => Some(Err(RpcError {
+						code: ErrorCode::ServerError(DECODE_ERROR),
+						message: "Unable to decode block events.".into(),
+						data: Some(e.into()),
+					})),
+				})
+			})
+			.map(Ok::<_, ()>);
+
+		self.manager.add(subscriber, move |sink| {
+			sink.sink_map_err(|e| log::warn!("Error sending event notifications: {:?}", e))
+				.send_all(Box::pin(stream).compat())
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
@@ -0,0 +1,66 @@
+//! Paginated queries against the optional indexer database.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result as RpcResult};
+use jsonrpc_derive::rpc;
+
+use crate::indexer::{
+	BalanceChange, IndexedEvent, IndexedExtrinsic, IndexerDb, IndexerQuery, Page,
+};
+
+/// Error code returned when the indexer database can't be queried.
+const DATABASE_ERROR: i64 = 4;
+
+/// Indexer RPC methods.
+#[rpc(server)]
+pub trait IndexerApi {
+	/// Extrinsics of finalized blocks, filtered by signer, pallet and block range.
+	#[rpc(name = "indexer_extrinsics")]
+	fn extrinsics(&self, query: IndexerQuery) -> RpcResult<Page<IndexedExtrinsic>>;
+
+	/// Events of finalized blocks, filtered by involved account, pallet and block range.
+	#[rpc(name = "indexer_events")]
+	fn events(&self, query: IndexerQuery) -> RpcResult<Page<IndexedEvent>>;
+
+	/// Balance changes in finalized blocks, filtered by account and block range.
+	#[rpc(name = "indexer_balanceChanges")]
+	fn balance_changes(&self, query: IndexerQuery) -> RpcResult<Page<BalanceChange>>;
+}
+
+/// Implements the [`IndexerApi`] RPC trait.
+pub struct Indexer {
+	db: Arc<IndexerDb>,
+}
+
+impl Indexer {
+	/// Create a new `Indexer` querying `db`.
+	pub fn new(db: Arc<IndexerDb>) -> Self {
+		Self { db }
+	}
+}
+
+fn database_error(e: rusqlite::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(DATABASE_ERROR),
+		message: "Indexer database query failed.".into(),
+		data: Some(e.to_string().into()),
+	}
+}
+
+impl IndexerApi for Indexer {
+	fn extrinsics(&self, query: IndexerQuery) -> RpcResult<Page<IndexedExtrinsic>> {
+		self.db.extrinsics(&query).map_err(database_error)
+	}
+
+	fn events(&self, query: IndexerQuery) -> RpcResult<Page<IndexedEvent>> {
+		self.db.events(&query).map_err(database_error)
+	}
+
+	fn balance_changes(&self, query: IndexerQuery) -> RpcResult<Page<BalanceChange>> {
+		if query.pallet.is_some() {
+			return Err(RpcError::invalid_params("Balance changes can't be filtered by pallet."))
+		}
+		self.db.balance_changes(&query).map_err(database_error)
+	}
+}
@@ -0,0 +1,136 @@
+//! Fee queries for light clients.
+//!
+//! `pallet-transaction-payment-rpc` runs the runtime API on local state, which a light client
+//! doesn't have. These methods answer the same requests by having a full node execute the call
+//! with `light_rpc::remote_call`.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::{Future, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, Balance, Hash};
+use pallet_transaction_payment_rpc_runtime_api::{FeeDetails, InclusionFee, RuntimeDispatchInfo};
+use sc_client_api::light::{Fetcher, RemoteBlockchain};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+/// Error code returned when the extrinsic can't be decoded, as in `pallet-transaction-payment-rpc`.
+const DECODE_ERROR: i64 = 2;
+
+/// Transaction payment RPC methods served by light clients.
+#[rpc(server)]
+pub trait LightTransactionPaymentApi {
+	/// Weight, class and partial fee of an encoded extrinsic at the given or best block.
+	#[rpc(name = "payment_queryInfo")]
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<RuntimeDispatchInfo<Balance>>;
+
+	/// Breakdown of the fee of an encoded extrinsic at the given or best block.
+	#[rpc(name = "payment_queryFeeDetails")]
+	fn query_fee_details(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<FeeDetails<NumberOrHex>>;
+}
+
+/// Implements the [`LightTransactionPaymentApi`] RPC trait.
+pub struct LightTransactionPayment<C, F> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F> LightTransactionPayment<C, F>
+where
+	C: HeaderBackend<Block>,
+	F: Fetcher<Block> + 'static,
+{
+	/// Create a new `LightTransactionPayment` requesting calls through `fetcher`.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		Self { client, remote_blockchain, fetcher }
+	}
+
+	/// Execute the runtime API function `method` on a full node at `at`, or at the best block.
+	fn remote_call<R: Decode>(
+		&self,
+		at: Option<Hash>,
+		method: &'static str,
+		call_data: Vec<u8>,
+	) -> impl Future<Output = Result<R, RpcError>> {
+		light_rpc::remote_call(
+			&*self.remote_blockchain,
+			self.fetcher.clone(),
+			at.unwrap_or_else(|| self.client.info().best_hash),
+			method,
+			call_data,
+			"Unable to query dispatch info.",
+		)
+	}
+}
+
+/// Arguments of the `TransactionPaymentApi` functions for an encoded extrinsic.
+fn call_data(encoded_xt: Bytes) -> Result<Vec<u8>, RpcError> {
+	let encoded_len = encoded_xt.len() as u32;
+	let uxt = <Block as BlockT>::Extrinsic::decode(&mut &*encoded_xt).map_err(|e| RpcError {
+		code: ErrorCode::ServerError(DECODE_ERROR),
+		message: "Unable to query dispatch info.".into(),
+		data: Some(format!("{:?}", e).into()),
+	})?;
+
+	Ok((uxt, encoded_len).encode())
+}
+
+impl<C, F> LightTransactionPaymentApi for LightTransactionPayment<C, F>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+{
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<RuntimeDispatchInfo<Balance>> {
+		let call_data = match call_data(encoded_xt) {
+			Ok(call_data) => call_data,
+			Err(e) => return Box::new(jsonrpc_core::futures::future::err(e)),
+		};
+		let result = self.remote_call(at, "TransactionPaymentApi_query_info", call_data);
+
+		Box::new(Box::pin(result).compat())
+	}
+
+	fn query_fee_details(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<FeeDetails<NumberOrHex>> {
+		let call_data = match call_data(encoded_xt) {
+			Ok(call_data) => call_data,
+			Err(e) => return Box::new(jsonrpc_core::futures::future::err(e)),
+		};
+		let result = self
+			.remote_call(at, "TransactionPaymentApi_query_fee_details", call_data)
+			.map_ok(|details: FeeDetails<Balance>| FeeDetails {
+				inclusion_fee: details.inclusion_fee.map(|fee| InclusionFee {
+					base_fee: fee.base_fee.into(),
+					len_fee: fee.len_fee.into(),
+					adjusted_weight_fee: fee.adjusted_weight_fee.into(),
+				}),
+				tip: Default::default(),
+			});
+
+		Box::new(Box::pin(result).compat())
+	}
+}
@@ -1,17 +1,41 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::{ExecutorProvider, RemoteBackend};
-use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use crate::{
+	chain_spec::Extensions,
+	cli::{Cli, Sealing},
+};
+use fc_mapping_sync::MappingSyncWorker;
+use futures::{channel::mpsc, Stream, StreamExt};
+use node_template_runtime::{self, opaque::Block, Hash, RuntimeApi};
+use remote_signer::RemoteKeystore;
+use sc_cli::SubstrateCli;
+use sc_client_api::{BlockchainEvents, ExecutorProvider, RemoteBackend};
+use sc_consensus_babe::{BabeParams, SlotProportion};
+use sc_consensus_manual_seal::{consensus::ConsensusDataProvider, EngineCommand, ManualSealParams};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_finality_grandpa::SharedVoterState;
-use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, BasePath, ChainType, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
-use sp_consensus::SlotData;
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockImportParams, SlotData};
+use sp_consensus_babe::{
+	digests::{CompatibleDigestItem, PreDigest, SecondaryPlainPreDigest},
+	Slot,
+};
+use sp_inherents::InherentData;
+use sp_runtime::{
+	generic::{BlockId, Digest, DigestItem},
+	traits::{Block as BlockT, DigestFor, Header as HeaderT},
+};
+use sp_timestamp::TimestampInherentData;
+use sp_transaction_pool::TransactionPool;
+use std::{
+	path::PathBuf,
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 // Our native executor instance.
 native_executor_instance!(
@@ -24,9 +48,12 @@ native_executor_instance!(
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
+type FullGrandpaBlockImport =
+	sc_finality_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
 
 pub fn new_partial(
 	config: &Configuration,
+	sealing: Option<Sealing>,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
@@ -35,22 +62,14 @@ pub fn new_partial(
 		sp_consensus::DefaultImportQueue<Block, FullClient>,
 		sc_transaction_pool::FullPool<Block, FullClient>,
 		(
-			sc_finality_grandpa::GrandpaBlockImport<
-				FullBackend,
-				Block,
-				FullClient,
-				FullSelectChain,
-			>,
+			sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 			sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
+			sc_consensus_babe::BabeLink<Block>,
 			Option<Telemetry>,
 		),
 	>,
 	ServiceError,
 > {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(format!("Remote Keystores are not supported.")))
-	}
-
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -90,33 +109,47 @@ pub fn new_partial(
 		select_chain.clone(),
 		telemetry.as_ref().map(|x| x.handle()),
 	)?;
+	let justification_import = grandpa_block_import.clone();
 
-	let slot_duration = sc_consensus_aura::slot_duration(&*client)?.slot_duration();
+	let (block_import, babe_link) = sc_consensus_babe::block_import(
+		sc_consensus_babe::Config::get_or_compute(&*client)?,
+		grandpa_block_import,
+		client.clone(),
+	)?;
 
-	let import_queue =
-		sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _, _>(ImportQueueParams {
-			block_import: grandpa_block_import.clone(),
-			justification_import: Some(Box::new(grandpa_block_import.clone())),
-			client: client.clone(),
-			create_inherent_data_providers: move |_, ()| async move {
+	// Manually sealed blocks carry no BABE seal, so they are imported straight into the client.
+	let import_queue = if sealing.is_some() {
+		sc_consensus_manual_seal::import_queue(
+			Box::new(client.clone()),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		)
+	} else {
+		let slot_duration = babe_link.config().slot_duration();
+
+		sc_consensus_babe::import_queue(
+			babe_link.clone(),
+			block_import.clone(),
+			Some(Box::new(justification_import)),
+			client.clone(),
+			select_chain.clone(),
+			move |_, ()| async move {
 				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
 				let slot =
-					sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_duration(
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
 						*timestamp,
 						slot_duration,
 					);
 
 				Ok((timestamp, slot))
 			},
-			spawner: &task_manager.spawn_essential_handle(),
-			can_author_with: sp_consensus::CanAuthorWithNativeVersion::new(
-				client.executor().clone(),
-			),
-			registry: config.prometheus_registry(),
-			check_for_equivocation: Default::default(),
-			telemetry: telemetry.as_ref().map(|x| x.handle()),
-		})?;
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+			telemetry.as_ref().map(|x| x.handle()),
+		)?
+	};
 
 	Ok(sc_service::PartialComponents {
 		client,
@@ -126,19 +159,157 @@ pub fn new_partial(
 		keystore_container,
 		select_chain,
 		transaction_pool,
-		other: (grandpa_block_import, grandpa_link, telemetry),
+		other: (block_import, grandpa_link, babe_link, telemetry),
 	})
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//        must return a concrete type (NOT `LocalKeystore`) that
-	//        implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+/// Opens Frontier's database, which maps Ethereum block and transaction hashes to substrate
+/// blocks. It lives in the chain's config directory, next to the chain database.
+fn open_frontier_backend(config: &Configuration) -> Result<Arc<fc_db::Backend<Block>>, String> {
+	let config_dir = config
+		.base_path
+		.as_ref()
+		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
+		.unwrap_or_else(|| {
+			BasePath::from_project("", "", &Cli::executable_name())
+				.config_dir(config.chain_spec.id())
+		});
+
+	Ok(Arc::new(fc_db::Backend::<Block>::new(&fc_db::DatabaseSettings {
+		source: fc_db::DatabaseSettingsSrc::RocksDb {
+			path: config_dir.join("frontier").join("db"),
+			cache_size: 0,
+		},
+	})?))
+}
+
+/// Supplies the BABE pre-runtime digest `pallet_babe` reads on every block, so the runtime needs
+/// no changes to run under manual sealing. The slot is derived from the block's timestamp and
+/// claimed as a secondary slot of the first authority. Such a claim carries no VRF output, so the
+/// epoch randomness doesn't change while blocks are sealed manually.
+struct BabeDigestProvider {
+	slot_duration: u64,
+}
+
+impl ConsensusDataProvider<Block> for BabeDigestProvider {
+	type Transaction = sp_api::TransactionFor<FullClient, Block>;
+
+	fn create_digest(
+		&self,
+		_parent: &<Block as BlockT>::Header,
+		inherents: &InherentData,
+	) -> Result<DigestFor<Block>, sc_consensus_manual_seal::Error> {
+		let timestamp = inherents
+			.timestamp_inherent_data()
+			.map_err(|e| sc_consensus_manual_seal::Error::StringError(e.to_string()))?
+			.ok_or_else(|| {
+				sc_consensus_manual_seal::Error::StringError("Timestamp inherent missing.".into())
+			})?;
+		let slot = Slot::from(*timestamp / self.slot_duration);
+
+		let pre_digest =
+			PreDigest::SecondaryPlain(SecondaryPlainPreDigest { authority_index: 0, slot });
+
+		Ok(Digest { logs: vec![DigestItem::babe_pre_digest(pre_digest)] })
+	}
+
+	fn append_block_import(
+		&self,
+		_parent: &<Block as BlockT>::Header,
+		_params: &mut BlockImportParams<Block, Self::Transaction>,
+		_inherents: &InherentData,
+	) -> Result<(), sc_consensus_manual_seal::Error> {
+		Ok(())
+	}
+}
+
+/// The command that seals and immediately finalizes a block on top of the best block.
+fn seal_command(create_empty: bool) -> EngineCommand<Hash> {
+	EngineCommand::SealNewBlock { create_empty, finalize: true, parent_hash: None, sender: None }
+}
+
+/// Spawns the manual-seal authorship task. Besides `engine_*` RPC requests arriving on
+/// `rpc_commands`, blocks are sealed on transaction import or on a timer, depending on `sealing`.
+fn start_manual_seal(
+	sealing: Sealing,
+	rpc_commands: mpsc::Receiver<EngineCommand<Hash>>,
+	client: Arc<FullClient>,
+	select_chain: FullSelectChain,
+	transaction_pool: Arc<sc_transaction_pool::FullPool<Block, FullClient>>,
+	proposer_factory: sc_basic_authorship::ProposerFactory<
+		sc_transaction_pool::FullPool<Block, FullClient>,
+		FullBackend,
+		FullClient,
+		sp_consensus::DisableProofRecording,
+	>,
+	task_manager: &TaskManager,
+) -> Result<(), ServiceError> {
+	let triggers: Pin<Box<dyn Stream<Item = EngineCommand<Hash>> + Send>> = match sealing {
+		Sealing::Manual => Box::pin(futures::stream::pending()),
+		Sealing::Instant =>
+			Box::pin(transaction_pool.import_notification_stream().map(|_| seal_command(false))),
+		Sealing::Interval(millis) => Box::pin(futures::stream::unfold((), move |()| async move {
+			futures_timer::Delay::new(Duration::from_millis(millis)).await;
+			Some((seal_command(true), ()))
+		})),
+	};
+
+	let slot_duration =
+		sc_consensus_babe::Config::get_or_compute(&*client)?.slot_duration().as_millis() as u64;
+	let inherent_client = client.clone();
+
+	let authorship = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+		block_import: client.clone(),
+		env: proposer_factory,
+		client,
+		pool: transaction_pool.pool().clone(),
+		commands_stream: futures::stream::select(rpc_commands, triggers),
+		select_chain,
+		consensus_data_provider: Some(Box::new(BabeDigestProvider { slot_duration })),
+		create_inherent_data_providers: move |parent, ()| {
+			let client = inherent_client.clone();
+			async move {
+				// Blocks may be sealed faster than wall-clock slots pass, so move the timestamp
+				// at least one slot past the parent's to keep the BABE slot increasing.
+				let parent_slot: u64 = client
+					.header(BlockId::Hash(parent))?
+					.and_then(|header| {
+						header.digest().logs().iter().find_map(|log| log.as_babe_pre_digest())
+					})
+					.map_or(0, |pre_digest| *pre_digest.slot());
+				let now = SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.expect("Current time is always after unix epoch; qed")
+					.as_millis() as u64;
+				let timestamp = now.max((parent_slot + 1) * slot_duration);
+
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>(
+					sp_timestamp::InherentDataProvider::new(timestamp.into()),
+				)
+			}
+		},
+	});
+
+	// the manual-seal authoring task is considered essential, i.e. if it
+	// fails we take down the service with it.
+	task_manager.spawn_essential_handle().spawn_blocking("manual-seal", authorship);
+
+	Ok(())
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	sealing: Option<Sealing>,
+	indexer_db: Option<PathBuf>,
+	keystore_token: Option<String>,
+) -> Result<TaskManager, ServiceError> {
+	if sealing.is_some() && config.chain_spec.chain_type() == ChainType::Live {
+		return Err(ServiceError::Other(
+			"Sealing is only available on development and local chains.".into(),
+		))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -147,12 +318,12 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		mut keystore_container,
 		select_chain,
 		transaction_pool,
-		other: (block_import, grandpa_link, mut telemetry),
-	} = new_partial(&config)?;
+		other: (block_import, grandpa_link, babe_link, mut telemetry),
+	} = new_partial(&config, sealing)?;
 
 	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
-			Ok(k) => keystore_container.set_remote_keystore(k),
+		match RemoteKeystore::open(url, keystore_token.as_deref()) {
+			Ok(k) => keystore_container.set_remote_keystore(Arc::new(k)),
 			Err(e) =>
 				return Err(ServiceError::Other(format!(
 					"Error hooking up remote keystore for {}: {}",
@@ -174,6 +345,22 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			block_announce_validator_builder: None,
 		})?;
 
+	let frontier_backend = open_frontier_backend(&config).map_err(ServiceError::Other)?;
+
+	// Records the Ethereum blocks of imported blocks in Frontier's database, so `eth_*` RPCs can
+	// find blocks and transactions by their Ethereum hashes.
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-mapping-sync-worker",
+		MappingSyncWorker::new(
+			client.import_notification_stream(),
+			babe_link.config().slot_duration(),
+			client.clone(),
+			backend.clone(),
+			frontier_backend.clone(),
+		)
+		.for_each(|()| futures::future::ready(())),
+	);
+
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config,
@@ -190,13 +377,53 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let indexer = match indexer_db {
+		Some(path) => {
+			let db = crate::indexer::IndexerDb::open(&path).map_err(|e| {
+				ServiceError::Other(format!(
+					"Failed to open indexer database {}: {}",
+					path.display(),
+					e
+				))
+			})?;
+			let db = Arc::new(db);
+			task_manager.spawn_handle().spawn_blocking(
+				"indexer",
+				crate::indexer::run(client.clone(), backend.clone(), db.clone()),
+			);
+			Some(db)
+		},
+		None => None,
+	};
+
+	let (command_sink, rpc_commands) = match sealing {
+		Some(_) => {
+			let (sink, stream) = mpsc::channel(1024);
+			(Some(sink), Some(stream))
+		},
+		None => (None, None),
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let backend = backend.clone();
+		let network = network.clone();
+		let is_authority = config.role.is_authority();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				backend: backend.clone(),
+				deny_unsafe,
+				subscription_executor,
+				indexer: indexer.clone(),
+				command_sink: command_sink.clone(),
+				network: network.clone(),
+				frontier_backend: frontier_backend.clone(),
+				is_authority,
+			};
 
 			crate::rpc::create_full(deps)
 		})
@@ -217,6 +444,29 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	if let (Some(sealing), Some(rpc_commands)) = (sealing, rpc_commands) {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		start_manual_seal(
+			sealing,
+			rpc_commands,
+			client,
+			select_chain,
+			transaction_pool,
+			proposer_factory,
+			&task_manager,
+		)?;
+
+		network_starter.start_network();
+		return Ok(task_manager)
+	}
+
 	if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
@@ -229,42 +479,39 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
-		let raw_slot_duration = slot_duration.slot_duration();
+		let slot_duration = babe_link.config().slot_duration();
 
-		let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _, _>(
-			StartAuraParams {
-				slot_duration,
-				client: client.clone(),
-				select_chain,
-				block_import,
-				proposer_factory,
-				create_inherent_data_providers: move |_, ()| async move {
-					let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-					let slot =
-						sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_duration(
-							*timestamp,
-							raw_slot_duration,
-						);
-
-					Ok((timestamp, slot))
-				},
-				force_authoring,
-				backoff_authoring_blocks,
-				keystore: keystore_container.sync_keystore(),
-				can_author_with,
-				sync_oracle: network.clone(),
-				justification_sync_link: network.clone(),
-				block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
-				max_block_proposal_slot_portion: None,
-				telemetry: telemetry.as_ref().map(|x| x.handle()),
+		let babe = sc_consensus_babe::start_babe(BabeParams {
+			keystore: keystore_container.sync_keystore(),
+			client: client.clone(),
+			select_chain,
+			env: proposer_factory,
+			block_import,
+			sync_oracle: network.clone(),
+			justification_sync_link: network.clone(),
+			create_inherent_data_providers: move |_, ()| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+				let slot =
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
+						*timestamp,
+						slot_duration,
+					);
+
+				Ok((timestamp, slot))
 			},
-		)?;
+			force_authoring,
+			backoff_authoring_blocks,
+			babe_link,
+			can_author_with,
+			block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
+			max_block_proposal_slot_portion: None,
+			telemetry: telemetry.as_ref().map(|x| x.handle()),
+		})?;
 
-		// the AURA authoring task is considered essential, i.e. if it
+		// the BABE authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking("aura", aura);
+		task_manager.spawn_essential_handle().spawn_blocking("babe-proposer", babe);
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -272,10 +519,10 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let keystore =
 		if role.is_authority() { Some(keystore_container.sync_keystore()) } else { None };
 
+	let grandpa_params = Extensions::of(&*config.chain_spec).grandpa;
 	let grandpa_config = sc_finality_grandpa::Config {
-		// FIXME #1578 make this available through chainspec
-		gossip_duration: Duration::from_millis(333),
-		justification_period: 512,
+		gossip_duration: Duration::from_millis(grandpa_params.gossip_duration),
+		justification_period: grandpa_params.justification_period,
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -355,30 +602,37 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry.as_ref().map(|x| x.handle()),
 	)?;
 
-	let slot_duration = sc_consensus_aura::slot_duration(&*client)?.slot_duration();
-
-	let import_queue =
-		sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _, _>(ImportQueueParams {
-			block_import: grandpa_block_import.clone(),
-			justification_import: Some(Box::new(grandpa_block_import.clone())),
-			client: client.clone(),
-			create_inherent_data_providers: move |_, ()| async move {
-				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+	let justification_import = grandpa_block_import.clone();
 
-				let slot =
-					sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_duration(
-						*timestamp,
-						slot_duration,
-					);
+	let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+		sc_consensus_babe::Config::get_or_compute(&*client)?,
+		grandpa_block_import,
+		client.clone(),
+	)?;
+	let slot_duration = babe_link.config().slot_duration();
 
-				Ok((timestamp, slot))
-			},
-			spawner: &task_manager.spawn_essential_handle(),
-			can_author_with: sp_consensus::NeverCanAuthor,
-			registry: config.prometheus_registry(),
-			check_for_equivocation: Default::default(),
-			telemetry: telemetry.as_ref().map(|x| x.handle()),
-		})?;
+	let import_queue = sc_consensus_babe::import_queue(
+		babe_link,
+		babe_block_import,
+		Some(Box::new(justification_import)),
+		client.clone(),
+		select_chain.clone(),
+		move |_, ()| async move {
+			let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+			let slot =
+				sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
+					*timestamp,
+					slot_duration,
+				);
+
+			Ok((timestamp, slot))
+		},
+		&task_manager.spawn_essential_handle(),
+		config.prometheus_registry(),
+		sp_consensus::NeverCanAuthor,
+		telemetry.as_ref().map(|x| x.handle()),
+	)?;
 
 	let (network, system_rpc_tx, network_starter) =
 		sc_service::build_network(sc_service::BuildNetworkParams {
@@ -403,10 +657,11 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	if enable_grandpa {
 		let name = config.network.node_name.clone();
+		let grandpa_params = Extensions::of(&*config.chain_spec).grandpa;
 
 		let config = sc_finality_grandpa::Config {
-			gossip_duration: std::time::Duration::from_millis(333),
-			justification_period: 512,
+			gossip_duration: Duration::from_millis(grandpa_params.gossip_duration),
+			justification_period: grandpa_params.justification_period,
 			name: Some(name),
 			observer_enabled: false,
 			keystore: None,
@@ -420,12 +675,20 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	let light_deps = crate::rpc::LightDeps {
+		client: client.clone(),
+		pool: transaction_pool.clone(),
+		remote_blockchain: backend.remote_blockchain(),
+		fetcher: on_demand.clone(),
+	};
+	let rpc_extensions = crate::rpc::create_light(light_deps);
+
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		remote_blockchain: Some(backend.remote_blockchain()),
 		transaction_pool,
 		task_manager: &mut task_manager,
 		on_demand: Some(on_demand),
-		rpc_extensions_builder: Box::new(|_, _| ()),
+		rpc_extensions_builder: Box::new(sc_service::NoopRpcExtensionBuilder(rpc_extensions)),
 		config,
 		client,
 		keystore: keystore_container.sync_keystore(),
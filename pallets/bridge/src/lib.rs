@@ -0,0 +1,387 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Lock-and-mint bridge from an external EVM chain.
+//!
+//! Tokens locked in the bridge contract on the external chain are minted here as wrapped assets
+//! once enough relayers voted for the deposit. Burning wrapped assets here emits a
+//! `WithdrawalRequested` event, on which relayers release the tokens on the external chain.
+//!
+//! Governance sets the relayers, the number of votes a deposit needs, and the external tokens
+//! bridged to each local asset with a limit on how much of it can be minted per `LimitPeriod`.
+//! Every deposit carries the nonce the bridge contract assigned to it; a nonce is minted at most
+//! once. Votes that did not reach the threshold in time are pruned in `on_initialize`.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+mod simulator;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::tokens::fungibles::{Inspect, Mutate},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Hash, One, Saturating, Zero};
+	use sp_std::vec::Vec;
+
+	/// Address of an account or contract on the external chain.
+	pub type ExternalAddress = [u8; 20];
+	/// Sequence number the bridge contract assigns to every deposit.
+	pub type DepositNonce = u64;
+
+	pub type AssetIdOf<T> =
+		<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+	pub type BalanceOf<T> =
+		<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// Tokens locked on the external chain, to be minted here.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Deposit<AccountId, Balance> {
+		/// Nonce assigned by the bridge contract.
+		pub nonce: DepositNonce,
+		/// The external token that was locked.
+		pub token: ExternalAddress,
+		/// The account to mint the wrapped asset to.
+		pub recipient: AccountId,
+		/// Amount to mint.
+		pub amount: Balance,
+	}
+
+	/// Votes collected for a deposit.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Proposal<AccountId, BlockNumber> {
+		/// Relayers who voted for the deposit.
+		pub votes: Vec<AccountId>,
+		/// Block after which the votes are discarded.
+		pub expiry: BlockNumber,
+	}
+
+	/// An external token bridged to a local asset.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct BridgedAsset<AssetId, Balance, BlockNumber> {
+		/// The local asset minted for deposits of the token.
+		pub asset: AssetId,
+		/// Maximum amount minted per `LimitPeriod`.
+		pub limit: Balance,
+		/// Start of the current period.
+		pub period_start: BlockNumber,
+		/// Amount minted since `period_start`.
+		pub minted: Balance,
+	}
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The assets wrapped tokens are minted in and burned from.
+		type Assets: Mutate<Self::AccountId>;
+
+		/// Origin allowed to change relayers, thresholds and bridged assets.
+		type BridgeOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of relayers.
+		#[pallet::constant]
+		type MaxRelayers: Get<u32>;
+
+		/// Number of blocks over which the mint limit of an asset applies.
+		#[pallet::constant]
+		type LimitPeriod: Get<Self::BlockNumber>;
+
+		/// Number of blocks a deposit has to collect enough votes, counted from the first one.
+		#[pallet::constant]
+		type ProposalLifetime: Get<Self::BlockNumber>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Accounts allowed to vote for deposits, sorted.
+	#[pallet::storage]
+	#[pallet::getter(fn relayers)]
+	pub type Relayers<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
+	/// Number of relayer votes needed to mint a deposit.
+	#[pallet::storage]
+	#[pallet::getter(fn threshold)]
+	pub type Threshold<T> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn bridged_asset)]
+	pub type BridgedAssets<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		ExternalAddress,
+		BridgedAsset<AssetIdOf<T>, BalanceOf<T>, T::BlockNumber>,
+	>;
+
+	/// Votes by deposit nonce and hash of the deposit, so relayers disagreeing on a deposit do
+	/// not add up.
+	#[pallet::storage]
+	#[pallet::getter(fn proposal)]
+	pub type Proposals<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		DepositNonce,
+		Identity,
+		T::Hash,
+		Proposal<T::AccountId, T::BlockNumber>,
+	>;
+
+	/// Proposals to prune at a block, the one after they expire, processed in `on_initialize`.
+	#[pallet::storage]
+	#[pallet::getter(fn expiring)]
+	pub type Expiring<T: Config> =
+		StorageMap<_, Twox64Concat, T::BlockNumber, Vec<(DepositNonce, T::Hash)>, ValueQuery>;
+
+	/// Block each deposit nonce was minted in.
+	#[pallet::storage]
+	#[pallet::getter(fn executed)]
+	pub type ExecutedDeposits<T: Config> =
+		StorageMap<_, Twox64Concat, DepositNonce, T::BlockNumber>;
+
+	/// Nonce of the next withdrawal.
+	#[pallet::storage]
+	#[pallet::getter(fn withdrawal_nonce)]
+	pub type WithdrawalNonce<T> = StorageValue<_, u64, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		AssetIdOf<T> = "AssetId",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The relayer set changed. [relayers, threshold]
+		RelayersSet(Vec<T::AccountId>, u32),
+		/// An external token was bridged to a local asset. [token, asset, limit]
+		AssetRegistered(ExternalAddress, AssetIdOf<T>, BalanceOf<T>),
+		/// The mint limit of a bridged token changed. [token, limit]
+		LimitSet(ExternalAddress, BalanceOf<T>),
+		/// A relayer voted for a deposit. [nonce, relayer]
+		Voted(DepositNonce, T::AccountId),
+		/// A deposit collected enough votes and was minted. [nonce, asset, recipient, amount]
+		Minted(DepositNonce, AssetIdOf<T>, T::AccountId, BalanceOf<T>),
+		/// Wrapped tokens were burned, to be released on the external chain.
+		/// [nonce, token, sender, recipient, amount]
+		WithdrawalRequested(u64, ExternalAddress, T::AccountId, ExternalAddress, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// More relayers than `MaxRelayers`.
+		TooManyRelayers,
+		/// The threshold is zero or higher than the number of relayers.
+		InvalidThreshold,
+		/// The sender is not a relayer.
+		NotRelayer,
+		/// The token is not bridged.
+		UnknownToken,
+		/// The token is already bridged.
+		AlreadyRegistered,
+		/// The deposit with this nonce was already minted.
+		AlreadyExecuted,
+		/// The relayer already voted for this deposit.
+		AlreadyVoted,
+		/// Minting the deposit would exceed the limit of the current period.
+		LimitExceeded,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Remove the proposals that expired in the previous block. Proposals voted for again
+		/// since were restarted with a later expiry and are kept.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let expired = Expiring::<T>::take(now);
+			for (nonce, hash) in expired.iter() {
+				let restarted = Proposals::<T>::get(nonce, hash)
+					.map_or(false, |proposal| proposal.expiry >= now);
+				if !restarted {
+					Proposals::<T>::remove(nonce, hash);
+				}
+			}
+			let pruned = expired.len() as Weight;
+			T::DbWeight::get().reads_writes(1 + pruned, 1 + pruned)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the relayers and the number of votes a deposit needs. Votes of accounts that
+		/// are no longer relayers stop counting.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		pub fn set_relayers(
+			origin: OriginFor<T>,
+			mut relayers: Vec<T::AccountId>,
+			threshold: u32,
+		) -> DispatchResult {
+			T::BridgeOrigin::ensure_origin(origin)?;
+			relayers.sort();
+			relayers.dedup();
+			ensure!(relayers.len() as u32 <= T::MaxRelayers::get(), Error::<T>::TooManyRelayers);
+			ensure!(
+				threshold > 0 && threshold as usize <= relayers.len(),
+				Error::<T>::InvalidThreshold
+			);
+
+			Relayers::<T>::put(&relayers);
+			Threshold::<T>::put(threshold);
+
+			Self::deposit_event(Event::RelayersSet(relayers, threshold));
+			Ok(())
+		}
+
+		/// Mint `asset` for deposits of the external `token`, at most `limit` per `LimitPeriod`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn register_asset(
+			origin: OriginFor<T>,
+			token: ExternalAddress,
+			asset: AssetIdOf<T>,
+			limit: BalanceOf<T>,
+		) -> DispatchResult {
+			T::BridgeOrigin::ensure_origin(origin)?;
+			ensure!(!BridgedAssets::<T>::contains_key(token), Error::<T>::AlreadyRegistered);
+
+			BridgedAssets::<T>::insert(
+				token,
+				BridgedAsset {
+					asset,
+					limit,
+					period_start: frame_system::Pallet::<T>::block_number(),
+					minted: Zero::zero(),
+				},
+			);
+
+			Self::deposit_event(Event::AssetRegistered(token, asset, limit));
+			Ok(())
+		}
+
+		/// Change how much of a bridged token can be minted per `LimitPeriod`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_limit(
+			origin: OriginFor<T>,
+			token: ExternalAddress,
+			limit: BalanceOf<T>,
+		) -> DispatchResult {
+			T::BridgeOrigin::ensure_origin(origin)?;
+
+			BridgedAssets::<T>::try_mutate(token, |bridged| {
+				let bridged = bridged.as_mut().ok_or(Error::<T>::UnknownToken)?;
+				bridged.limit = limit;
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::LimitSet(token, limit));
+			Ok(())
+		}
+
+		/// Vote for minting `deposit`. The vote reaching the threshold mints the deposit, and
+		/// fails without being counted if that would exceed the token's limit; it can be cast
+		/// again in a later period. Votes expire `ProposalLifetime` blocks after the first one.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6, 5))]
+		pub fn vote_deposit(
+			origin: OriginFor<T>,
+			deposit: Deposit<T::AccountId, BalanceOf<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let relayers = Relayers::<T>::get();
+			ensure!(relayers.binary_search(&who).is_ok(), Error::<T>::NotRelayer);
+			ensure!(
+				!ExecutedDeposits::<T>::contains_key(deposit.nonce),
+				Error::<T>::AlreadyExecuted
+			);
+			ensure!(BridgedAssets::<T>::contains_key(deposit.token), Error::<T>::UnknownToken);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let hash = T::Hashing::hash_of(&deposit);
+			let current =
+				Proposals::<T>::get(deposit.nonce, hash).filter(|proposal| proposal.expiry >= now);
+			let is_new = current.is_none();
+			let mut proposal = current.unwrap_or_else(|| Proposal {
+				votes: Vec::new(),
+				expiry: now.saturating_add(T::ProposalLifetime::get()),
+			});
+			ensure!(!proposal.votes.contains(&who), Error::<T>::AlreadyVoted);
+			proposal.votes.push(who.clone());
+
+			let votes = proposal.votes.iter().filter(|v| relayers.binary_search(v).is_ok()).count();
+			if votes as u32 >= Threshold::<T>::get() {
+				Self::execute(&deposit, now)?;
+				for _ in Proposals::<T>::drain_prefix(deposit.nonce) {}
+			} else {
+				if is_new {
+					Expiring::<T>::append(
+						proposal.expiry.saturating_add(One::one()),
+						(deposit.nonce, hash),
+					);
+				}
+				Proposals::<T>::insert(deposit.nonce, hash, proposal);
+			}
+
+			Self::deposit_event(Event::Voted(deposit.nonce, who));
+			Ok(())
+		}
+
+		/// Burn `amount` of the asset bridged to `token` and have relayers release it to
+		/// `recipient` on the external chain.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			token: ExternalAddress,
+			amount: BalanceOf<T>,
+			recipient: ExternalAddress,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let bridged = BridgedAssets::<T>::get(token).ok_or(Error::<T>::UnknownToken)?;
+			T::Assets::burn_from(bridged.asset, &who, amount)?;
+
+			let nonce = WithdrawalNonce::<T>::get();
+			WithdrawalNonce::<T>::put(nonce.wrapping_add(1));
+
+			Self::deposit_event(Event::WithdrawalRequested(nonce, token, who, recipient, amount));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Mint `deposit` within the limit of its token.
+		fn execute(
+			deposit: &Deposit<T::AccountId, BalanceOf<T>>,
+			now: T::BlockNumber,
+		) -> DispatchResult {
+			let mut bridged =
+				BridgedAssets::<T>::get(deposit.token).ok_or(Error::<T>::UnknownToken)?;
+			if now >= bridged.period_start.saturating_add(T::LimitPeriod::get()) {
+				bridged.period_start = now;
+				bridged.minted = Zero::zero();
+			}
+			let minted = bridged.minted.saturating_add(deposit.amount);
+			ensure!(minted <= bridged.limit, Error::<T>::LimitExceeded);
+
+			T::Assets::mint_into(bridged.asset, &deposit.recipient, deposit.amount)?;
+			bridged.minted = minted;
+			BridgedAssets::<T>::insert(deposit.token, &bridged);
+			ExecutedDeposits::<T>::insert(deposit.nonce, now);
+
+			Self::deposit_event(Event::Minted(
+				deposit.nonce,
+				bridged.asset,
+				deposit.recipient.clone(),
+				deposit.amount,
+			));
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,98 @@
+//! A stand-in for the external chain and its relayers, for tests of the bridge.
+
+use std::convert::TryInto;
+
+use crate::{BalanceOf, Config, Deposit, DepositNonce, Event, ExternalAddress};
+use frame_support::dispatch::DispatchResult;
+use frame_system::RawOrigin;
+
+/// A withdrawal relayers execute on the external chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Withdrawal<AccountId, Balance> {
+	/// Nonce of the withdrawal.
+	pub nonce: u64,
+	/// The external token to release.
+	pub token: ExternalAddress,
+	/// The account that burned the wrapped asset.
+	pub sender: AccountId,
+	/// The external account to release the token to.
+	pub recipient: ExternalAddress,
+	/// Amount to release.
+	pub amount: Balance,
+}
+
+/// Locks tokens in the bridge contract of the external chain and relays deposits and withdrawals
+/// the way relayers do. Calls are dispatched directly, so it must run inside externalities.
+pub struct RelayerSimulator<T: Config> {
+	relayers: Vec<T::AccountId>,
+	next_deposit: DepositNonce,
+	next_withdrawal: u64,
+}
+
+impl<T: Config> RelayerSimulator<T>
+where
+	<T as Config>::Event: TryInto<Event<T>>,
+{
+	/// A simulator voting with `relayers`, in order.
+	pub fn new(relayers: Vec<T::AccountId>) -> Self {
+		Self { relayers, next_deposit: 0, next_withdrawal: 0 }
+	}
+
+	/// Lock `amount` of `token` for `recipient`, returning the deposit the bridge contract emits.
+	pub fn lock(
+		&mut self,
+		token: ExternalAddress,
+		recipient: T::AccountId,
+		amount: BalanceOf<T>,
+	) -> Deposit<T::AccountId, BalanceOf<T>> {
+		let nonce = self.next_deposit;
+		self.next_deposit += 1;
+		Deposit { nonce, token, recipient, amount }
+	}
+
+	/// Have the first `count` relayers vote for `deposit`, stopping at the first failed vote.
+	pub fn relay(
+		&self,
+		deposit: &Deposit<T::AccountId, BalanceOf<T>>,
+		count: usize,
+	) -> DispatchResult {
+		for relayer in self.relayers.iter().take(count) {
+			crate::Pallet::<T>::vote_deposit(
+				RawOrigin::Signed(relayer.clone()).into(),
+				deposit.clone(),
+			)?;
+		}
+		Ok(())
+	}
+
+	/// Lock `amount` of `token` for `recipient` and have as many relayers as the threshold
+	/// requires vote for it.
+	pub fn bridge(
+		&mut self,
+		token: ExternalAddress,
+		recipient: T::AccountId,
+		amount: BalanceOf<T>,
+	) -> DispatchResult {
+		let deposit = self.lock(token, recipient, amount);
+		self.relay(&deposit, crate::Pallet::<T>::threshold() as usize)
+	}
+
+	/// Withdrawals requested in the events of the current block since the previous call.
+	pub fn withdrawals(&mut self) -> Vec<Withdrawal<T::AccountId, BalanceOf<T>>> {
+		let withdrawals: Vec<_> = frame_system::Pallet::<T>::events()
+			.into_iter()
+			.filter_map(|record| {
+				match <T as Config>::Event::from(record.event).try_into().ok()? {
+					Event::WithdrawalRequested(nonce, token, sender, recipient, amount) =>
+						Some(Withdrawal { nonce, token, sender, recipient, amount }),
+					_ => None,
+				}
+			})
+			.filter(|withdrawal| withdrawal.nonce >= self.next_withdrawal)
+			.collect();
+		if let Some(last) = withdrawals.last() {
+			self.next_withdrawal = last.nonce + 1;
+		}
+		withdrawals
+	}
+}
@@ -0,0 +1,232 @@
+use crate::{mock::*, simulator::RelayerSimulator, Error};
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash},
+	DispatchError,
+};
+
+fn simulator() -> RelayerSimulator<Test> {
+	RelayerSimulator::new(RELAYERS.to_vec())
+}
+
+#[test]
+fn relayers_are_set_by_bridge_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Bridge::set_relayers(Origin::signed(1), vec![1, 2], 1),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Bridge::set_relayers(Origin::root(), vec![1, 2], 0),
+			Error::<Test>::InvalidThreshold
+		);
+		// Duplicates count once.
+		assert_noop!(
+			Bridge::set_relayers(Origin::root(), vec![1, 1], 2),
+			Error::<Test>::InvalidThreshold
+		);
+		assert_noop!(
+			Bridge::set_relayers(Origin::root(), vec![1, 2, 3, 4, 5], 3),
+			Error::<Test>::TooManyRelayers
+		);
+
+		assert_ok!(Bridge::set_relayers(Origin::root(), vec![3, 1, 2], 3));
+		assert_eq!(Bridge::relayers(), vec![1, 2, 3]);
+		assert_eq!(Bridge::threshold(), 3);
+	});
+}
+
+#[test]
+fn assets_are_registered_once() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Bridge::register_asset(Origin::root(), TOKEN, 1, 10),
+			Error::<Test>::AlreadyRegistered
+		);
+		assert_noop!(Bridge::set_limit(Origin::root(), [1; 20], 10), Error::<Test>::UnknownToken);
+		assert_ok!(Bridge::set_limit(Origin::root(), TOKEN, 10));
+		assert_eq!(Bridge::bridged_asset(TOKEN).unwrap().limit, 10);
+	});
+}
+
+#[test]
+fn deposit_is_minted_at_threshold() {
+	new_test_ext().execute_with(|| {
+		let mut relayers = simulator();
+		let deposit = relayers.lock(TOKEN, 5, 100);
+
+		assert_ok!(relayers.relay(&deposit, 1));
+		assert_eq!(Assets::balance(0, 5), 0);
+		assert!(Bridge::proposal(0, BlakeTwo256::hash_of(&deposit)).is_some());
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit.clone()));
+		assert_eq!(Assets::balance(0, 5), 100);
+		assert_eq!(Bridge::executed(0), Some(1));
+		assert!(Bridge::proposal(0, BlakeTwo256::hash_of(&deposit)).is_none());
+	});
+}
+
+#[test]
+fn votes_are_checked() {
+	new_test_ext().execute_with(|| {
+		let deposit = simulator().lock(TOKEN, 5, 100);
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(1), deposit.clone()),
+			Error::<Test>::NotRelayer
+		);
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()));
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()),
+			Error::<Test>::AlreadyVoted
+		);
+
+		let mut unknown = deposit;
+		unknown.token = [1; 20];
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(RELAYERS[1]), unknown),
+			Error::<Test>::UnknownToken
+		);
+	});
+}
+
+#[test]
+fn executed_nonce_cannot_be_replayed() {
+	new_test_ext().execute_with(|| {
+		let mut relayers = simulator();
+		let deposit = relayers.lock(TOKEN, 5, 100);
+		assert_ok!(relayers.relay(&deposit, 2));
+
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(RELAYERS[2]), deposit.clone()),
+			Error::<Test>::AlreadyExecuted
+		);
+		// Changing the content does not make the nonce usable again.
+		let mut replay = deposit;
+		replay.recipient = 6;
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(RELAYERS[2]), replay),
+			Error::<Test>::AlreadyExecuted
+		);
+		assert_eq!(Assets::balance(0, 5), 100);
+		assert_eq!(Assets::balance(0, 6), 0);
+	});
+}
+
+#[test]
+fn conflicting_votes_do_not_add_up() {
+	new_test_ext().execute_with(|| {
+		let deposit = simulator().lock(TOKEN, 5, 100);
+		let mut forged = deposit.clone();
+		forged.amount = 1_000;
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()));
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), forged));
+		assert_eq!(Assets::balance(0, 5), 0);
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[2]), deposit));
+		assert_eq!(Assets::balance(0, 5), 100);
+	});
+}
+
+#[test]
+fn votes_of_removed_relayers_do_not_count() {
+	new_test_ext().execute_with(|| {
+		let deposit = simulator().lock(TOKEN, 5, 100);
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()));
+
+		assert_ok!(Bridge::set_relayers(Origin::root(), vec![RELAYERS[1], RELAYERS[2]], 2));
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit.clone()));
+		assert_eq!(Assets::balance(0, 5), 0);
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[2]), deposit));
+		assert_eq!(Assets::balance(0, 5), 100);
+	});
+}
+
+#[test]
+fn votes_expire() {
+	new_test_ext().execute_with(|| {
+		let deposit = simulator().lock(TOKEN, 5, 100);
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()));
+
+		System::set_block_number(7);
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit.clone()));
+		assert_eq!(Assets::balance(0, 5), 0);
+
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit));
+		assert_eq!(Assets::balance(0, 5), 100);
+	});
+}
+
+#[test]
+fn expired_votes_are_pruned() {
+	new_test_ext().execute_with(|| {
+		let deposit = simulator().lock(TOKEN, 5, 100);
+		let hash = BlakeTwo256::hash_of(&deposit);
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[0]), deposit.clone()));
+		assert_eq!(Bridge::expiring(7), vec![(0, hash)]);
+
+		// Voted for again after expiring, so restarted and kept.
+		System::set_block_number(7);
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit.clone()));
+		Bridge::on_initialize(7);
+		assert!(Bridge::proposal(0, hash).is_some());
+		assert!(Bridge::expiring(7).is_empty());
+
+		Bridge::on_initialize(13);
+		assert!(Bridge::proposal(0, hash).is_none());
+		assert!(Bridge::expiring(13).is_empty());
+	});
+}
+
+#[test]
+fn mints_are_limited_per_period() {
+	new_test_ext().execute_with(|| {
+		let mut relayers = simulator();
+		assert_ok!(relayers.bridge(TOKEN, 5, 600));
+
+		let deposit = relayers.lock(TOKEN, 5, 500);
+		assert_ok!(relayers.relay(&deposit, 1));
+		assert_noop!(
+			Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit.clone()),
+			Error::<Test>::LimitExceeded
+		);
+		assert_ok!(relayers.bridge(TOKEN, 5, 400));
+		assert_eq!(Assets::balance(0, 5), 1_000);
+
+		// The limit resets `LimitPeriod` blocks after the period started. The earlier vote
+		// expired meanwhile, so both votes are needed again.
+		System::set_block_number(11);
+		assert_ok!(relayers.relay(&deposit, 1));
+		assert_ok!(Bridge::vote_deposit(Origin::signed(RELAYERS[1]), deposit));
+		assert_eq!(Assets::balance(0, 5), 1_500);
+	});
+}
+
+#[test]
+fn withdrawals_burn_and_are_relayed() {
+	new_test_ext().execute_with(|| {
+		let mut relayers = simulator();
+		assert_ok!(relayers.bridge(TOKEN, 5, 100));
+
+		assert_noop!(
+			Bridge::withdraw(Origin::signed(5), [1; 20], 10, [7; 20]),
+			Error::<Test>::UnknownToken
+		);
+		assert!(Bridge::withdraw(Origin::signed(5), TOKEN, 101, [7; 20]).is_err());
+
+		assert_ok!(Bridge::withdraw(Origin::signed(5), TOKEN, 30, [7; 20]));
+		assert_ok!(Bridge::withdraw(Origin::signed(5), TOKEN, 20, [8; 20]));
+		assert_eq!(Assets::balance(0, 5), 50);
+
+		let withdrawals = relayers.withdrawals();
+		assert_eq!(withdrawals.len(), 2);
+		assert_eq!(withdrawals[0].nonce, 0);
+		assert_eq!(withdrawals[0].recipient, [7; 20]);
+		assert_eq!(withdrawals[0].amount, 30);
+		assert_eq!(withdrawals[1].nonce, 1);
+		assert_eq!(withdrawals[1].sender, 5);
+		assert!(relayers.withdrawals().is_empty());
+	});
+}
@@ -0,0 +1,508 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Airdrops claimed with Merkle proofs, so allocations to many holders need not be in genesis.
+//!
+//! Governance registers an airdrop with the root of a Merkle tree of allocations, the total they
+//! add up to and a deadline. Each leaf is the Blake2-256 hash of the SCALE encoded
+//! `(index, claimant, amount)`, and each inner node the hash of its two children, the smaller
+//! first, so a proof is the list of sibling hashes from the leaf up, without positions.
+//!
+//! Claims are unsigned transactions, as claimants may not hold tokens to pay fees, and are only
+//! let into the pool if they would succeed:
+//! - an allocation to an account is paid to that account, so anyone can submit its proof;
+//! - an allocation to an Ethereum address is paid to the account its holder names by signing
+//!   `Prefix` followed by the hex encoded account, as an Ethereum signed message.
+//!
+//! Allocations are minted when claimed. Those of an airdrop with a vesting schedule are locked by
+//! this pallet, which keeps a schedule per allocation rather than relying on the single schedule
+//! per account of the vesting pallet, so accounts can claim any number of vested allocations.
+//! Holders `vest` to release what has unlocked, and can pay transaction fees out of what is still
+//! locked. After the deadline, anyone can sweep the unclaimed rest of the total to the treasury,
+//! which removes the airdrop. Airdrop identifiers are not reused.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{Currency, Imbalance, LockIdentifier, LockableCurrency, WithdrawReasons},
+		transactional,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_core::{ecdsa, H160, H256};
+	use sp_io::{
+		crypto::secp256k1_ecdsa_recover,
+		hashing::{blake2_256, keccak_256},
+	};
+	use sp_runtime::traits::{AtLeast32BitUnsigned, Convert, One, Saturating, Zero};
+	use sp_std::prelude::*;
+
+	pub type AirdropId = u32;
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type AirdropOf<T> = Airdrop<BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+	pub type ScheduleOf<T> = Schedule<BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+
+	/// Identifier of the lock on vested allocations.
+	pub const CLAIMS_ID: LockIdentifier = *b"claimvst";
+
+	/// Who an allocation is for.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub enum Claimant<AccountId> {
+		/// An account, paid directly.
+		Account(AccountId),
+		/// An Ethereum address, paid to the account its holder signs for.
+		Ethereum(H160),
+	}
+
+	/// Linear vesting of claimed allocations.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Vesting<BlockNumber> {
+		/// The block allocations start vesting at.
+		pub start: BlockNumber,
+		/// The number of blocks allocations vest over.
+		pub period: BlockNumber,
+	}
+
+	/// The vesting of a claimed allocation.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Schedule<Balance, BlockNumber> {
+		/// The allocation.
+		pub locked: Balance,
+		/// The amount unlocked in each block after `start`.
+		pub per_block: Balance,
+		/// The block the allocation starts vesting at.
+		pub start: BlockNumber,
+	}
+
+	impl<Balance: AtLeast32BitUnsigned + Copy, BlockNumber: AtLeast32BitUnsigned + Copy>
+		Schedule<Balance, BlockNumber>
+	{
+		/// The amount still locked at block `now`.
+		pub fn locked_at<BlockNumberToBalance: Convert<BlockNumber, Balance>>(
+			&self,
+			now: BlockNumber,
+		) -> Balance {
+			let vested = BlockNumberToBalance::convert(now.saturating_sub(self.start));
+			self.locked.saturating_sub(vested.saturating_mul(self.per_block))
+		}
+	}
+
+	/// An airdrop and the amount claimed from it.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Airdrop<Balance, BlockNumber> {
+		/// The root of the Merkle tree of allocations.
+		pub merkle_root: H256,
+		/// The sum of all allocations.
+		pub total: Balance,
+		/// The sum of the allocations claimed so far.
+		pub claimed: Balance,
+		/// The last block allocations can be claimed in.
+		pub deadline: BlockNumber,
+		/// The vesting schedule claimed allocations are locked by, if any.
+		pub vesting: Option<Vesting<BlockNumber>>,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency allocations are minted and locked in.
+		type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>;
+
+		/// Converts vesting periods into balances, to divide allocations by.
+		type BlockNumberToBalance: Convert<Self::BlockNumber, BalanceOf<Self>>;
+
+		/// Origin allowed to register airdrops.
+		type AirdropOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The account unclaimed allocations are swept to.
+		type Treasury: Get<Self::AccountId>;
+
+		/// Text signed by holders of Ethereum addresses, before the account they claim to.
+		#[pallet::constant]
+		type Prefix: Get<&'static [u8]>;
+
+		/// Maximum number of hashes in a proof.
+		#[pallet::constant]
+		type MaxProofLength: Get<u32>;
+
+		/// Priority of claims in the transaction pool.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The identifier of the next airdrop to be registered.
+	#[pallet::storage]
+	#[pallet::getter(fn next_airdrop_id)]
+	pub type NextAirdropId<T> = StorageValue<_, AirdropId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn airdrop)]
+	pub type Airdrops<T: Config> = StorageMap<_, Blake2_128Concat, AirdropId, AirdropOf<T>>;
+
+	/// Whether the allocation at an index of an airdrop was claimed.
+	#[pallet::storage]
+	#[pallet::getter(fn is_claimed)]
+	pub type Claimed<T> =
+		StorageDoubleMap<_, Blake2_128Concat, AirdropId, Blake2_128Concat, u32, bool, ValueQuery>;
+
+	/// The vesting schedules of the allocations an account claimed, by airdrop and index.
+	#[pallet::storage]
+	#[pallet::getter(fn schedule)]
+	pub type Schedules<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		(AirdropId, u32),
+		ScheduleOf<T>,
+	>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		BalanceOf<T> = "Balance",
+		T::BlockNumber = "BlockNumber"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An airdrop was registered. [airdrop, merkle_root, total, deadline]
+		AirdropRegistered(AirdropId, H256, BalanceOf<T>, T::BlockNumber),
+		/// An allocation was claimed. [airdrop, index, account, amount]
+		Claimed(AirdropId, u32, T::AccountId, BalanceOf<T>),
+		/// The unclaimed rest of an airdrop was swept to the treasury. [airdrop, amount]
+		Swept(AirdropId, BalanceOf<T>),
+		/// The lock on the vested allocations of an account changed. [who, locked]
+		Vested(T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The airdrop does not exist.
+		UnknownAirdrop,
+		/// The deadline has passed.
+		DeadlineInPast,
+		/// A vesting schedule must have a period of one block or more.
+		InvalidVesting,
+		/// All airdrop identifiers are in use.
+		NoAvailableId,
+		/// The deadline of the airdrop has passed.
+		ClaimPeriodOver,
+		/// The deadline of the airdrop has not passed yet.
+		ClaimPeriodNotOver,
+		/// The allocation was already claimed.
+		AlreadyClaimed,
+		/// The proof has more than `MaxProofLength` hashes.
+		ProofTooLong,
+		/// The proof does not lead from the allocation to the Merkle root of the airdrop.
+		InvalidProof,
+		/// The signature of the Ethereum claim is malformed.
+		InvalidSignature,
+		/// The allocation would bring the claimed amount above the total of the airdrop.
+		ExceedsTotal,
+		/// The account has no vested allocations.
+		NotVesting,
+		/// The allocation is below the existential deposit and the account does not exist.
+		PayoutBelowMinimum,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register an airdrop of `total` claimable until block `deadline`, with allocations
+		/// committed to by `merkle_root` and optionally locked by a vesting schedule.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn register_airdrop(
+			origin: OriginFor<T>,
+			merkle_root: H256,
+			#[pallet::compact] total: BalanceOf<T>,
+			deadline: T::BlockNumber,
+			vesting: Option<Vesting<T::BlockNumber>>,
+		) -> DispatchResult {
+			T::AirdropOrigin::ensure_origin(origin)?;
+			ensure!(
+				deadline > frame_system::Pallet::<T>::block_number(),
+				Error::<T>::DeadlineInPast
+			);
+			if let Some(vesting) = &vesting {
+				ensure!(!vesting.period.is_zero(), Error::<T>::InvalidVesting);
+			}
+
+			let airdrop = NextAirdropId::<T>::get();
+			let next = airdrop.checked_add(1).ok_or(Error::<T>::NoAvailableId)?;
+			Airdrops::<T>::insert(
+				airdrop,
+				Airdrop { merkle_root, total, claimed: Zero::zero(), deadline, vesting },
+			);
+			NextAirdropId::<T>::put(next);
+
+			Self::deposit_event(Event::AirdropRegistered(airdrop, merkle_root, total, deadline));
+			Ok(())
+		}
+
+		/// Pay the allocation of `amount` at `index` of an airdrop to `who`, proven by the
+		/// sibling hashes `proof`.
+		#[pallet::weight(
+			10_000 + 1_000 * proof.len() as Weight + T::DbWeight::get().reads_writes(5, 5)
+		)]
+		#[transactional]
+		pub fn claim(
+			origin: OriginFor<T>,
+			airdrop: AirdropId,
+			index: u32,
+			who: T::AccountId,
+			#[pallet::compact] amount: BalanceOf<T>,
+			proof: Vec<H256>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			Self::pay(airdrop, index, &Claimant::Account(who.clone()), amount, &proof, who)
+		}
+
+		/// Pay the allocation of `amount` at `index` of an airdrop to an Ethereum address to
+		/// `dest`, with the `signature` of the message returned by `ethereum_message(dest)` by
+		/// the holder of the address.
+		#[pallet::weight(
+			50_000 + 1_000 * proof.len() as Weight + T::DbWeight::get().reads_writes(5, 5)
+		)]
+		#[transactional]
+		pub fn claim_ethereum(
+			origin: OriginFor<T>,
+			airdrop: AirdropId,
+			index: u32,
+			dest: T::AccountId,
+			#[pallet::compact] amount: BalanceOf<T>,
+			proof: Vec<H256>,
+			signature: ecdsa::Signature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			let address =
+				Self::ethereum_signer(&dest, &signature).ok_or(Error::<T>::InvalidSignature)?;
+			Self::pay(airdrop, index, &Claimant::Ethereum(address), amount, &proof, dest)
+		}
+
+		/// Unlock what has vested of the allocations the sender claimed.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn vest(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Schedules::<T>::iter_prefix(&who).next().is_some(), Error::<T>::NotVesting);
+			Self::update_lock(&who);
+			Ok(())
+		}
+
+		/// Mint the unclaimed rest of an airdrop whose deadline has passed to the treasury, and
+		/// remove the airdrop.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn sweep(origin: OriginFor<T>, airdrop: AirdropId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let details = Airdrops::<T>::get(airdrop).ok_or(Error::<T>::UnknownAirdrop)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() > details.deadline,
+				Error::<T>::ClaimPeriodNotOver
+			);
+			let unclaimed = details.total.saturating_sub(details.claimed);
+			let swept = T::Currency::deposit_creating(&T::Treasury::get(), unclaimed).peek();
+			Airdrops::<T>::remove(airdrop);
+
+			Self::deposit_event(Event::Swept(airdrop, swept));
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Claims pay no fees, so only those that would succeed are valid. Each allocation can
+		/// be in the pool once.
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (airdrop, index, result) = match call {
+				Call::claim(airdrop, index, who, amount, proof) => {
+					let claimant = Claimant::Account(who.clone());
+					let result =
+						Self::check_claim(*airdrop, *index, &claimant, *amount, proof, who);
+					(*airdrop, *index, result)
+				},
+				Call::claim_ethereum(airdrop, index, dest, amount, proof, signature) => {
+					let result = Self::ethereum_signer(dest, signature)
+						.ok_or(Error::<T>::InvalidSignature)
+						.and_then(|address| {
+							let claimant = Claimant::Ethereum(address);
+							Self::check_claim(*airdrop, *index, &claimant, *amount, proof, dest)
+						});
+					(*airdrop, *index, result)
+				},
+				_ => return InvalidTransaction::Call.into(),
+			};
+			result.map_err(|error| match error {
+				Error::<T>::AlreadyClaimed => InvalidTransaction::Stale,
+				Error::<T>::ClaimPeriodOver => InvalidTransaction::Outdated,
+				Error::<T>::InvalidProof | Error::<T>::InvalidSignature =>
+					InvalidTransaction::BadProof,
+				_ => InvalidTransaction::Call,
+			})?;
+
+			ValidTransaction::with_tag_prefix("Claims")
+				.priority(T::UnsignedPriority::get())
+				.and_provides((airdrop, index))
+				.build()
+		}
+	}
+
+	/// The leaf of the allocation of `amount` at `index` to `claimant`.
+	pub fn leaf<AccountId: Encode, Balance: Encode>(
+		index: u32,
+		claimant: &Claimant<AccountId>,
+		amount: Balance,
+	) -> H256 {
+		H256((index, claimant, amount).using_encoded(blake2_256))
+	}
+
+	/// The inner node with children `a` and `b`.
+	pub fn hash_pair(a: H256, b: H256) -> H256 {
+		let (first, second) = if a <= b { (a, b) } else { (b, a) };
+		H256(blake2_256(&[first.as_bytes(), second.as_bytes()].concat()))
+	}
+
+	/// The root reached from `leaf` through the sibling hashes `proof`.
+	pub fn merkle_root(leaf: H256, proof: &[H256]) -> H256 {
+		proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling))
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The message holders of Ethereum addresses sign to claim to `dest`: `Prefix` and `dest`
+		/// in hex, after the header of Ethereum signed messages.
+		pub fn ethereum_message(dest: &T::AccountId) -> Vec<u8> {
+			const HEX: &[u8; 16] = b"0123456789abcdef";
+
+			let mut payload = T::Prefix::get().to_vec();
+			for byte in dest.encode() {
+				payload.push(HEX[(byte >> 4) as usize]);
+				payload.push(HEX[(byte & 0xf) as usize]);
+			}
+
+			let mut length = Vec::new();
+			let mut remaining = payload.len();
+			loop {
+				length.insert(0, b'0' + (remaining % 10) as u8);
+				remaining /= 10;
+				if remaining == 0 {
+					break
+				}
+			}
+
+			let mut message = b"\x19Ethereum Signed Message:\n".to_vec();
+			message.extend(length);
+			message.extend(payload);
+			message
+		}
+
+		/// The Ethereum address whose key signed the claim to `dest`.
+		fn ethereum_signer(dest: &T::AccountId, signature: &ecdsa::Signature) -> Option<H160> {
+			let hash = keccak_256(&Self::ethereum_message(dest));
+			let public = secp256k1_ecdsa_recover(&signature.0, &hash).ok()?;
+			Some(H160::from_slice(&keccak_256(&public)[12..]))
+		}
+
+		/// Check that the allocation can be claimed to `dest`, returning its airdrop.
+		fn check_claim(
+			airdrop: AirdropId,
+			index: u32,
+			claimant: &Claimant<T::AccountId>,
+			amount: BalanceOf<T>,
+			proof: &[H256],
+			dest: &T::AccountId,
+		) -> Result<AirdropOf<T>, Error<T>> {
+			let details = Airdrops::<T>::get(airdrop).ok_or(Error::<T>::UnknownAirdrop)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= details.deadline,
+				Error::<T>::ClaimPeriodOver
+			);
+			ensure!(!Claimed::<T>::get(airdrop, index), Error::<T>::AlreadyClaimed);
+			ensure!(proof.len() <= T::MaxProofLength::get() as usize, Error::<T>::ProofTooLong);
+			ensure!(
+				merkle_root(leaf(index, claimant, amount), proof) == details.merkle_root,
+				Error::<T>::InvalidProof
+			);
+			ensure!(
+				details.claimed.saturating_add(amount) <= details.total,
+				Error::<T>::ExceedsTotal
+			);
+			// Minting less than the existential deposit to a new account would lose it.
+			ensure!(
+				amount >= T::Currency::minimum_balance() ||
+					!T::Currency::total_balance(dest).is_zero(),
+				Error::<T>::PayoutBelowMinimum
+			);
+			Ok(details)
+		}
+
+		/// Mint the allocation to `dest`, locking it if the airdrop has a vesting schedule.
+		fn pay(
+			airdrop: AirdropId,
+			index: u32,
+			claimant: &Claimant<T::AccountId>,
+			amount: BalanceOf<T>,
+			proof: &[H256],
+			dest: T::AccountId,
+		) -> DispatchResult {
+			let mut details = Self::check_claim(airdrop, index, claimant, amount, proof, &dest)?;
+
+			T::Currency::deposit_creating(&dest, amount);
+			if let Some(vesting) = &details.vesting {
+				// Rounding up vests the allocation in `period` blocks at most.
+				let period = T::BlockNumberToBalance::convert(vesting.period);
+				let per_block = amount.saturating_add(period.saturating_sub(One::one())) / period;
+				let schedule = Schedule { locked: amount, per_block, start: vesting.start };
+				Schedules::<T>::insert(&dest, (airdrop, index), schedule);
+				Self::update_lock(&dest);
+			}
+			details.claimed = details.claimed.saturating_add(amount);
+			Airdrops::<T>::insert(airdrop, details);
+			Claimed::<T>::insert(airdrop, index, true);
+
+			Self::deposit_event(Event::Claimed(airdrop, index, dest, amount));
+			Ok(())
+		}
+
+		/// Lock what is still locked of the vested allocations of `who`, removing the schedules
+		/// of those fully vested.
+		fn update_lock(who: &T::AccountId) {
+			let now = frame_system::Pallet::<T>::block_number();
+			let mut locked = BalanceOf::<T>::zero();
+			let schedules: Vec<_> = Schedules::<T>::iter_prefix(who).collect();
+			for (key, schedule) in schedules {
+				let left = schedule.locked_at::<T::BlockNumberToBalance>(now);
+				if left.is_zero() {
+					Schedules::<T>::remove(who, key);
+				}
+				locked = locked.saturating_add(left);
+			}
+			if locked.is_zero() {
+				T::Currency::remove_lock(CLAIMS_ID, who);
+			} else {
+				// Locked allocations still pay fees, so claimants need no other funds to `vest`.
+				let reasons = WithdrawReasons::except(WithdrawReasons::TRANSACTION_PAYMENT);
+				T::Currency::set_lock(CLAIMS_ID, who, locked, reasons);
+			}
+			Self::deposit_event(Event::Vested(who.clone(), locked));
+		}
+	}
+}
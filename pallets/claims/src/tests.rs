@@ -0,0 +1,330 @@
+use crate::{hash_pair, leaf, mock::*, Claimant, Error, Schedule, Vesting};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{Currency, ExistenceRequirement, WithdrawReasons},
+};
+use sp_core::{ecdsa, keccak_256, Pair, H160, H256};
+use sp_runtime::{
+	traits::ValidateUnsigned,
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	DispatchError,
+};
+
+/// The root of the Merkle tree of `leaves` and the proof of each leaf. A node without a sibling
+/// moves up a level unchanged.
+fn tree(leaves: &[H256]) -> (H256, Vec<Vec<H256>>) {
+	let mut proofs = vec![Vec::new(); leaves.len()];
+	let mut positions: Vec<usize> = (0..leaves.len()).collect();
+	let mut level = leaves.to_vec();
+	while level.len() > 1 {
+		for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+			if let Some(sibling) = level.get(*position ^ 1) {
+				proof.push(*sibling);
+			}
+			*position /= 2;
+		}
+		level = level
+			.chunks(2)
+			.map(|pair| if pair.len() == 2 { hash_pair(pair[0], pair[1]) } else { pair[0] })
+			.collect();
+	}
+	(level[0], proofs)
+}
+
+fn account(who: u64) -> Claimant<u64> {
+	Claimant::Account(who)
+}
+
+fn ethereum_key() -> ecdsa::Pair {
+	ecdsa::Pair::from_seed(&[7; 32])
+}
+
+fn ethereum_address(pair: &ecdsa::Pair) -> H160 {
+	let hash = [0; 32];
+	let public = sp_io::crypto::secp256k1_ecdsa_recover(&pair.sign_prehashed(&hash).0, &hash);
+	H160::from_slice(&keccak_256(&public.unwrap())[12..])
+}
+
+fn sign_claim(pair: &ecdsa::Pair, dest: u64) -> ecdsa::Signature {
+	pair.sign_prehashed(&keccak_256(&Claims::ethereum_message(&dest)))
+}
+
+/// Register an airdrop of the allocations, ending at block 10, and return the proofs.
+fn register(allocations: &[(Claimant<u64>, u64)], vesting: Option<Vesting<u64>>) -> Vec<Vec<H256>> {
+	let leaves: Vec<_> = allocations
+		.iter()
+		.enumerate()
+		.map(|(index, (claimant, amount))| leaf(index as u32, claimant, *amount))
+		.collect();
+	let (root, proofs) = tree(&leaves);
+	let total = allocations.iter().map(|(_, amount)| amount).sum();
+	assert_ok!(Claims::register_airdrop(Origin::root(), root, total, 10, vesting));
+	proofs
+}
+
+fn validate(call: crate::Call<Test>) -> Result<(), TransactionValidityError> {
+	Claims::validate_unsigned(TransactionSource::External, &call).map(|_| ())
+}
+
+#[test]
+fn register_airdrop_checks_origin_and_parameters() {
+	new_test_ext().execute_with(|| {
+		let root = H256::repeat_byte(1);
+		assert_noop!(
+			Claims::register_airdrop(Origin::signed(1), root, 100, 10, None),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Claims::register_airdrop(Origin::root(), root, 100, 1, None),
+			Error::<Test>::DeadlineInPast
+		);
+		assert_noop!(
+			Claims::register_airdrop(
+				Origin::root(),
+				root,
+				100,
+				10,
+				Some(Vesting { start: 1, period: 0 })
+			),
+			Error::<Test>::InvalidVesting
+		);
+
+		assert_ok!(Claims::register_airdrop(Origin::root(), root, 100, 10, None));
+		assert_eq!(Claims::next_airdrop_id(), 1);
+		assert_eq!(Claims::airdrop(0).unwrap().total, 100);
+		// Registering mints nothing until allocations are claimed.
+		assert_eq!(Balances::total_issuance(), 0);
+	});
+}
+
+#[test]
+fn claims_pay_each_allocation_once() {
+	new_test_ext().execute_with(|| {
+		let proofs = register(&[(account(1), 100), (account(2), 50), (account(3), 25)], None);
+
+		// Anyone can submit a claim, which always pays the account in the allocation.
+		let claim = crate::Call::claim(0, 1, 2, 50, proofs[1].clone());
+		assert_ok!(validate(claim.clone()));
+		assert_ok!(Claims::claim(Origin::none(), 0, 1, 2, 50, proofs[1].clone()));
+		assert_eq!(Balances::free_balance(2), 50);
+		assert!(Claims::is_claimed(0, 1));
+		assert_eq!(Claims::airdrop(0).unwrap().claimed, 50);
+
+		assert_eq!(validate(claim), Err(InvalidTransaction::Stale.into()));
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 1, 2, 50, proofs[1].clone()),
+			Error::<Test>::AlreadyClaimed
+		);
+		assert_noop!(
+			Claims::claim(Origin::signed(2), 0, 0, 1, 100, proofs[0].clone()),
+			DispatchError::BadOrigin
+		);
+
+		// The proof commits to the index, account and amount.
+		assert_eq!(
+			validate(crate::Call::claim(0, 0, 1, 101, proofs[0].clone())),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 0, 4, 100, proofs[0].clone()),
+			Error::<Test>::InvalidProof
+		);
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 2, 3, 25, proofs[0].clone()),
+			Error::<Test>::InvalidProof
+		);
+		assert_noop!(
+			Claims::claim(Origin::none(), 1, 0, 1, 100, proofs[0].clone()),
+			Error::<Test>::UnknownAirdrop
+		);
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 0, 1, 100, vec![H256::zero(); 5]),
+			Error::<Test>::ProofTooLong
+		);
+
+		// The last leaf has no sibling, so its proof is one hash shorter.
+		assert_eq!(proofs[2].len(), 1);
+		assert_ok!(Claims::claim(Origin::none(), 0, 2, 3, 25, proofs[2].clone()));
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+		assert_eq!(Balances::total_issuance(), 175);
+	});
+}
+
+#[test]
+fn claims_cannot_exceed_the_total() {
+	new_test_ext().execute_with(|| {
+		let leaves = [leaf(0, &account(1), 100u64), leaf(1, &account(2), 50u64)];
+		let (root, proofs) = tree(&leaves);
+		assert_ok!(Claims::register_airdrop(Origin::root(), root, 120, 10, None));
+
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 1, 2, 50, proofs[1].clone()),
+			Error::<Test>::ExceedsTotal
+		);
+	});
+}
+
+#[test]
+fn ethereum_claims_pay_the_signed_account() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Claims::ethereum_message(&5),
+			b"\x19Ethereum Signed Message:\n23Pay to:0500000000000000".to_vec()
+		);
+
+		let key = ethereum_key();
+		let address = ethereum_address(&key);
+		let proofs = register(&[(Claimant::Ethereum(address), 80), (account(1), 20)], None);
+		let signature = sign_claim(&key, 5);
+		let proof = proofs[0].clone();
+
+		// A signature for another account recovers another address, which has no allocation.
+		assert_eq!(
+			validate(crate::Call::claim_ethereum(0, 0, 6, 80, proof.clone(), signature.clone())),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		assert_noop!(
+			Claims::claim_ethereum(Origin::none(), 0, 0, 6, 80, proof.clone(), signature.clone()),
+			Error::<Test>::InvalidProof
+		);
+		let malformed = ecdsa::Signature::from_raw([0; 65]);
+		assert_noop!(
+			Claims::claim_ethereum(Origin::none(), 0, 0, 5, 80, proof.clone(), malformed),
+			Error::<Test>::InvalidSignature
+		);
+
+		let claim = crate::Call::claim_ethereum(0, 0, 5, 80, proof.clone(), signature.clone());
+		assert_ok!(validate(claim));
+		assert_ok!(Claims::claim_ethereum(Origin::none(), 0, 0, 5, 80, proof, signature));
+		assert_eq!(Balances::free_balance(5), 80);
+		assert!(Claims::is_claimed(0, 0));
+	});
+}
+
+#[test]
+fn vesting_locks_claimed_allocations() {
+	new_test_ext().execute_with(|| {
+		let proofs = register(
+			&[(account(1), 100), (account(2), 35)],
+			Some(Vesting { start: 5, period: 10 }),
+		);
+
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+		assert_eq!(Balances::free_balance(1), 100);
+		assert_eq!(
+			Claims::schedule(1, (0, 0)),
+			Some(Schedule { locked: 100, per_block: 10, start: 5 })
+		);
+		assert_noop!(
+			Balances::transfer(Origin::signed(1), 3, 10),
+			pallet_balances::Error::<Test>::LiquidityRestrictions
+		);
+
+		// Rounding up vests 35 in 9 blocks rather than 12.
+		assert_ok!(Claims::claim(Origin::none(), 0, 1, 2, 35, proofs[1].clone()));
+		assert_eq!(Claims::schedule(2, (0, 1)).unwrap().per_block, 4);
+
+		// Vesting unlocks 10 a block after block 5.
+		assert_noop!(Claims::vest(Origin::signed(3)), Error::<Test>::NotVesting);
+		System::set_block_number(8);
+		assert_ok!(Claims::vest(Origin::signed(1)));
+		assert_ok!(Balances::transfer(Origin::signed(1), 3, 30));
+		assert_noop!(
+			Balances::transfer(Origin::signed(1), 3, 1),
+			pallet_balances::Error::<Test>::LiquidityRestrictions
+		);
+
+		System::set_block_number(15);
+		assert_ok!(Claims::vest(Origin::signed(1)));
+		assert!(Claims::schedule(1, (0, 0)).is_none());
+		assert_ok!(Balances::transfer(Origin::signed(1), 3, 70));
+	});
+}
+
+#[test]
+fn locked_allocations_pay_fees() {
+	new_test_ext().execute_with(|| {
+		let proofs = register(&[(account(1), 100)], Some(Vesting { start: 5, period: 10 }));
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+		assert_eq!(Balances::free_balance(1), 100);
+
+		// The claimant has nothing but their locked allocation to pay the fee of `vest` with.
+		let (fee, keep_alive) = (10, ExistenceRequirement::KeepAlive);
+		let reason = WithdrawReasons::TRANSACTION_PAYMENT;
+		assert!(Balances::withdraw(&1, fee, reason, keep_alive).is_ok());
+		assert_eq!(Balances::free_balance(1), 90);
+		assert!(Balances::withdraw(&1, fee, WithdrawReasons::TRANSFER, keep_alive).is_err());
+	});
+}
+
+#[test]
+fn vested_allocations_add_up() {
+	new_test_ext().execute_with(|| {
+		// Anyone can give an account a schedule of the vesting pallet, which allows one per
+		// account; it doesn't stop the account from claiming vested allocations.
+		let info = pallet_vesting::VestingInfo { locked: 5, per_block: 1, starting_block: 100 };
+		assert_ok!(Balances::set_balance(Origin::root(), 4, 20, 0));
+		assert_ok!(pallet_vesting::Pallet::<Test>::vested_transfer(Origin::signed(4), 1, info));
+
+		let vesting = Some(Vesting { start: 5, period: 10 });
+		let proofs = register(&[(account(1), 100), (account(1), 50)], vesting.clone());
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+		assert_ok!(Claims::claim(Origin::none(), 0, 1, 1, 50, proofs[1].clone()));
+		let leaves = [leaf(0, &account(1), 20u64)];
+		let (root, _) = tree(&leaves);
+		assert_ok!(Claims::register_airdrop(Origin::root(), root, 20, 10, vesting));
+		assert_ok!(Claims::claim(Origin::none(), 1, 0, 1, 20, vec![]));
+		assert_eq!(Balances::free_balance(1), 175);
+
+		// At block 9, 40 of the first, 20 of the second and 8 of the third have vested.
+		System::set_block_number(9);
+		assert_ok!(Claims::vest(Origin::signed(1)));
+		assert_eq!(Balances::locks(1).iter().map(|lock| lock.amount).max(), Some(102));
+	});
+}
+
+#[test]
+fn allocations_below_the_existential_deposit_need_an_existing_account() {
+	new_test_ext().execute_with(|| {
+		let proofs = register(&[(account(1), 3), (account(2), 5)], None);
+
+		let claim = crate::Call::claim(0, 0, 1, 3, proofs[0].clone());
+		assert_eq!(validate(claim.clone()), Err(InvalidTransaction::Call.into()));
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 0, 1, 3, proofs[0].clone()),
+			Error::<Test>::PayoutBelowMinimum
+		);
+		assert_ok!(Claims::claim(Origin::none(), 0, 1, 2, 5, proofs[1].clone()));
+
+		assert_ok!(Balances::set_balance(Origin::root(), 1, 10, 0));
+		assert_ok!(validate(claim));
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 3, proofs[0].clone()));
+		assert_eq!(Balances::free_balance(1), 13);
+	});
+}
+
+#[test]
+fn unclaimed_funds_are_swept_after_the_deadline() {
+	new_test_ext().execute_with(|| {
+		let proofs = register(&[(account(1), 100), (account(2), 50)], None);
+		assert_ok!(Claims::claim(Origin::none(), 0, 0, 1, 100, proofs[0].clone()));
+
+		System::set_block_number(10);
+		assert_noop!(Claims::sweep(Origin::signed(3), 0), Error::<Test>::ClaimPeriodNotOver);
+
+		System::set_block_number(11);
+		let claim = crate::Call::claim(0, 1, 2, 50, proofs[1].clone());
+		assert_eq!(validate(claim), Err(InvalidTransaction::Outdated.into()));
+		assert_noop!(
+			Claims::claim(Origin::none(), 0, 1, 2, 50, proofs[1].clone()),
+			Error::<Test>::ClaimPeriodOver
+		);
+
+		assert_ok!(Claims::sweep(Origin::signed(3), 0));
+		assert_eq!(Balances::free_balance(TreasuryAccount::get()), 50);
+		assert_eq!(Balances::total_issuance(), 150);
+		assert!(Claims::airdrop(0).is_none());
+		assert_noop!(Claims::sweep(Origin::signed(3), 0), Error::<Test>::UnknownAirdrop);
+	});
+}
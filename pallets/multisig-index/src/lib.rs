@@ -0,0 +1,302 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! An index of multisig accounts and their pending operations by signatory, so wallets can show
+//! an account the operations waiting for its approval.
+//!
+//! The multisig pallet stores operations by multisig account, which is derived from the
+//! signatories and threshold, and keeps their details private. This pallet records:
+//! - the multisig accounts of each signatory, from the signatories and threshold named by the
+//!   multisig calls of extrinsics;
+//! - the details of pending operations, from the events of the multisig pallet.
+//!
+//! Both are recorded by the `IndexMultisigs` signed extension, which finds the multisig calls of
+//! an extrinsic, including nested ones, through `Config::FindMultisigCalls`. A multisig account is
+//! only recorded once the multisig pallet accepted an operation or approval for it, which costs
+//! the signatory a deposit or requires an operation opened with one, so extrinsics can't fill the
+//! index with accounts naming arbitrary signatories. The extension registers the weight of its
+//! reads and writes before dispatch. Operations opened by calls that no extrinsic makes, such as
+//! scheduled ones, are not indexed.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		traits::Currency,
+		weights::{DispatchClass, Weight},
+	};
+	use pallet_multisig::Timepoint;
+	use sp_runtime::traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension};
+	use sp_std::{convert::TryInto, fmt, marker::PhantomData, prelude::*};
+
+	pub type CallHash = [u8; 32];
+	pub type BalanceOf<T> = <<T as pallet_multisig::Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::Balance;
+	pub type OperationOf<T> = Operation<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+
+	/// A multisig call and the account it is dispatched from.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct MultisigCall<AccountId> {
+		/// The signatory making the call.
+		pub signatory: AccountId,
+		/// The other signatories of the multisig account.
+		pub other_signatories: Vec<AccountId>,
+		/// The number of approvals operations of the multisig account need.
+		pub threshold: u16,
+	}
+
+	/// Finds the multisig calls a runtime call makes, including those it nests.
+	pub trait FindMultisigCalls<Call, AccountId> {
+		/// The multisig calls `call` makes when dispatched from `who`.
+		fn find(who: &AccountId, call: &Call) -> Vec<MultisigCall<AccountId>>;
+	}
+
+	/// An operation of a multisig account waiting for approvals.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Operation<AccountId, Balance, BlockNumber> {
+		/// The extrinsic that opened the operation. Approvals must refer to it.
+		pub when: Timepoint<BlockNumber>,
+		/// The deposit reserved from the depositor.
+		pub deposit: Balance,
+		/// The signatory who opened the operation.
+		pub depositor: AccountId,
+		/// The signatories who approved the operation so far, sorted.
+		pub approvals: Vec<AccountId>,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_multisig::Config {
+		/// Finds the multisig calls of extrinsics.
+		type FindMultisigCalls: FindMultisigCalls<
+			<Self as frame_system::Config>::Call,
+			Self::AccountId,
+		>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The threshold of each multisig account of a signatory, by signatory and multisig account.
+	#[pallet::storage]
+	#[pallet::getter(fn threshold)]
+	pub type Memberships<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		u16,
+	>;
+
+	/// The operations of a multisig account waiting for approvals, by multisig account and hash
+	/// of the call they dispatch.
+	#[pallet::storage]
+	#[pallet::getter(fn operation)]
+	pub type Operations<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		CallHash,
+		OperationOf<T>,
+	>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+
+	impl<T: Config> Pallet<T> {
+		/// The operations waiting for approvals of the multisig accounts `signatory` is a
+		/// signatory of, with the multisig account and call hash of each.
+		pub fn pending_operations(
+			signatory: &T::AccountId,
+		) -> Vec<(T::AccountId, CallHash, OperationOf<T>)> {
+			Memberships::<T>::iter_prefix(signatory)
+				.flat_map(|(multisig, _)| {
+					Operations::<T>::iter_prefix(&multisig)
+						// Skip operations ended by calls no extrinsic made.
+						.filter(|(call_hash, _)| {
+							pallet_multisig::Multisigs::<T>::contains_key(&multisig, call_hash)
+						})
+						.map(|(call_hash, operation)| (multisig.clone(), call_hash, operation))
+						.collect::<Vec<_>>()
+				})
+				.collect()
+		}
+
+		/// The multisig account `call` is made for.
+		pub fn multisig_account(call: &MultisigCall<T::AccountId>) -> T::AccountId {
+			let mut signatories = call.other_signatories.clone();
+			signatories.push(call.signatory.clone());
+			signatories.sort();
+			signatories.dedup();
+			pallet_multisig::Pallet::<T>::multi_account_id(&signatories, call.threshold)
+		}
+
+		/// Record the multisig account of `call` for each of its signatories.
+		fn note_call(call: &MultisigCall<T::AccountId>, multisig: &T::AccountId) {
+			if Memberships::<T>::contains_key(&call.signatory, multisig) {
+				return
+			}
+
+			Memberships::<T>::insert(&call.signatory, multisig, call.threshold);
+			for signatory in &call.other_signatories {
+				Memberships::<T>::insert(signatory, multisig, call.threshold);
+			}
+		}
+
+		/// The weight of indexing `calls`: reading the events, and for each call, recording its
+		/// signatories and the operation it opens or approves.
+		pub fn index_weight(calls: &[MultisigCall<T::AccountId>]) -> Weight {
+			let db = <T as frame_system::Config>::DbWeight::get();
+			calls.iter().fold(db.reads(1), |weight, call| {
+				// The membership check, the operation and the timepoint of new operations.
+				let reads = 4;
+				let writes = call.other_signatories.len() as Weight + 2;
+				weight.saturating_add(db.reads_writes(reads, writes))
+			})
+		}
+
+		/// Update the index with the events of the multisig pallet after the first `from` events
+		/// of the block, recording the multisig accounts of the `calls` it accepted.
+		fn note_events(from: u32, calls: &[MultisigCall<T::AccountId>])
+		where
+			<T as frame_system::Config>::Event: TryInto<pallet_multisig::Event<T>>,
+		{
+			use pallet_multisig::Event::*;
+
+			// The call of `signatory` the multisig pallet accepted for `multisig`, if any.
+			let accepted = |signatory: &T::AccountId, multisig: &T::AccountId| {
+				calls.iter().find(|call| {
+					call.signatory == *signatory && Self::multisig_account(call) == *multisig
+				})
+			};
+
+			let events = frame_system::Pallet::<T>::events();
+			for record in events.into_iter().skip(from as usize) {
+				match record.event.try_into() {
+					Ok(NewMultisig(depositor, multisig, call_hash)) => {
+						// Operations of multisig accounts no call named can't be listed.
+						let threshold = match accepted(&depositor, &multisig) {
+							Some(call) => {
+								Self::note_call(call, &multisig);
+								call.threshold
+							},
+							None => continue,
+						};
+						// As the multisig pallet computes it.
+						let deposit =
+							T::DepositBase::get() + T::DepositFactor::get() * threshold.into();
+						let operation = Operation {
+							when: pallet_multisig::Pallet::<T>::timepoint(),
+							deposit,
+							depositor: depositor.clone(),
+							approvals: sp_std::vec![depositor],
+						};
+						Operations::<T>::insert(&multisig, call_hash, operation);
+					},
+					Ok(MultisigApproval(approving, _, multisig, call_hash)) => {
+						if let Some(call) = accepted(&approving, &multisig) {
+							Self::note_call(call, &multisig);
+						}
+						Operations::<T>::mutate(&multisig, call_hash, |operation| {
+							if let Some(operation) = operation {
+								if let Err(index) = operation.approvals.binary_search(&approving) {
+									operation.approvals.insert(index, approving);
+								}
+							}
+						});
+					},
+					Ok(MultisigExecuted(_, _, multisig, call_hash, _)) |
+					Ok(MultisigCancelled(_, _, multisig, call_hash)) => {
+						Operations::<T>::remove(&multisig, call_hash);
+					},
+					_ => {},
+				}
+			}
+		}
+	}
+
+	/// Records the multisig accounts and operations of extrinsics in the index.
+	#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+	pub struct IndexMultisigs<T: Config + Send + Sync>(PhantomData<T>);
+
+	impl<T: Config + Send + Sync> IndexMultisigs<T> {
+		pub fn new() -> Self {
+			Self(PhantomData)
+		}
+	}
+
+	impl<T: Config + Send + Sync> Default for IndexMultisigs<T> {
+		fn default() -> Self {
+			Self::new()
+		}
+	}
+
+	impl<T: Config + Send + Sync> fmt::Debug for IndexMultisigs<T> {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			write!(f, "IndexMultisigs")
+		}
+	}
+
+	impl<T: Config + Send + Sync> SignedExtension for IndexMultisigs<T>
+	where
+		<T as frame_system::Config>::Event: TryInto<pallet_multisig::Event<T>>,
+	{
+		const IDENTIFIER: &'static str = "IndexMultisigs";
+		type AccountId = T::AccountId;
+		type Call = <T as frame_system::Config>::Call;
+		type AdditionalSigned = ();
+		/// The number of events before the extrinsic, and its multisig calls.
+		type Pre = (u32, Vec<MultisigCall<T::AccountId>>);
+
+		fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+			Ok(())
+		}
+
+		fn pre_dispatch(
+			self,
+			who: &Self::AccountId,
+			call: &Self::Call,
+			_info: &DispatchInfoOf<Self::Call>,
+			_len: usize,
+		) -> Result<Self::Pre, TransactionValidityError> {
+			let calls = T::FindMultisigCalls::find(who, call);
+			if !calls.is_empty() {
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					Pallet::<T>::index_weight(&calls),
+					DispatchClass::Normal,
+				);
+			}
+			Ok((frame_system::Pallet::<T>::event_count(), calls))
+		}
+
+		fn post_dispatch(
+			(from, calls): Self::Pre,
+			_info: &DispatchInfoOf<Self::Call>,
+			_post_info: &PostDispatchInfoOf<Self::Call>,
+			_len: usize,
+			result: &DispatchResult,
+		) -> Result<(), TransactionValidityError> {
+			if calls.is_empty() || result.is_err() {
+				return Ok(())
+			}
+
+			Pallet::<T>::note_events(from, &calls);
+			Ok(())
+		}
+	}
+}
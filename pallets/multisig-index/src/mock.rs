@@ -0,0 +1,139 @@
+use crate::{self as pallet_multisig_index, FindMultisigCalls, IndexMultisigs, MultisigCall};
+use frame_support::{
+	dispatch::DispatchResultWithPostInfo,
+	parameter_types,
+	weights::{constants::RocksDbWeight, GetDispatchInfo, PostDispatchInfo},
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Dispatchable, IdentityLookup, SignedExtension},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Multisig: pallet_multisig::{Pallet, Call, Storage, Event<T>},
+		MultisigIndex: pallet_multisig_index::{Pallet, Storage},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const DepositBase: u64 = 1;
+	pub const DepositFactor: u64 = 1;
+	pub const MaxSignatories: u16 = 3;
+}
+
+impl pallet_multisig::Config for Test {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type DepositBase = DepositBase;
+	type DepositFactor = DepositFactor;
+	type MaxSignatories = MaxSignatories;
+	type WeightInfo = ();
+}
+
+/// Finds multisig calls made directly by extrinsics.
+pub struct MultisigCalls;
+
+impl FindMultisigCalls<Call, u64> for MultisigCalls {
+	fn find(who: &u64, call: &Call) -> Vec<MultisigCall<u64>> {
+		use pallet_multisig::Call::*;
+
+		match call {
+			Call::Multisig(as_multi(threshold, other_signatories, ..)) |
+			Call::Multisig(approve_as_multi(threshold, other_signatories, ..)) |
+			Call::Multisig(cancel_as_multi(threshold, other_signatories, ..)) => vec![MultisigCall {
+				signatory: *who,
+				other_signatories: other_signatories.clone(),
+				threshold: *threshold,
+			}],
+			_ => Vec::new(),
+		}
+	}
+}
+
+impl pallet_multisig_index::Config for Test {
+	type FindMultisigCalls = MultisigCalls;
+}
+
+/// Dispatch `call` from `who` as an extrinsic would, around the signed extension.
+pub fn dispatch(who: u64, call: Call) -> DispatchResultWithPostInfo {
+	let info = call.get_dispatch_info();
+	let pre = IndexMultisigs::<Test>::new().pre_dispatch(&who, &call, &info, 0).unwrap();
+	let result = call.dispatch(Origin::signed(who));
+	let post_info: PostDispatchInfo = result.unwrap_or_else(|error| error.post_info);
+	let outcome = result.map(|_| ()).map_err(|error| error.error);
+	IndexMultisigs::<Test>::post_dispatch(pre, &info, &post_info, 0, &outcome).unwrap();
+	result
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: (1..=4).map(|who| (who, 100)).collect() }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
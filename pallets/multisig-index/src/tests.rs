@@ -0,0 +1,145 @@
+use crate::{mock::*, Memberships, MultisigCall, Operation, Operations};
+use codec::Encode;
+use frame_support::{assert_ok, weights::GetDispatchInfo};
+use pallet_multisig::Timepoint;
+use sp_io::hashing::blake2_256;
+
+/// The call operations of the 2-of-3 multisig account of 1, 2 and 3 dispatch.
+fn transfer() -> Call {
+	Call::Balances(pallet_balances::Call::transfer(4, 10))
+}
+
+fn call_hash() -> [u8; 32] {
+	blake2_256(&transfer().encode())
+}
+
+fn multisig() -> u64 {
+	Multisig::multi_account_id(&[1, 2, 3], 2)
+}
+
+fn approve(other_signatories: Vec<u64>, timepoint: Option<Timepoint<u64>>) -> Call {
+	Call::Multisig(pallet_multisig::Call::approve_as_multi(
+		2,
+		other_signatories,
+		timepoint,
+		call_hash(),
+		0,
+	))
+}
+
+fn execute(other_signatories: Vec<u64>, timepoint: Timepoint<u64>) -> Call {
+	let weight = transfer().get_dispatch_info().weight;
+	Call::Multisig(pallet_multisig::Call::as_multi(
+		2,
+		other_signatories,
+		Some(timepoint),
+		transfer().encode(),
+		false,
+		weight,
+	))
+}
+
+fn cancel(other_signatories: Vec<u64>, timepoint: Timepoint<u64>) -> Call {
+	Call::Multisig(pallet_multisig::Call::cancel_as_multi(
+		2,
+		other_signatories,
+		timepoint,
+		call_hash(),
+	))
+}
+
+/// Open the operation from 1 and return its timepoint.
+fn open() -> Timepoint<u64> {
+	assert_ok!(dispatch(1, approve(vec![2, 3], None)));
+	Multisig::timepoint()
+}
+
+#[test]
+fn operations_are_listed_for_every_signatory() {
+	new_test_ext().execute_with(|| {
+		let when = open();
+
+		let operation = Operation { when, deposit: 3, depositor: 1, approvals: vec![1] };
+		for signatory in 1..=3 {
+			assert_eq!(
+				MultisigIndex::pending_operations(&signatory),
+				vec![(multisig(), call_hash(), operation.clone())]
+			);
+			assert_eq!(MultisigIndex::threshold(signatory, multisig()), Some(2));
+		}
+		assert!(MultisigIndex::pending_operations(&4).is_empty());
+		// The deposit the multisig pallet reserved.
+		assert_eq!(Balances::reserved_balance(1), 3);
+	});
+}
+
+#[test]
+fn approvals_are_recorded_until_the_operation_executes() {
+	new_test_ext().execute_with(|| {
+		let when = open();
+		assert_ok!(dispatch(3, approve(vec![1, 2], Some(when))));
+
+		let operation = Operation { when, deposit: 3, depositor: 1, approvals: vec![1, 3] };
+		assert_eq!(
+			MultisigIndex::pending_operations(&2),
+			vec![(multisig(), call_hash(), operation)]
+		);
+
+		assert_ok!(dispatch(2, execute(vec![1, 3], when)));
+		for signatory in 1..=3 {
+			assert!(MultisigIndex::pending_operations(&signatory).is_empty());
+		}
+		assert_eq!(Operations::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn cancelled_operations_are_removed() {
+	new_test_ext().execute_with(|| {
+		let when = open();
+		assert_ok!(dispatch(1, cancel(vec![2, 3], when)));
+
+		assert!(MultisigIndex::pending_operations(&2).is_empty());
+		assert_eq!(Operations::<Test>::iter().count(), 0);
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn operations_ended_outside_extrinsics_are_not_listed() {
+	new_test_ext().execute_with(|| {
+		let when = open();
+		// Not through the signed extension, as a scheduled call would be.
+		assert_ok!(Multisig::cancel_as_multi(Origin::signed(1), 2, vec![2, 3], when, call_hash()));
+
+		assert!(MultisigIndex::pending_operations(&2).is_empty());
+	});
+}
+
+#[test]
+fn calls_the_multisig_pallet_rejects_are_not_indexed() {
+	new_test_ext().execute_with(|| {
+		let when = Timepoint { height: 1, index: 0 };
+		// Approving an operation that was never opened fails, so 4 names 2 and 3 for free.
+		assert!(dispatch(4, approve(vec![2, 3], Some(when))).is_err());
+
+		let multisig = Multisig::multi_account_id(&[2, 3, 4], 2);
+		for signatory in 2..=4 {
+			assert_eq!(MultisigIndex::threshold(signatory, &multisig), None);
+		}
+		assert_eq!(Memberships::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn indexing_weight_is_registered_before_dispatch() {
+	new_test_ext().execute_with(|| {
+		let calls =
+			vec![MultisigCall { signatory: 1, other_signatories: vec![2, 3], threshold: 2 }];
+		let weight = MultisigIndex::index_weight(&calls);
+		assert!(weight > 0);
+
+		open();
+		assert_eq!(System::block_weight().total(), weight);
+	});
+}
@@ -0,0 +1,796 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Non-fungible tokens grouped in collections, and a fixed-price marketplace to sell them.
+//!
+//! A collection has three roles, all held by its creator at first:
+//! - the owner pays the deposits of the collection and everything in it, sets its team and
+//!   royalty, and destroys it once empty;
+//! - the issuer mints items;
+//! - the admin burns and transfers any item, and sets item metadata and attributes.
+//!
+//! The deposits of items, metadata and attributes are reserved from the owner even when the issuer
+//! or admin adds them, so an owner appointing another team lets it spend the owner's free balance
+//! on deposits. Ownership passes to a new owner only once they accept it and its deposits.
+//!
+//! Item owners can approve another account to transfer an item, or list it for sale at a fixed
+//! price. A sale pays the collection's royalty share of the price to the collection owner and the
+//! rest to the seller.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{Currency, ExistenceRequirement, ReservableCurrency},
+		transactional, BoundedVec,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::{
+		traits::{Saturating, StaticLookup, Zero},
+		ArithmeticError, Percent,
+	};
+	use sp_std::{convert::TryInto, vec::Vec};
+
+	/// Identifier of a collection.
+	pub type CollectionId = u32;
+	/// Identifier of an item within its collection.
+	pub type ItemId = u32;
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// A collection of items.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct CollectionDetails<AccountId, Balance> {
+		/// Can change the team and royalty, and destroy the collection once empty.
+		pub owner: AccountId,
+		/// Can mint items.
+		pub issuer: AccountId,
+		/// Can burn and transfer any item, and set item metadata and attributes.
+		pub admin: AccountId,
+		/// Deposits reserved from `owner` for the collection and everything in it.
+		pub total_deposit: Balance,
+		/// Number of items in the collection.
+		pub items: u32,
+		/// Share of every marketplace sale paid to `owner`.
+		pub royalty: Percent,
+	}
+
+	/// An item of a collection.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct ItemDetails<AccountId, Balance> {
+		/// The account holding the item.
+		pub owner: AccountId,
+		/// An account allowed to transfer the item on behalf of `owner`.
+		pub approved: Option<AccountId>,
+		/// Deposit reserved for the item itself.
+		pub deposit: Balance,
+		/// Number of attributes set on the item.
+		pub attributes: u32,
+	}
+
+	/// Metadata of an item, typically a URI to its off-chain description.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct ItemMetadata<Balance, StringLimit: Get<u32>> {
+		/// Deposit reserved for the metadata.
+		pub deposit: Balance,
+		/// The metadata itself.
+		pub data: BoundedVec<u8, StringLimit>,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency deposits are reserved in and items are sold for.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Deposit reserved for a collection.
+		#[pallet::constant]
+		type CollectionDeposit: Get<BalanceOf<Self>>;
+
+		/// Deposit reserved for an item.
+		#[pallet::constant]
+		type ItemDeposit: Get<BalanceOf<Self>>;
+
+		/// Deposit reserved for the metadata of an item, on top of `DepositPerByte`.
+		#[pallet::constant]
+		type MetadataDepositBase: Get<BalanceOf<Self>>;
+
+		/// Deposit reserved for an attribute, on top of `DepositPerByte`.
+		#[pallet::constant]
+		type AttributeDepositBase: Get<BalanceOf<Self>>;
+
+		/// Deposit reserved for every byte of metadata, attribute key and attribute value.
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Maximum length of metadata, attribute keys and attribute values.
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+
+		/// Maximum number of attributes on an item.
+		#[pallet::constant]
+		type MaxAttributes: Get<u32>;
+
+		/// Maximum royalty a collection can take from sales.
+		#[pallet::constant]
+		type MaxRoyalty: Get<Percent>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The identifier of the next collection to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_collection_id)]
+	pub type NextCollectionId<T> = StorageValue<_, CollectionId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn collection)]
+	pub type Collections<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		CollectionId,
+		CollectionDetails<T::AccountId, BalanceOf<T>>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn item)]
+	pub type Items<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		CollectionId,
+		Blake2_128Concat,
+		ItemId,
+		ItemDetails<T::AccountId, BalanceOf<T>>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn metadata)]
+	pub type MetadataOf<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		CollectionId,
+		Blake2_128Concat,
+		ItemId,
+		ItemMetadata<BalanceOf<T>, T::StringLimit>,
+	>;
+
+	/// Attribute values and their deposits, by item and key.
+	#[pallet::storage]
+	#[pallet::getter(fn attribute)]
+	pub type Attributes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(CollectionId, ItemId),
+		Blake2_128Concat,
+		BoundedVec<u8, T::StringLimit>,
+		(BoundedVec<u8, T::StringLimit>, BalanceOf<T>),
+	>;
+
+	/// The account each collection's owner offered ownership to, until it accepts.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_owner)]
+	pub type PendingOwners<T: Config> = StorageMap<_, Blake2_128Concat, CollectionId, T::AccountId>;
+
+	/// Prices of the items listed for sale.
+	#[pallet::storage]
+	#[pallet::getter(fn listing)]
+	pub type Listings<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, CollectionId, Blake2_128Concat, ItemId, BalanceOf<T>>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", BalanceOf<T> = "Balance")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A collection was created. [collection, owner]
+		Created(CollectionId, T::AccountId),
+		/// An empty collection was destroyed. [collection]
+		Destroyed(CollectionId),
+		/// The issuer and admin of a collection changed. [collection, issuer, admin]
+		TeamChanged(CollectionId, T::AccountId, T::AccountId),
+		/// The owner of a collection offered ownership to another account, or withdrew the offer
+		/// if none. [collection, pending owner]
+		OwnershipOffered(CollectionId, Option<T::AccountId>),
+		/// The owner of a collection changed. [collection, owner]
+		OwnerChanged(CollectionId, T::AccountId),
+		/// The royalty of a collection changed. [collection, royalty]
+		RoyaltySet(CollectionId, Percent),
+		/// An item was minted. [collection, item, owner]
+		Minted(CollectionId, ItemId, T::AccountId),
+		/// An item was burned. [collection, item, owner]
+		Burned(CollectionId, ItemId, T::AccountId),
+		/// An item was transferred. [collection, item, from, to]
+		Transferred(CollectionId, ItemId, T::AccountId, T::AccountId),
+		/// An account was approved to transfer an item. [collection, item, owner, delegate]
+		ApprovedTransfer(CollectionId, ItemId, T::AccountId, T::AccountId),
+		/// An approval to transfer an item was cancelled. [collection, item, owner, delegate]
+		ApprovalCancelled(CollectionId, ItemId, T::AccountId, T::AccountId),
+		/// The metadata of an item was set. [collection, item, data]
+		MetadataSet(CollectionId, ItemId, Vec<u8>),
+		/// The metadata of an item was cleared. [collection, item]
+		MetadataCleared(CollectionId, ItemId),
+		/// An attribute of an item was set. [collection, item, key, value]
+		AttributeSet(CollectionId, ItemId, Vec<u8>, Vec<u8>),
+		/// An attribute of an item was cleared. [collection, item, key]
+		AttributeCleared(CollectionId, ItemId, Vec<u8>),
+		/// An item was listed for sale. [collection, item, seller, price]
+		Listed(CollectionId, ItemId, T::AccountId, BalanceOf<T>),
+		/// A listing was cancelled. [collection, item]
+		ListingCancelled(CollectionId, ItemId),
+		/// A listed item was sold. [collection, item, seller, buyer, price, royalty]
+		Sold(CollectionId, ItemId, T::AccountId, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The collection does not exist.
+		UnknownCollection,
+		/// The item does not exist.
+		UnknownItem,
+		/// An item with this identifier already exists in the collection.
+		AlreadyExists,
+		/// The sender does not hold the role required for this call.
+		NoPermission,
+		/// The collection still has items.
+		CollectionNotEmpty,
+		/// All collection identifiers are in use.
+		NoAvailableCollectionId,
+		/// Metadata, an attribute key or an attribute value is longer than `StringLimit`.
+		TooLong,
+		/// The item already has `MaxAttributes` attributes.
+		TooManyAttributes,
+		/// The attribute is not set.
+		UnknownAttribute,
+		/// The item has no metadata.
+		NoMetadata,
+		/// The royalty is higher than `MaxRoyalty`.
+		RoyaltyTooHigh,
+		/// No account is approved to transfer the item.
+		NotApproved,
+		/// The item is not listed for sale.
+		NotListed,
+		/// The listing price is higher than the buyer's limit.
+		PriceTooHigh,
+		/// The buyer already owns the item.
+		BuyerIsSeller,
+		/// Ownership of the collection was not offered to the sender.
+		NotPendingOwner,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a collection with the sender as owner, issuer and admin, reserving
+		/// `CollectionDeposit` from the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn create(origin: OriginFor<T>) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+
+			let collection = NextCollectionId::<T>::get();
+			let next = collection.checked_add(1).ok_or(Error::<T>::NoAvailableCollectionId)?;
+			let deposit = T::CollectionDeposit::get();
+			T::Currency::reserve(&owner, deposit)?;
+
+			Collections::<T>::insert(
+				collection,
+				CollectionDetails {
+					owner: owner.clone(),
+					issuer: owner.clone(),
+					admin: owner.clone(),
+					total_deposit: deposit,
+					items: 0,
+					royalty: Percent::zero(),
+				},
+			);
+			NextCollectionId::<T>::put(next);
+
+			Self::deposit_event(Event::Created(collection, owner));
+			Ok(())
+		}
+
+		/// Destroy an empty collection, releasing its deposit. Only the owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn destroy(origin: OriginFor<T>, collection: CollectionId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Collections::<T>::get(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == who, Error::<T>::NoPermission);
+			ensure!(details.items == 0, Error::<T>::CollectionNotEmpty);
+
+			T::Currency::unreserve(&details.owner, details.total_deposit);
+			Collections::<T>::remove(collection);
+			PendingOwners::<T>::remove(collection);
+
+			Self::deposit_event(Event::Destroyed(collection));
+			Ok(())
+		}
+
+		/// Change the issuer and admin of a collection. Only the owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_team(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			issuer: <T::Lookup as StaticLookup>::Source,
+			admin: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let issuer = T::Lookup::lookup(issuer)?;
+			let admin = T::Lookup::lookup(admin)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.owner == who, Error::<T>::NoPermission);
+				details.issuer = issuer.clone();
+				details.admin = admin.clone();
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::TeamChanged(collection, issuer, admin));
+			Ok(())
+		}
+
+		/// Offer ownership of a collection to `new_owner`, replacing any previous offer. Offering
+		/// it to the owner itself withdraws the offer. Only the owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn transfer_ownership(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			new_owner: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let new_owner = T::Lookup::lookup(new_owner)?;
+
+			let details = Collections::<T>::get(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == who, Error::<T>::NoPermission);
+			let pending = if new_owner == who {
+				PendingOwners::<T>::remove(collection);
+				None
+			} else {
+				PendingOwners::<T>::insert(collection, &new_owner);
+				Some(new_owner)
+			};
+
+			Self::deposit_event(Event::OwnershipOffered(collection, pending));
+			Ok(())
+		}
+
+		/// Take over a collection whose ownership was offered to the sender, together with its
+		/// deposits: they are reserved from the sender and released to the previous owner.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		pub fn accept_ownership(origin: OriginFor<T>, collection: CollectionId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				PendingOwners::<T>::get(collection).as_ref() == Some(&who),
+				Error::<T>::NotPendingOwner
+			);
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				T::Currency::reserve(&who, details.total_deposit)?;
+				T::Currency::unreserve(&details.owner, details.total_deposit);
+				details.owner = who.clone();
+				Ok::<_, DispatchError>(())
+			})?;
+			PendingOwners::<T>::remove(collection);
+
+			Self::deposit_event(Event::OwnerChanged(collection, who));
+			Ok(())
+		}
+
+		/// Set the share of marketplace sales paid to the owner of a collection, up to
+		/// `MaxRoyalty`. Only the owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_royalty(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			royalty: Percent,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(royalty <= T::MaxRoyalty::get(), Error::<T>::RoyaltyTooHigh);
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.owner == who, Error::<T>::NoPermission);
+				details.royalty = royalty;
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::RoyaltySet(collection, royalty));
+			Ok(())
+		}
+
+		/// Mint `item` in `collection` for `owner`, reserving `ItemDeposit` from the collection
+		/// owner rather than the issuer. Only the issuer can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn mint(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.issuer == who, Error::<T>::NoPermission);
+				ensure!(!Items::<T>::contains_key(collection, item), Error::<T>::AlreadyExists);
+
+				details.items = details.items.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+				let deposit = T::ItemDeposit::get();
+				T::Currency::reserve(&details.owner, deposit)?;
+				details.total_deposit = details.total_deposit.saturating_add(deposit);
+
+				Items::<T>::insert(
+					collection,
+					item,
+					ItemDetails { owner: owner.clone(), approved: None, deposit, attributes: 0 },
+				);
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::Minted(collection, item, owner));
+			Ok(())
+		}
+
+		/// Burn an item with its metadata and attributes, releasing their deposits. The item's
+		/// owner or the collection admin can do this.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(2, 5 + T::MaxAttributes::get() as Weight)
+		)]
+		pub fn burn(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let owner = Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				let item_details =
+					Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+				ensure!(
+					item_details.owner == who || details.admin == who,
+					Error::<T>::NoPermission
+				);
+
+				let mut deposit = item_details.deposit;
+				if let Some(metadata) = MetadataOf::<T>::take(collection, item) {
+					deposit = deposit.saturating_add(metadata.deposit);
+				}
+				for (_, (_, attribute_deposit)) in Attributes::<T>::drain_prefix((collection, item))
+				{
+					deposit = deposit.saturating_add(attribute_deposit);
+				}
+				T::Currency::unreserve(&details.owner, deposit);
+				details.total_deposit = details.total_deposit.saturating_sub(deposit);
+				details.items = details.items.saturating_sub(1);
+
+				Items::<T>::remove(collection, item);
+				Listings::<T>::remove(collection, item);
+				Ok::<_, DispatchError>(item_details.owner)
+			})?;
+
+			Self::deposit_event(Event::Burned(collection, item, owner));
+			Ok(())
+		}
+
+		/// Transfer an item to `dest`, cancelling its approval and listing. The item's owner, the
+		/// account approved for it or the collection admin can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			dest: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			let details = Collections::<T>::get(collection).ok_or(Error::<T>::UnknownCollection)?;
+			let item_details = Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+			ensure!(
+				item_details.owner == who ||
+					item_details.approved.as_ref() == Some(&who) ||
+					details.admin == who,
+				Error::<T>::NoPermission
+			);
+
+			Self::do_transfer(collection, item, item_details, dest);
+			Ok(())
+		}
+
+		/// Allow `delegate` to transfer an item, replacing any previous approval. Only the item's
+		/// owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn approve_transfer(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			delegate: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+
+			Items::<T>::try_mutate(collection, item, |item_details| {
+				let item_details = item_details.as_mut().ok_or(Error::<T>::UnknownItem)?;
+				ensure!(item_details.owner == who, Error::<T>::NoPermission);
+				item_details.approved = Some(delegate.clone());
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::ApprovedTransfer(collection, item, who, delegate));
+			Ok(())
+		}
+
+		/// Cancel the approval to transfer an item. Only the item's owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn cancel_approval(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let delegate = Items::<T>::try_mutate(collection, item, |item_details| {
+				let item_details = item_details.as_mut().ok_or(Error::<T>::UnknownItem)?;
+				ensure!(item_details.owner == who, Error::<T>::NoPermission);
+				item_details.approved.take().ok_or(Error::<T>::NotApproved)
+			})?;
+
+			Self::deposit_event(Event::ApprovalCancelled(collection, item, who, delegate));
+			Ok(())
+		}
+
+		/// Set the metadata of an item, adjusting the deposit reserved from the collection owner
+		/// rather than the admin. Only the collection admin can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn set_metadata(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			data: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let bounded: BoundedVec<u8, T::StringLimit> =
+				data.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.admin == who, Error::<T>::NoPermission);
+				ensure!(Items::<T>::contains_key(collection, item), Error::<T>::UnknownItem);
+
+				let old = MetadataOf::<T>::get(collection, item).map(|m| m.deposit);
+				let deposit = T::MetadataDepositBase::get().saturating_add(
+					T::DepositPerByte::get().saturating_mul((bounded.len() as u32).into()),
+				);
+				Self::update_deposit(details, old.unwrap_or_else(Zero::zero), deposit)?;
+
+				MetadataOf::<T>::insert(collection, item, ItemMetadata { deposit, data: bounded });
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::MetadataSet(collection, item, data));
+			Ok(())
+		}
+
+		/// Clear the metadata of an item, releasing its deposit. Only the collection admin can do
+		/// this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn clear_metadata(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.admin == who, Error::<T>::NoPermission);
+
+				let metadata =
+					MetadataOf::<T>::take(collection, item).ok_or(Error::<T>::NoMetadata)?;
+				Self::update_deposit(details, metadata.deposit, Zero::zero())
+			})?;
+
+			Self::deposit_event(Event::MetadataCleared(collection, item));
+			Ok(())
+		}
+
+		/// Set an attribute of an item, adjusting the deposit reserved from the collection owner
+		/// rather than the admin. Only the collection admin can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn set_attribute(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			key: Vec<u8>,
+			value: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let bounded_key: BoundedVec<u8, T::StringLimit> =
+				key.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+			let bounded_value: BoundedVec<u8, T::StringLimit> =
+				value.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.admin == who, Error::<T>::NoPermission);
+				let mut item_details =
+					Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+
+				let old = Attributes::<T>::get((collection, item), &bounded_key).map(|(_, d)| d);
+				if old.is_none() {
+					ensure!(
+						item_details.attributes < T::MaxAttributes::get(),
+						Error::<T>::TooManyAttributes
+					);
+					item_details.attributes += 1;
+				}
+				let bytes = (bounded_key.len() + bounded_value.len()) as u32;
+				let deposit = T::AttributeDepositBase::get()
+					.saturating_add(T::DepositPerByte::get().saturating_mul(bytes.into()));
+				Self::update_deposit(details, old.unwrap_or_else(Zero::zero), deposit)?;
+
+				Attributes::<T>::insert((collection, item), bounded_key, (bounded_value, deposit));
+				Items::<T>::insert(collection, item, item_details);
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::AttributeSet(collection, item, key, value));
+			Ok(())
+		}
+
+		/// Clear an attribute of an item, releasing its deposit. Only the collection admin can do
+		/// this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn clear_attribute(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			key: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let bounded_key: BoundedVec<u8, T::StringLimit> =
+				key.clone().try_into().map_err(|_| Error::<T>::TooLong)?;
+
+			Collections::<T>::try_mutate(collection, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownCollection)?;
+				ensure!(details.admin == who, Error::<T>::NoPermission);
+
+				let (_, deposit) = Attributes::<T>::take((collection, item), &bounded_key)
+					.ok_or(Error::<T>::UnknownAttribute)?;
+				Items::<T>::mutate(collection, item, |item_details| {
+					if let Some(item_details) = item_details {
+						item_details.attributes = item_details.attributes.saturating_sub(1);
+					}
+				});
+				Self::update_deposit(details, deposit, Zero::zero())
+			})?;
+
+			Self::deposit_event(Event::AttributeCleared(collection, item, key));
+			Ok(())
+		}
+
+		/// List an item for sale at `price`, replacing any previous listing. Only the item's
+		/// owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn list(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			#[pallet::compact] price: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let item_details = Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+			ensure!(item_details.owner == who, Error::<T>::NoPermission);
+			Listings::<T>::insert(collection, item, price);
+
+			Self::deposit_event(Event::Listed(collection, item, who, price));
+			Ok(())
+		}
+
+		/// Withdraw an item from sale. Only the item's owner can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn cancel_listing(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let item_details = Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+			ensure!(item_details.owner == who, Error::<T>::NoPermission);
+			ensure!(Listings::<T>::contains_key(collection, item), Error::<T>::NotListed);
+			Listings::<T>::remove(collection, item);
+
+			Self::deposit_event(Event::ListingCancelled(collection, item));
+			Ok(())
+		}
+
+		/// Buy a listed item at its price, if it is not higher than `max_price`. The collection's
+		/// royalty share of the price goes to the collection owner and the rest to the seller.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 5))]
+		#[transactional]
+		pub fn buy(
+			origin: OriginFor<T>,
+			collection: CollectionId,
+			item: ItemId,
+			#[pallet::compact] max_price: BalanceOf<T>,
+		) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+
+			let price = Listings::<T>::get(collection, item).ok_or(Error::<T>::NotListed)?;
+			ensure!(price <= max_price, Error::<T>::PriceTooHigh);
+			let details = Collections::<T>::get(collection).ok_or(Error::<T>::UnknownCollection)?;
+			let item_details = Items::<T>::get(collection, item).ok_or(Error::<T>::UnknownItem)?;
+			let seller = item_details.owner.clone();
+			ensure!(seller != buyer, Error::<T>::BuyerIsSeller);
+
+			let royalty = details.royalty * price;
+			T::Currency::transfer(
+				&buyer,
+				&seller,
+				price.saturating_sub(royalty),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			if !royalty.is_zero() {
+				T::Currency::transfer(
+					&buyer,
+					&details.owner,
+					royalty,
+					ExistenceRequirement::KeepAlive,
+				)?;
+			}
+			Self::do_transfer(collection, item, item_details, buyer.clone());
+
+			Self::deposit_event(Event::Sold(collection, item, seller, buyer, price, royalty));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Move an item to `dest`, dropping its approval and listing.
+		fn do_transfer(
+			collection: CollectionId,
+			item: ItemId,
+			mut item_details: ItemDetails<T::AccountId, BalanceOf<T>>,
+			dest: T::AccountId,
+		) {
+			let from = sp_std::mem::replace(&mut item_details.owner, dest.clone());
+			item_details.approved = None;
+			Items::<T>::insert(collection, item, item_details);
+			Listings::<T>::remove(collection, item);
+
+			Self::deposit_event(Event::Transferred(collection, item, from, dest));
+		}
+
+		/// Replace a deposit of `old` reserved from the collection owner by one of `new`.
+		fn update_deposit(
+			details: &mut CollectionDetails<T::AccountId, BalanceOf<T>>,
+			old: BalanceOf<T>,
+			new: BalanceOf<T>,
+		) -> DispatchResult {
+			if new > old {
+				T::Currency::reserve(&details.owner, new - old)?;
+			} else {
+				T::Currency::unreserve(&details.owner, old - new);
+			}
+			details.total_deposit = details.total_deposit.saturating_add(new).saturating_sub(old);
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,266 @@
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::Percent;
+
+fn reserved(who: u64) -> u64 {
+	Balances::reserved_balance(who)
+}
+
+fn free(who: u64) -> u64 {
+	Balances::free_balance(who)
+}
+
+// Collection 0 owned by 1, with item 7 held by 2.
+fn setup() {
+	assert_ok!(Nft::create(Origin::signed(1)));
+	assert_ok!(Nft::mint(Origin::signed(1), 0, 7, 2));
+}
+
+#[test]
+fn create_reserves_deposit_and_assigns_ids() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create(Origin::signed(1)));
+		assert_ok!(Nft::create(Origin::signed(2)));
+
+		assert_eq!(Nft::collection(0).unwrap().owner, 1);
+		assert_eq!(Nft::collection(1).unwrap().owner, 2);
+		assert_eq!(Nft::next_collection_id(), 2);
+		assert_eq!(reserved(1), 100);
+	});
+}
+
+#[test]
+fn destroy_requires_owner_and_empty_collection() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(Nft::destroy(Origin::signed(2), 0), Error::<Test>::NoPermission);
+		assert_noop!(Nft::destroy(Origin::signed(1), 0), Error::<Test>::CollectionNotEmpty);
+
+		assert_ok!(Nft::burn(Origin::signed(2), 0, 7));
+		assert_ok!(Nft::destroy(Origin::signed(1), 0));
+		assert!(Nft::collection(0).is_none());
+		assert_eq!(reserved(1), 0);
+	});
+}
+
+#[test]
+fn only_issuer_mints_and_owner_pays_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create(Origin::signed(1)));
+		assert_noop!(Nft::mint(Origin::signed(3), 0, 7, 2), Error::<Test>::NoPermission);
+
+		assert_ok!(Nft::set_team(Origin::signed(1), 0, 3, 3));
+		assert_noop!(Nft::mint(Origin::signed(1), 0, 7, 2), Error::<Test>::NoPermission);
+		assert_ok!(Nft::mint(Origin::signed(3), 0, 7, 2));
+		assert_noop!(Nft::mint(Origin::signed(3), 0, 7, 3), Error::<Test>::AlreadyExists);
+
+		assert_eq!(Nft::item(0, 7).unwrap().owner, 2);
+		assert_eq!(Nft::collection(0).unwrap().items, 1);
+		assert_eq!(reserved(1), 110);
+		assert_eq!(reserved(3), 0);
+	});
+}
+
+#[test]
+fn transfer_by_owner_approved_account_or_admin() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(Nft::transfer(Origin::signed(3), 0, 7, 3), Error::<Test>::NoPermission);
+
+		assert_ok!(Nft::approve_transfer(Origin::signed(2), 0, 7, 3));
+		assert_ok!(Nft::transfer(Origin::signed(3), 0, 7, 3));
+		assert_eq!(Nft::item(0, 7).unwrap().owner, 3);
+		// The approval does not survive the transfer.
+		assert_eq!(Nft::item(0, 7).unwrap().approved, None);
+
+		assert_ok!(Nft::transfer(Origin::signed(1), 0, 7, 2));
+		assert_eq!(Nft::item(0, 7).unwrap().owner, 2);
+	});
+}
+
+#[test]
+fn cancel_approval_revokes_delegate() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(Nft::cancel_approval(Origin::signed(2), 0, 7), Error::<Test>::NotApproved);
+		assert_ok!(Nft::approve_transfer(Origin::signed(2), 0, 7, 3));
+		assert_noop!(Nft::cancel_approval(Origin::signed(3), 0, 7), Error::<Test>::NoPermission);
+
+		assert_ok!(Nft::cancel_approval(Origin::signed(2), 0, 7));
+		assert_noop!(Nft::transfer(Origin::signed(3), 0, 7, 3), Error::<Test>::NoPermission);
+	});
+}
+
+#[test]
+fn metadata_deposit_follows_length() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(
+			Nft::set_metadata(Origin::signed(2), 0, 7, b"ipfs://a".to_vec()),
+			Error::<Test>::NoPermission
+		);
+		assert_noop!(
+			Nft::set_metadata(Origin::signed(1), 0, 7, vec![0; 17]),
+			Error::<Test>::TooLong
+		);
+
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 7, b"ipfs://abcdef".to_vec()));
+		assert_eq!(reserved(1), 110 + 5 + 13);
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 7, b"ipfs://a".to_vec()));
+		assert_eq!(reserved(1), 110 + 5 + 8);
+		assert_eq!(Nft::metadata(0, 7).unwrap().data.to_vec(), b"ipfs://a".to_vec());
+
+		assert_ok!(Nft::clear_metadata(Origin::signed(1), 0, 7));
+		assert_eq!(reserved(1), 110);
+		assert_eq!(Nft::collection(0).unwrap().total_deposit, 110);
+	});
+}
+
+#[test]
+fn attributes_are_limited_and_release_deposits() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Nft::set_attribute(
+			Origin::signed(1),
+			0,
+			7,
+			b"rarity".to_vec(),
+			b"rare".to_vec()
+		));
+		assert_ok!(Nft::set_attribute(Origin::signed(1), 0, 7, b"level".to_vec(), b"1".to_vec()));
+		assert_eq!(reserved(1), 110 + (3 + 10) + (3 + 6));
+
+		// Replacing an attribute does not count against the limit.
+		assert_ok!(Nft::set_attribute(Origin::signed(1), 0, 7, b"level".to_vec(), b"10".to_vec()));
+		assert_noop!(
+			Nft::set_attribute(Origin::signed(1), 0, 7, b"speed".to_vec(), b"9".to_vec()),
+			Error::<Test>::TooManyAttributes
+		);
+
+		assert_ok!(Nft::clear_attribute(Origin::signed(1), 0, 7, b"rarity".to_vec()));
+		assert_eq!(reserved(1), 110 + (3 + 7));
+		assert_eq!(Nft::item(0, 7).unwrap().attributes, 1);
+		assert_noop!(
+			Nft::clear_attribute(Origin::signed(1), 0, 7, b"rarity".to_vec()),
+			Error::<Test>::UnknownAttribute
+		);
+	});
+}
+
+#[test]
+fn burn_releases_all_item_deposits() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 7, b"ipfs://a".to_vec()));
+		assert_ok!(Nft::set_attribute(Origin::signed(1), 0, 7, b"level".to_vec(), b"1".to_vec()));
+		assert_noop!(Nft::burn(Origin::signed(3), 0, 7), Error::<Test>::NoPermission);
+
+		assert_ok!(Nft::burn(Origin::signed(2), 0, 7));
+		assert!(Nft::item(0, 7).is_none());
+		assert!(Nft::metadata(0, 7).is_none());
+		assert_eq!(reserved(1), 100);
+		assert_eq!(Nft::collection(0).unwrap().total_deposit, 100);
+		assert_eq!(Nft::collection(0).unwrap().items, 0);
+	});
+}
+
+#[test]
+fn ownership_passes_with_deposits_once_accepted() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(Nft::transfer_ownership(Origin::signed(2), 0, 2), Error::<Test>::NoPermission);
+
+		assert_ok!(Nft::transfer_ownership(Origin::signed(1), 0, 3));
+		// Nothing moves until 3 accepts.
+		assert_eq!(Nft::collection(0).unwrap().owner, 1);
+		assert_eq!(Nft::pending_owner(0), Some(3));
+		assert_eq!(reserved(3), 0);
+		assert_noop!(Nft::accept_ownership(Origin::signed(2), 0), Error::<Test>::NotPendingOwner);
+
+		assert_ok!(Nft::accept_ownership(Origin::signed(3), 0));
+		assert_eq!(Nft::collection(0).unwrap().owner, 3);
+		assert_eq!(Nft::pending_owner(0), None);
+		assert_eq!(reserved(1), 0);
+		assert_eq!(reserved(3), 110);
+	});
+}
+
+#[test]
+fn ownership_offers_can_be_withdrawn() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Nft::transfer_ownership(Origin::signed(1), 0, 3));
+		assert_ok!(Nft::transfer_ownership(Origin::signed(1), 0, 1));
+
+		assert_eq!(Nft::pending_owner(0), None);
+		assert_noop!(Nft::accept_ownership(Origin::signed(3), 0), Error::<Test>::NotPendingOwner);
+	});
+}
+
+#[test]
+fn royalty_is_capped() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(
+			Nft::set_royalty(Origin::signed(1), 0, Percent::from_percent(51)),
+			Error::<Test>::RoyaltyTooHigh
+		);
+		assert_noop!(
+			Nft::set_royalty(Origin::signed(2), 0, Percent::from_percent(10)),
+			Error::<Test>::NoPermission
+		);
+		assert_ok!(Nft::set_royalty(Origin::signed(1), 0, Percent::from_percent(10)));
+		assert_eq!(Nft::collection(0).unwrap().royalty, Percent::from_percent(10));
+	});
+}
+
+#[test]
+fn buy_pays_seller_and_royalty() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Nft::set_royalty(Origin::signed(1), 0, Percent::from_percent(10)));
+		assert_noop!(Nft::list(Origin::signed(3), 0, 7, 200), Error::<Test>::NoPermission);
+		assert_ok!(Nft::list(Origin::signed(2), 0, 7, 200));
+
+		assert_noop!(Nft::buy(Origin::signed(3), 0, 7, 199), Error::<Test>::PriceTooHigh);
+		assert_noop!(Nft::buy(Origin::signed(2), 0, 7, 200), Error::<Test>::BuyerIsSeller);
+
+		assert_ok!(Nft::buy(Origin::signed(3), 0, 7, 200));
+		assert_eq!(Nft::item(0, 7).unwrap().owner, 3);
+		assert_eq!(free(3), 800);
+		assert_eq!(free(2), 1_180);
+		assert_eq!(free(1), 1_000 - 110 + 20);
+		assert!(Nft::listing(0, 7).is_none());
+		assert_noop!(Nft::buy(Origin::signed(2), 0, 7, 200), Error::<Test>::NotListed);
+	});
+}
+
+#[test]
+fn failed_payment_keeps_item_listed() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Nft::list(Origin::signed(2), 0, 7, 200));
+
+		assert_noop!(
+			Nft::buy(Origin::signed(4), 0, 7, 200),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+		assert_eq!(Nft::item(0, 7).unwrap().owner, 2);
+		assert_eq!(Nft::listing(0, 7), Some(200));
+	});
+}
+
+#[test]
+fn transfer_and_cancel_remove_listing() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(Nft::cancel_listing(Origin::signed(2), 0, 7), Error::<Test>::NotListed);
+		assert_ok!(Nft::list(Origin::signed(2), 0, 7, 200));
+		assert_ok!(Nft::cancel_listing(Origin::signed(2), 0, 7));
+		assert!(Nft::listing(0, 7).is_none());
+
+		assert_ok!(Nft::list(Origin::signed(2), 0, 7, 200));
+		assert_ok!(Nft::transfer(Origin::signed(2), 0, 7, 3));
+		assert_noop!(Nft::buy(Origin::signed(1), 0, 7, 200), Error::<Test>::NotListed);
+	});
+}
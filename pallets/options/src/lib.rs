@@ -0,0 +1,725 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! European call and put options on assets, settled in cash at expiry.
+//!
+//! An option series has an underlying asset, a kind, a strike price in units of the native
+//! currency per unit of the underlying, and an expiry block. Each option covers one unit of the
+//! underlying, and both kinds are paid in the native currency. Writers lock the most an option
+//! can pay as collateral to mint options, which they can transfer or offer for sale at a fixed
+//! price:
+//! - a call has a cap above its strike, locks the cap above the strike, and pays the price of the
+//!   underlying above the strike, up to the cap;
+//! - a put locks the strike, and pays the strike above the price of the underlying.
+//!
+//! Until expiry, a writer holding options they wrote can close them, releasing their collateral.
+//! At the expiry block, `on_initialize` settles the series at the price of the underlying given
+//! by the price provider, retrying in the next `MaxSettlementDelay` blocks while it has none.
+//! A series still unsettled after them is settled by anyone with `settle` once the price provider
+//! has a price, or at a given price by `SeriesOrigin` with `force_settle`. Holders of options in
+//! the money then exercise any part of them, and writers release the collateral left after the
+//! payoff of the options they wrote; options out of the money expire worthless and leave their
+//! whole collateral to the writers.
+//!
+//! Collateral is transferred from writers to the account of `PalletId`, and paid out of it on
+//! release and exercise.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{tokens::fungibles::Inspect, Currency, ExistenceRequirement},
+		transactional, BoundedVec, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_price_feed::PriceProvider;
+	use sp_core::U256;
+	use sp_runtime::{
+		traits::{
+			AccountIdConversion, CheckedAdd, CheckedMul, CheckedSub, One, Saturating, StaticLookup,
+			UniqueSaturatedInto, Zero,
+		},
+		ArithmeticError, FixedPointNumber, FixedU128,
+	};
+	use sp_std::convert::{TryFrom, TryInto};
+
+	/// Identifier of an option series.
+	pub type SeriesId = u32;
+	/// Identifier of an offer.
+	pub type OfferId = u64;
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type AssetIdOf<T> =
+		<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+	pub type SeriesOf<T> =
+		OptionSeries<AssetIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+
+	/// The right an option gives.
+	#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub enum OptionKind {
+		/// Pays the price of the underlying above the strike, up to `cap`, a price above the
+		/// strike.
+		Call { cap: FixedU128 },
+		/// Pays the strike above the price of the underlying.
+		Put,
+	}
+
+	/// Options on an asset with the same kind, strike and expiry.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct OptionSeries<AssetId, Balance, BlockNumber> {
+		/// The asset the options are written on.
+		pub underlying: AssetId,
+		/// Call or put.
+		pub kind: OptionKind,
+		/// Units of the native currency per unit of the underlying.
+		pub strike: FixedU128,
+		/// Block at which the options expire and settle.
+		pub expiry: BlockNumber,
+		/// Options written and not closed.
+		pub outstanding: Balance,
+		/// Price of the underlying the series settled at, once it has.
+		pub settlement_price: Option<FixedU128>,
+	}
+
+	/// Options an account wrote in a series.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default)]
+	pub struct Position<Balance> {
+		/// Options written and not closed.
+		pub written: Balance,
+		/// Collateral locked for them, in the native currency.
+		pub collateral: Balance,
+	}
+
+	/// Options offered for sale at a fixed price.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Offer<AccountId, Balance> {
+		/// The account selling the options, which are held by the offer until sold or cancelled.
+		pub seller: AccountId,
+		/// The series of the options.
+		pub series: SeriesId,
+		/// Number of options left for sale.
+		pub amount: Balance,
+		/// Price of an option, in the native currency.
+		pub price: Balance,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The native currency: collateral and payoff of options, and currency of offers.
+		type Currency: Currency<Self::AccountId>;
+
+		/// The underlying assets.
+		type Assets: Inspect<Self::AccountId>;
+
+		/// Prices of the underlying assets in the native currency, read at expiry.
+		type Prices: PriceProvider<AssetIdOf<Self>>;
+
+		/// Origin allowed to create series and to settle them at a given price.
+		type SeriesOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Identifier of the account holding collateral.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Maximum number of series settling in a block.
+		#[pallet::constant]
+		type MaxExpiringPerBlock: Get<u32>;
+
+		/// Maximum number of blocks after its expiry in which settling a series is retried, while
+		/// the price provider has no price or the blocks are full.
+		#[pallet::constant]
+		type MaxSettlementDelay: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The identifier of the next series to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_series_id)]
+	pub type NextSeriesId<T> = StorageValue<_, SeriesId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn series)]
+	pub type Series<T: Config> = StorageMap<_, Blake2_128Concat, SeriesId, SeriesOf<T>>;
+
+	/// Options held by an account.
+	#[pallet::storage]
+	#[pallet::getter(fn options)]
+	pub type Holdings<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		SeriesId,
+		Blake2_128Concat,
+		T::AccountId,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// Options written by an account and their collateral.
+	#[pallet::storage]
+	#[pallet::getter(fn position)]
+	pub type Positions<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		SeriesId,
+		Blake2_128Concat,
+		T::AccountId,
+		Position<BalanceOf<T>>,
+	>;
+
+	/// Series to settle at a block, processed in `on_initialize`.
+	#[pallet::storage]
+	#[pallet::getter(fn expiring)]
+	pub type Expiring<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<SeriesId, T::MaxExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	/// The identifier of the next offer to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_offer_id)]
+	pub type NextOfferId<T> = StorageValue<_, OfferId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn offer)]
+	pub type Offers<T: Config> =
+		StorageMap<_, Blake2_128Concat, OfferId, Offer<T::AccountId, BalanceOf<T>>>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		AssetIdOf<T> = "AssetId",
+		BalanceOf<T> = "Balance",
+		T::BlockNumber = "BlockNumber"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A series was created. [series, underlying, kind, strike, expiry]
+		SeriesCreated(SeriesId, AssetIdOf<T>, OptionKind, FixedU128, T::BlockNumber),
+		/// Options were written. [series, writer, amount, collateral]
+		Written(SeriesId, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+		/// A writer closed options before expiry. [series, writer, amount, collateral]
+		Closed(SeriesId, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+		/// Options were transferred. [series, from, to, amount]
+		Transferred(SeriesId, T::AccountId, T::AccountId, BalanceOf<T>),
+		/// Options were offered for sale. [offer, seller, series, amount, price]
+		OfferCreated(OfferId, T::AccountId, SeriesId, BalanceOf<T>, BalanceOf<T>),
+		/// Options of an offer were bought. [offer, buyer, amount]
+		OfferFilled(OfferId, T::AccountId, BalanceOf<T>),
+		/// An offer was cancelled, returning its options to the seller. [offer]
+		OfferCancelled(OfferId),
+		/// A series settled at the price of its underlying. [series, price]
+		Settled(SeriesId, FixedU128),
+		/// A series found no price within `MaxSettlementDelay` blocks of its expiry, and is left
+		/// to `settle` and `force_settle`. [series]
+		SettlementAbandoned(SeriesId),
+		/// Options were exercised. [series, holder, amount, payoff]
+		Exercised(SeriesId, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+		/// A writer released their collateral after settlement. [series, writer, collateral]
+		Released(SeriesId, T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The series does not exist.
+		UnknownSeries,
+		/// The strike must be positive.
+		ZeroStrike,
+		/// The cap of a call must be above its strike.
+		CapNotAboveStrike,
+		/// The expiry block has passed.
+		ExpiryInPast,
+		/// Too many series expire at the expiry block.
+		TooManyExpiring,
+		/// All series or offer identifiers are in use.
+		NoAvailableId,
+		/// The series has expired.
+		Expired,
+		/// The series has not expired yet.
+		NotExpired,
+		/// The series has not settled yet.
+		NotSettled,
+		/// The series has already settled.
+		AlreadySettled,
+		/// The price provider has no price for the underlying.
+		NoPrice,
+		/// The account does not hold enough options.
+		InsufficientOptions,
+		/// The account did not write enough options.
+		InsufficientWritten,
+		/// The account wrote no options in the series.
+		NoPosition,
+		/// The options expired out of the money.
+		OutOfTheMoney,
+		/// The offer does not exist.
+		UnknownOffer,
+		/// The offer has fewer options left.
+		OfferTooSmall,
+		/// The sender is not the seller of the offer.
+		NotSeller,
+		/// The buyer is the seller of the offer.
+		BuyerIsSeller,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Settle the series expiring in this block at the price of their underlying, or retry
+		/// in the next block if the price provider has none.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let due = Expiring::<T>::take(now);
+			// Retries are scheduled at the first of the blocks up to `MaxSettlementDelay` after
+			// expiry with room for them, so the blocks tried are charged too.
+			let mut tried: Weight = 0;
+			for series in due.iter() {
+				tried = tried.saturating_add(Self::try_settle(*series, now).into());
+			}
+			let settled = due.len() as Weight;
+			T::DbWeight::get().reads_writes(1 + 2 * settled + tried, 1 + settled)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a series of `kind` options on `underlying`, with `strike` in units of the
+		/// native currency per unit of the underlying, expiring at block `expiry`. The cap of a
+		/// call must be above the strike.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 3))]
+		pub fn create_series(
+			origin: OriginFor<T>,
+			underlying: AssetIdOf<T>,
+			kind: OptionKind,
+			strike: FixedU128,
+			expiry: T::BlockNumber,
+		) -> DispatchResult {
+			T::SeriesOrigin::ensure_origin(origin)?;
+			ensure!(!strike.is_zero(), Error::<T>::ZeroStrike);
+			if let OptionKind::Call { cap } = kind {
+				ensure!(cap > strike, Error::<T>::CapNotAboveStrike);
+			}
+			ensure!(expiry > frame_system::Pallet::<T>::block_number(), Error::<T>::ExpiryInPast);
+
+			let series = NextSeriesId::<T>::get();
+			let next = series.checked_add(1).ok_or(Error::<T>::NoAvailableId)?;
+			Expiring::<T>::try_mutate(expiry, |due| due.try_push(series))
+				.map_err(|_| Error::<T>::TooManyExpiring)?;
+
+			Series::<T>::insert(
+				series,
+				OptionSeries {
+					underlying,
+					kind,
+					strike,
+					expiry,
+					outstanding: Zero::zero(),
+					settlement_price: None,
+				},
+			);
+			NextSeriesId::<T>::put(next);
+
+			Self::deposit_event(Event::SeriesCreated(series, underlying, kind, strike, expiry));
+			Ok(())
+		}
+
+		/// Write `amount` options of a series before its expiry, locking their collateral from
+		/// the sender and minting the options to the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		#[transactional]
+		pub fn write(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut details = Self::live_series(series)?;
+			let collateral =
+				mul_rational(amount, Self::max_payoff(&details).into_inner(), DIV, true)?;
+			details.outstanding =
+				details.outstanding.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+			Positions::<T>::try_mutate(series, &who, |position| {
+				let position = position.get_or_insert_with(Default::default);
+				position.written =
+					position.written.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+				position.collateral = position
+					.collateral
+					.checked_add(&collateral)
+					.ok_or(ArithmeticError::Overflow)?;
+				Ok::<_, DispatchError>(())
+			})?;
+			Self::lock(&who, collateral)?;
+			Self::give_options(series, &who, amount);
+			Series::<T>::insert(series, details);
+
+			Self::deposit_event(Event::Written(series, who, amount, collateral));
+			Ok(())
+		}
+
+		/// Close `amount` options the sender wrote and holds before expiry, burning them and
+		/// releasing their share of the sender's collateral.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		#[transactional]
+		pub fn close(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut details = Self::live_series(series)?;
+			let released = Positions::<T>::try_mutate_exists(series, &who, |maybe_position| {
+				let position = maybe_position.as_mut().ok_or(Error::<T>::NoPosition)?;
+				ensure!(amount <= position.written, Error::<T>::InsufficientWritten);
+				let mut released = mul_rational(
+					position.collateral,
+					into_u128::<T>(amount)?,
+					into_u128::<T>(position.written)?,
+					false,
+				)?;
+				position.written -= amount;
+				position.collateral -= released;
+				if position.written.is_zero() {
+					// Rounding down leaves dust with the last options closed; release it too.
+					released = released.saturating_add(position.collateral);
+					*maybe_position = None;
+				}
+				Ok::<_, DispatchError>(released)
+			})?;
+			Self::take_options(series, &who, amount)?;
+			details.outstanding = details.outstanding.saturating_sub(amount);
+			Self::pay(&who, released)?;
+			Series::<T>::insert(series, details);
+
+			Self::deposit_event(Event::Closed(series, who, amount, released));
+			Ok(())
+		}
+
+		/// Transfer `amount` options of a series to `dest`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			dest: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			ensure!(Series::<T>::contains_key(series), Error::<T>::UnknownSeries);
+			Self::take_options(series, &who, amount)?;
+			Self::give_options(series, &dest, amount);
+
+			Self::deposit_event(Event::Transferred(series, who, dest, amount));
+			Ok(())
+		}
+
+		/// Offer `amount` options of a series for sale at `price` per option. The offer holds the
+		/// options until they are bought or the offer is cancelled.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn create_offer(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			#[pallet::compact] amount: BalanceOf<T>,
+			#[pallet::compact] price: BalanceOf<T>,
+		) -> DispatchResult {
+			let seller = ensure_signed(origin)?;
+
+			ensure!(Series::<T>::contains_key(series), Error::<T>::UnknownSeries);
+			let offer = NextOfferId::<T>::get();
+			let next = offer.checked_add(1).ok_or(Error::<T>::NoAvailableId)?;
+			Self::take_options(series, &seller, amount)?;
+
+			Offers::<T>::insert(offer, Offer { seller: seller.clone(), series, amount, price });
+			NextOfferId::<T>::put(next);
+
+			Self::deposit_event(Event::OfferCreated(offer, seller, series, amount, price));
+			Ok(())
+		}
+
+		/// Buy `amount` options of an offer at its price.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		#[transactional]
+		pub fn fill_offer(
+			origin: OriginFor<T>,
+			offer: OfferId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+
+			let mut details = Offers::<T>::get(offer).ok_or(Error::<T>::UnknownOffer)?;
+			ensure!(details.seller != buyer, Error::<T>::BuyerIsSeller);
+			details.amount =
+				details.amount.checked_sub(&amount).ok_or(Error::<T>::OfferTooSmall)?;
+
+			let cost = details.price.checked_mul(&amount).ok_or(ArithmeticError::Overflow)?;
+			T::Currency::transfer(&buyer, &details.seller, cost, ExistenceRequirement::KeepAlive)?;
+			Self::give_options(details.series, &buyer, amount);
+			if details.amount.is_zero() {
+				Offers::<T>::remove(offer);
+			} else {
+				Offers::<T>::insert(offer, details);
+			}
+
+			Self::deposit_event(Event::OfferFilled(offer, buyer, amount));
+			Ok(())
+		}
+
+		/// Cancel an offer, returning its options. Only the seller can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn cancel_offer(origin: OriginFor<T>, offer: OfferId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Offers::<T>::get(offer).ok_or(Error::<T>::UnknownOffer)?;
+			ensure!(details.seller == who, Error::<T>::NotSeller);
+			Self::give_options(details.series, &who, details.amount);
+			Offers::<T>::remove(offer);
+
+			Self::deposit_event(Event::OfferCancelled(offer));
+			Ok(())
+		}
+
+		/// Exercise `amount` options of a settled series in the money, burning them for their
+		/// payoff.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		#[transactional]
+		pub fn exercise(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Series::<T>::get(series).ok_or(Error::<T>::UnknownSeries)?;
+			let payoff = Self::payoff(&details).ok_or(Error::<T>::NotSettled)?;
+			let paid = mul_rational(amount, payoff.into_inner(), DIV, false)?;
+			ensure!(!paid.is_zero(), Error::<T>::OutOfTheMoney);
+			Self::take_options(series, &who, amount)?;
+			Self::pay(&who, paid)?;
+
+			Self::deposit_event(Event::Exercised(series, who, amount, paid));
+			Ok(())
+		}
+
+		/// Release the collateral the sender locked in a settled series, less the payoff of the
+		/// options they wrote.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		#[transactional]
+		pub fn release(origin: OriginFor<T>, series: SeriesId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Series::<T>::get(series).ok_or(Error::<T>::UnknownSeries)?;
+			let payoff = Self::payoff(&details).ok_or(Error::<T>::NotSettled)?;
+			let position = Positions::<T>::take(series, &who).ok_or(Error::<T>::NoPosition)?;
+			// Owed payoffs are rounded up and paid ones down, so the collateral always covers
+			// what holders are paid.
+			let owed = mul_rational(position.written, payoff.into_inner(), DIV, true)?;
+			let released = position.collateral.saturating_sub(owed);
+			Self::pay(&who, released)?;
+
+			Self::deposit_event(Event::Released(series, who, released));
+			Ok(())
+		}
+
+		/// Settle an expired series left unsettled at the current price of its underlying.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn settle(origin: OriginFor<T>, series: SeriesId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let details = Self::unsettled_series(series)?;
+			let price = T::Prices::price(details.underlying).ok_or(Error::<T>::NoPrice)?;
+			Self::settle_at(series, details, price);
+			Ok(())
+		}
+
+		/// Settle an expired series left unsettled at `price`, for when the price provider has
+		/// none. Only `SeriesOrigin` can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn force_settle(
+			origin: OriginFor<T>,
+			series: SeriesId,
+			price: FixedU128,
+		) -> DispatchResult {
+			T::SeriesOrigin::ensure_origin(origin)?;
+
+			let details = Self::unsettled_series(series)?;
+			Self::settle_at(series, details, price);
+			Ok(())
+		}
+	}
+
+	/// Denominator of the inner value of a `FixedU128`.
+	const DIV: u128 = <FixedU128 as FixedPointNumber>::DIV;
+
+	fn into_u128<T: Config>(amount: BalanceOf<T>) -> Result<u128, DispatchError> {
+		amount.try_into().map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	/// `amount * numerator / denominator`, rounded up or down.
+	fn mul_rational<Balance: TryInto<u128> + TryFrom<u128>>(
+		amount: Balance,
+		numerator: u128,
+		denominator: u128,
+		round_up: bool,
+	) -> Result<Balance, DispatchError> {
+		let amount: u128 = amount.try_into().map_err(|_| ArithmeticError::Overflow)?;
+		let product = U256::from(amount) * U256::from(numerator);
+		let denominator = U256::from(denominator);
+		let mut result = product.checked_div(denominator).ok_or(ArithmeticError::DivisionByZero)?;
+		if round_up && !(product % denominator).is_zero() {
+			result += U256::one();
+		}
+		if result > U256::from(u128::MAX) {
+			return Err(ArithmeticError::Overflow.into())
+		}
+		Balance::try_from(result.low_u128()).map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// A series that exists and has not expired.
+		fn live_series(series: SeriesId) -> Result<SeriesOf<T>, DispatchError> {
+			let details = Series::<T>::get(series).ok_or(Error::<T>::UnknownSeries)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() < details.expiry,
+				Error::<T>::Expired
+			);
+			Ok(details)
+		}
+
+		/// A series that has expired and not settled.
+		fn unsettled_series(series: SeriesId) -> Result<SeriesOf<T>, DispatchError> {
+			let details = Series::<T>::get(series).ok_or(Error::<T>::UnknownSeries)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= details.expiry,
+				Error::<T>::NotExpired
+			);
+			ensure!(details.settlement_price.is_none(), Error::<T>::AlreadySettled);
+			Ok(details)
+		}
+
+		/// Settle a series at the current price of its underlying, or try again in the next
+		/// block until `MaxSettlementDelay` blocks after its expiry. Returns the number of blocks
+		/// tried for the retry.
+		fn try_settle(series: SeriesId, now: T::BlockNumber) -> u32 {
+			let details = match Series::<T>::get(series) {
+				Some(details) if details.settlement_price.is_none() => details,
+				_ => return 0,
+			};
+			if let Some(price) = T::Prices::price(details.underlying) {
+				Self::settle_at(series, details, price);
+				return 0
+			}
+			let last = details.expiry.saturating_add(T::MaxSettlementDelay::get().into());
+			let next = now.saturating_add(One::one());
+			let scheduled = if next <= last {
+				Self::schedule(next, series, (last - next).unique_saturated_into())
+			} else {
+				Err(0)
+			};
+			scheduled.unwrap_or_else(|tried| {
+				Self::deposit_event(Event::SettlementAbandoned(series));
+				tried
+			})
+		}
+
+		fn settle_at(series: SeriesId, mut details: SeriesOf<T>, price: FixedU128) {
+			details.settlement_price = Some(price);
+			Series::<T>::insert(series, details);
+			Self::deposit_event(Event::Settled(series, price));
+		}
+
+		/// Schedule the settlement of `series` at block `at`, or at the first of the next
+		/// `max_delay` blocks with room for it. Returns the number of blocks tried, as an error if
+		/// none had room.
+		fn schedule(mut at: T::BlockNumber, series: SeriesId, max_delay: u32) -> Result<u32, u32> {
+			let mut tried = 0u32;
+			loop {
+				tried = tried.saturating_add(1);
+				if Expiring::<T>::try_mutate(at, |due| due.try_push(series)).is_ok() {
+					return Ok(tried)
+				}
+				if tried > max_delay {
+					return Err(tried)
+				}
+				at = at.saturating_add(One::one());
+			}
+		}
+
+		/// The most an option of a series can pay, locked as its collateral.
+		fn max_payoff(details: &SeriesOf<T>) -> FixedU128 {
+			match details.kind {
+				OptionKind::Call { cap } => cap.saturating_sub(details.strike),
+				OptionKind::Put => details.strike,
+			}
+		}
+
+		/// The payoff of an option of a settled series, in the native currency.
+		fn payoff(details: &SeriesOf<T>) -> Option<FixedU128> {
+			let price = details.settlement_price?;
+			Some(match details.kind {
+				OptionKind::Call { cap } => price.min(cap).saturating_sub(details.strike),
+				OptionKind::Put => details.strike.saturating_sub(price),
+			})
+		}
+
+		/// The account holding collateral.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// Transfer collateral from a writer to the pallet account.
+		fn lock(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			T::Currency::transfer(who, &Self::account_id(), amount, ExistenceRequirement::KeepAlive)
+		}
+
+		/// Pay released collateral or a payoff out of the pallet account.
+		fn pay(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(())
+			}
+			T::Currency::transfer(
+				&Self::account_id(),
+				who,
+				amount,
+				ExistenceRequirement::AllowDeath,
+			)
+		}
+
+		fn take_options(
+			series: SeriesId,
+			who: &T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			Holdings::<T>::try_mutate_exists(series, who, |held| {
+				let left = held
+					.unwrap_or_default()
+					.checked_sub(&amount)
+					.ok_or(Error::<T>::InsufficientOptions)?;
+				*held = Some(left).filter(|left| !left.is_zero());
+				Ok(())
+			})
+		}
+
+		fn give_options(series: SeriesId, who: &T::AccountId, amount: BalanceOf<T>) {
+			if !amount.is_zero() {
+				Holdings::<T>::mutate(series, who, |held| *held = held.saturating_add(amount));
+			}
+		}
+	}
+}
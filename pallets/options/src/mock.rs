@@ -0,0 +1,169 @@
+use crate as pallet_options;
+use frame_support::{parameter_types, traits::Hooks, weights::Weight, PalletId};
+use frame_system::{self as system, EnsureRoot};
+use pallet_price_feed::PriceProvider;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	FixedU128,
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+		Options: pallet_options::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AssetDeposit: u64 = 0;
+	pub const ApprovalDeposit: u64 = 0;
+	pub const StringLimit: u32 = 16;
+	pub const MetadataDepositBase: u64 = 0;
+	pub const MetadataDepositPerByte: u64 = 0;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<u64>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static PRICE: RefCell<Option<FixedU128>> = RefCell::new(None);
+}
+
+/// Serves the price set with `set_price` for every asset.
+pub struct MockPrices;
+
+impl PriceProvider<u32> for MockPrices {
+	fn price(_asset: u32) -> Option<FixedU128> {
+		PRICE.with(|price| *price.borrow())
+	}
+
+	fn price_weight() -> Weight {
+		0
+	}
+}
+
+pub fn set_price(price: Option<FixedU128>) {
+	PRICE.with(|current| *current.borrow_mut() = price);
+}
+
+parameter_types! {
+	pub const OptionsPalletId: PalletId = PalletId(*b"py/optns");
+	pub const MaxExpiringPerBlock: u32 = 2;
+	pub const MaxSettlementDelay: u32 = 2;
+}
+
+impl pallet_options::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type Assets = Assets;
+	type Prices = MockPrices;
+	type SeriesOrigin = EnsureRoot<u64>;
+	type PalletId = OptionsPalletId;
+	type MaxExpiringPerBlock = MaxExpiringPerBlock;
+	type MaxSettlementDelay = MaxSettlementDelay;
+}
+
+/// The underlying asset of the series.
+pub const ASSET: u32 = 0;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000), (2, 1_000), (3, 1_000), (4, 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Assets::force_create(Origin::root(), ASSET, 1, true, 1).unwrap();
+		for who in 1..=4 {
+			Assets::mint(Origin::signed(1), ASSET, who, 1_000).unwrap();
+		}
+	});
+	ext
+}
+
+/// Run blocks up to and including `n`, initializing the pallet in each.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Options::on_initialize(System::block_number());
+	}
+}
@@ -0,0 +1,351 @@
+use crate::{mock::*, Error, OptionKind, Position};
+use frame_support::{assert_noop, assert_ok, traits::tokens::fungibles::Inspect};
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128};
+
+fn free(who: u64) -> u64 {
+	Balances::free_balance(who)
+}
+
+fn asset(who: u64) -> u64 {
+	Assets::balance(ASSET, who)
+}
+
+fn price(n: u128, d: u128) -> FixedU128 {
+	FixedU128::saturating_from_rational(n, d)
+}
+
+// Series 0 of `kind` options with `strike`, expiring at block 10, with 100 written by 1.
+fn setup(kind: OptionKind, strike: FixedU128) {
+	assert_ok!(Options::create_series(Origin::root(), ASSET, kind, strike, 10));
+	assert_ok!(Options::write(Origin::signed(1), 0, 100));
+}
+
+// Like `setup`, with the options held by 3 and the series settled at `settlement`.
+fn settled(kind: OptionKind, strike: FixedU128, settlement: FixedU128) {
+	setup(kind, strike);
+	assert_ok!(Options::transfer(Origin::signed(1), 0, 3, 100));
+	set_price(Some(settlement));
+	run_to_block(10);
+}
+
+#[test]
+fn create_series_checks_strike_and_expiry() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Options::create_series(Origin::signed(1), ASSET, OptionKind::Put, price(2, 1), 10),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(0, 1), 10),
+			Error::<Test>::ZeroStrike
+		);
+		assert_noop!(
+			Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(2, 1), 1),
+			Error::<Test>::ExpiryInPast
+		);
+		let call = OptionKind::Call { cap: price(2, 1) };
+		assert_noop!(
+			Options::create_series(Origin::root(), ASSET, call, price(2, 1), 10),
+			Error::<Test>::CapNotAboveStrike
+		);
+
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(2, 1), 10));
+		let series = Options::series(0).unwrap();
+		assert_eq!(series.kind, OptionKind::Put);
+		assert_eq!(series.strike, price(2, 1));
+		assert_eq!(series.outstanding, 0);
+		assert_eq!(series.settlement_price, None);
+		assert_eq!(Options::next_series_id(), 1);
+		assert_eq!(Options::expiring(10).into_inner(), vec![0]);
+	});
+}
+
+#[test]
+fn series_expiring_in_a_block_are_limited() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(2, 1), 10));
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(4, 1), 10));
+		assert_noop!(
+			Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(3, 1), 10),
+			Error::<Test>::TooManyExpiring
+		);
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(3, 1), 11));
+	});
+}
+
+#[test]
+fn writing_puts_locks_the_strike() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		setup(OptionKind::Put, price(2, 1));
+		assert_eq!(free(1), 800);
+		assert_eq!(free(Options::account_id()), 200);
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert_eq!(Options::options(0, 1), 100);
+		assert_eq!(Options::position(0, 1), Some(Position { written: 100, collateral: 200 }));
+		assert_eq!(Options::series(0).unwrap().outstanding, 100);
+
+		// The strike of a put rounds the collateral up.
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(3, 2), 10));
+		assert_ok!(Options::write(Origin::signed(2), 1, 3));
+		assert_eq!(Options::position(1, 2), Some(Position { written: 3, collateral: 5 }));
+		assert_eq!(free(2), 995);
+	});
+}
+
+#[test]
+fn writing_calls_locks_the_cap_above_the_strike() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Call { cap: price(5, 2) }, price(2, 1));
+		assert_eq!(free(1), 950);
+		assert_eq!(free(Options::account_id()), 50);
+		// Calls are settled in cash, so the underlying is not touched.
+		assert_eq!(asset(1), 1_000);
+		assert_eq!(<Assets as Inspect<u64>>::total_issuance(ASSET), 4_000);
+		assert_eq!(Options::options(0, 1), 100);
+		assert_eq!(Options::position(0, 1), Some(Position { written: 100, collateral: 50 }));
+
+		assert_noop!(Options::write(Origin::signed(1), 1, 100), Error::<Test>::UnknownSeries);
+	});
+}
+
+#[test]
+fn closing_releases_collateral_proportionally() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(3, 2), 10));
+		assert_ok!(Options::write(Origin::signed(1), 0, 3));
+		assert_eq!(free(1), 995);
+
+		assert_noop!(Options::close(Origin::signed(1), 0, 4), Error::<Test>::InsufficientWritten);
+		assert_noop!(Options::close(Origin::signed(2), 0, 1), Error::<Test>::NoPosition);
+
+		assert_ok!(Options::close(Origin::signed(1), 0, 1));
+		assert_eq!(free(1), 996);
+		assert_eq!(Options::position(0, 1), Some(Position { written: 2, collateral: 4 }));
+		assert_eq!(Options::series(0).unwrap().outstanding, 2);
+
+		// Options sold can't be closed.
+		assert_ok!(Options::transfer(Origin::signed(1), 0, 2, 1));
+		assert_noop!(Options::close(Origin::signed(1), 0, 2), Error::<Test>::InsufficientOptions);
+		assert_ok!(Options::transfer(Origin::signed(2), 0, 1, 1));
+
+		// The last close releases the rounding dust.
+		assert_ok!(Options::close(Origin::signed(1), 0, 2));
+		assert_eq!(free(1), 1_000);
+		assert_eq!(Options::position(0, 1), None);
+		assert_eq!(Options::options(0, 1), 0);
+		assert_eq!(Options::series(0).unwrap().outstanding, 0);
+	});
+}
+
+#[test]
+fn transfer_moves_options() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		assert_ok!(Options::transfer(Origin::signed(1), 0, 3, 30));
+		assert_eq!(Options::options(0, 1), 70);
+		assert_eq!(Options::options(0, 3), 30);
+
+		assert_noop!(
+			Options::transfer(Origin::signed(3), 0, 1, 31),
+			Error::<Test>::InsufficientOptions
+		);
+		assert_noop!(Options::transfer(Origin::signed(1), 1, 3, 1), Error::<Test>::UnknownSeries);
+	});
+}
+
+#[test]
+fn offers_sell_options_at_a_fixed_price() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		assert_ok!(Options::create_offer(Origin::signed(1), 0, 50, 3));
+		assert_eq!(Options::options(0, 1), 50);
+		assert_eq!(Options::next_offer_id(), 1);
+
+		assert_noop!(Options::fill_offer(Origin::signed(1), 0, 10), Error::<Test>::BuyerIsSeller);
+		assert_noop!(Options::fill_offer(Origin::signed(3), 0, 51), Error::<Test>::OfferTooSmall);
+		assert_noop!(Options::fill_offer(Origin::signed(3), 1, 10), Error::<Test>::UnknownOffer);
+
+		assert_ok!(Options::fill_offer(Origin::signed(3), 0, 20));
+		assert_eq!(free(3), 940);
+		assert_eq!(free(1), 860);
+		assert_eq!(Options::options(0, 3), 20);
+		assert_eq!(Options::offer(0).unwrap().amount, 30);
+
+		assert_noop!(Options::cancel_offer(Origin::signed(3), 0), Error::<Test>::NotSeller);
+		assert_ok!(Options::cancel_offer(Origin::signed(1), 0));
+		assert_eq!(Options::options(0, 1), 80);
+		assert!(Options::offer(0).is_none());
+	});
+}
+
+#[test]
+fn series_settle_at_expiry() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		run_to_block(9);
+		assert_noop!(Options::exercise(Origin::signed(1), 0, 10), Error::<Test>::NotSettled);
+		assert_noop!(Options::release(Origin::signed(1), 0), Error::<Test>::NotSettled);
+
+		// Without a price, settlement is retried in the next block.
+		run_to_block(10);
+		assert_eq!(Options::series(0).unwrap().settlement_price, None);
+		assert_eq!(Options::expiring(11).into_inner(), vec![0]);
+		assert_noop!(Options::write(Origin::signed(1), 0, 10), Error::<Test>::Expired);
+		assert_noop!(Options::close(Origin::signed(1), 0, 10), Error::<Test>::Expired);
+
+		set_price(Some(price(3, 2)));
+		run_to_block(11);
+		assert_eq!(Options::series(0).unwrap().settlement_price, Some(price(3, 2)));
+		assert!(Options::expiring(12).is_empty());
+	});
+}
+
+#[test]
+fn settlement_is_retried_for_a_bounded_number_of_blocks() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		assert_noop!(Options::settle(Origin::signed(2), 0), Error::<Test>::NotExpired);
+
+		// Retries stop `MaxSettlementDelay` blocks after expiry.
+		run_to_block(12);
+		assert!(Options::expiring(13).is_empty());
+		run_to_block(20);
+		assert_eq!(Options::series(0).unwrap().settlement_price, None);
+
+		// Anyone can then settle the series once there is a price.
+		assert_noop!(Options::settle(Origin::signed(2), 0), Error::<Test>::NoPrice);
+		set_price(Some(price(3, 2)));
+		assert_ok!(Options::settle(Origin::signed(2), 0));
+		assert_eq!(Options::series(0).unwrap().settlement_price, Some(price(3, 2)));
+		assert_noop!(Options::settle(Origin::signed(2), 0), Error::<Test>::AlreadySettled);
+	});
+}
+
+#[test]
+fn retries_skip_full_blocks() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(2, 1), 11));
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(3, 1), 11));
+
+		// Block 11 is full, so series 0 is retried in block 12, its last retry.
+		run_to_block(10);
+		assert_eq!(Options::expiring(12).into_inner(), vec![0]);
+		run_to_block(12);
+		assert!(!Options::expiring(13).contains(&0));
+	});
+}
+
+#[test]
+fn series_origin_can_settle_at_a_given_price() {
+	new_test_ext().execute_with(|| {
+		setup(OptionKind::Put, price(2, 1));
+		assert_noop!(
+			Options::force_settle(Origin::root(), 0, price(3, 2)),
+			Error::<Test>::NotExpired
+		);
+		run_to_block(11);
+
+		assert_noop!(
+			Options::force_settle(Origin::signed(1), 0, price(3, 2)),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Options::force_settle(Origin::root(), 0, price(3, 2)));
+		assert_eq!(Options::series(0).unwrap().settlement_price, Some(price(3, 2)));
+		assert_noop!(
+			Options::force_settle(Origin::root(), 0, price(1, 1)),
+			Error::<Test>::AlreadySettled
+		);
+
+		// A retry left scheduled finds the series settled.
+		run_to_block(13);
+		assert_eq!(Options::series(0).unwrap().settlement_price, Some(price(3, 2)));
+	});
+}
+
+#[test]
+fn exercising_puts_in_the_money() {
+	new_test_ext().execute_with(|| {
+		// Each option pays 2 - 1.5 in the native currency.
+		settled(OptionKind::Put, price(2, 1), price(3, 2));
+
+		assert_ok!(Options::exercise(Origin::signed(3), 0, 40));
+		assert_eq!(free(3), 1_020);
+		assert_eq!(Options::options(0, 3), 60);
+		assert_noop!(
+			Options::exercise(Origin::signed(3), 0, 61),
+			Error::<Test>::InsufficientOptions
+		);
+		assert_ok!(Options::exercise(Origin::signed(3), 0, 60));
+		assert_eq!(free(3), 1_050);
+
+		assert_ok!(Options::release(Origin::signed(1), 0));
+		assert_eq!(free(1), 950);
+		assert_noop!(Options::release(Origin::signed(1), 0), Error::<Test>::NoPosition);
+	});
+}
+
+#[test]
+fn exercising_calls_in_the_money() {
+	new_test_ext().execute_with(|| {
+		// Each option pays 2.5 - 2 in the native currency, of the 1 locked for it.
+		settled(OptionKind::Call { cap: price(3, 1) }, price(2, 1), price(5, 2));
+
+		assert_ok!(Options::exercise(Origin::signed(3), 0, 100));
+		assert_eq!(free(3), 1_050);
+		assert_eq!(Options::options(0, 3), 0);
+
+		assert_ok!(Options::release(Origin::signed(1), 0));
+		assert_eq!(free(1), 950);
+		assert_eq!(asset(3), 1_000);
+	});
+}
+
+#[test]
+fn call_payoffs_stop_at_the_cap() {
+	new_test_ext().execute_with(|| {
+		// Each option pays 3 - 2, all of its collateral.
+		settled(OptionKind::Call { cap: price(3, 1) }, price(2, 1), price(4, 1));
+
+		assert_ok!(Options::exercise(Origin::signed(3), 0, 100));
+		assert_eq!(free(3), 1_100);
+		assert_ok!(Options::release(Origin::signed(1), 0));
+		assert_eq!(free(1), 900);
+	});
+}
+
+#[test]
+fn options_out_of_the_money_expire_worthless() {
+	new_test_ext().execute_with(|| {
+		settled(OptionKind::Put, price(2, 1), price(3, 1));
+
+		assert_noop!(Options::exercise(Origin::signed(3), 0, 100), Error::<Test>::OutOfTheMoney);
+		assert_ok!(Options::release(Origin::signed(1), 0));
+		assert_eq!(free(1), 1_000);
+	});
+}
+
+#[test]
+fn payoffs_never_exceed_collateral() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		assert_ok!(Options::create_series(Origin::root(), ASSET, OptionKind::Put, price(1, 1), 10));
+		assert_ok!(Options::write(Origin::signed(1), 0, 10));
+		assert_ok!(Options::transfer(Origin::signed(1), 0, 3, 5));
+		assert_ok!(Options::transfer(Origin::signed(1), 0, 4, 5));
+		set_price(Some(price(2, 3)));
+		run_to_block(10);
+
+		// Each option pays a third: holders are paid rounded down, and the writer is left what
+		// is not owed rounded up.
+		assert_ok!(Options::exercise(Origin::signed(3), 0, 5));
+		assert_ok!(Options::exercise(Origin::signed(4), 0, 5));
+		assert_eq!(free(3), 1_001);
+		assert_eq!(free(4), 1_001);
+		assert_ok!(Options::release(Origin::signed(1), 0));
+		assert_eq!(free(1), 996);
+		assert_eq!(free(Options::account_id()), 2);
+		assert_eq!(Balances::total_issuance(), issuance);
+	});
+}
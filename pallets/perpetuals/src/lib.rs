@@ -0,0 +1,804 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Perpetual futures on assets, priced by virtual AMMs and anchored to an index price.
+//!
+//! Each market trades its underlying asset against the native currency on a virtual
+//! constant-product AMM, whose reserves set the mark price but hold no funds. Traders open a long
+//! or short position of some notional value in the native currency, backed by margin isolated to
+//! that position, up to the market's maximum leverage. They can then increase or decrease the
+//! position, add or remove margin, and close it for its margin plus profit or less loss. Every
+//! trade pays a fee to the insurance fund.
+//!
+//! Every `FundingPeriod` blocks, `on_initialize` charges funding on each market: the mark price
+//! above the index price given by the price provider, scaled by `FundingFactor`, is paid per unit
+//! of the underlying by longs to shorts, and by shorts to longs when it is below. Funding accrues
+//! in a cumulative index and is settled into the margin of a position when it is next changed.
+//!
+//! Anyone can liquidate a position whose equity, its margin plus unrealized profit less funding
+//! owed, falls below the maintenance margin, for a reward out of what is left of it. The insurance
+//! fund takes the rest, and the difference between the funding paid by and to each side. It
+//! absorbs the negative equity of positions liquidated at a loss larger than their margin.
+//!
+//! Margin and the insurance fund are held in the account of `PalletId`, and payouts are transferred
+//! out of it, so the pallet never mints. A loss larger than the insurance fund, from a shortfall or
+//! from funding, is recorded as bad debt, which is repaid out of the fund before it grows again.
+//! Until then the account may not hold enough to pay every trader, and payouts it can't cover fail
+//! rather than create unbacked funds.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{Currency, ExistenceRequirement},
+		transactional, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_price_feed::PriceProvider;
+	use sp_core::U256;
+	use sp_runtime::{
+		traits::{
+			AccountIdConversion, CheckedAdd, CheckedSub, SaturatedConversion, Saturating,
+			StaticLookup, Zero,
+		},
+		ArithmeticError, FixedI128, FixedPointNumber, FixedU128, Perbill,
+	};
+	use sp_std::{
+		cmp,
+		convert::{TryFrom, TryInto},
+	};
+
+	/// Identifier of a market.
+	pub type MarketId = u32;
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type MarketOf<T> = Market<<T as Config>::AssetId>;
+	pub type PositionOf<T> = Position<BalanceOf<T>>;
+
+	/// The direction of a position.
+	#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub enum Side {
+		/// Profits when the price of the underlying rises.
+		Long,
+		/// Profits when the price of the underlying falls.
+		Short,
+	}
+
+	/// A market and the reserves of its virtual AMM.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Market<AssetId> {
+		/// The asset the market trades.
+		pub underlying: AssetId,
+		/// Virtual reserve of the underlying.
+		pub base_reserve: u128,
+		/// Virtual reserve of the native currency.
+		pub quote_reserve: u128,
+		/// Maximum notional value of a position per unit of its margin.
+		pub max_leverage: u32,
+		/// Funding paid per unit of the underlying held long since the market was created.
+		pub cumulative_funding: FixedI128,
+		/// Total size of long positions.
+		pub long_size: u128,
+		/// Total size of short positions.
+		pub short_size: u128,
+	}
+
+	impl<AssetId> Market<AssetId> {
+		/// The price of the underlying on the virtual AMM, in the native currency.
+		pub fn mark_price(&self) -> Option<FixedU128> {
+			FixedU128::checked_from_rational(self.quote_reserve, self.base_reserve)
+		}
+
+		/// Trade `quote` of the native currency on the virtual AMM to open `side`: longs add it to
+		/// the quote reserve and receive units of the underlying, shorts take it out and owe units.
+		/// Returns the units, rounded against the trader.
+		fn open(&mut self, side: Side, quote: u128) -> Result<u128, ArithmeticError> {
+			let k = self.invariant();
+			let quote_reserve = match side {
+				Side::Long => self.quote_reserve.checked_add(quote),
+				Side::Short => self.quote_reserve.checked_sub(quote).filter(|r| !r.is_zero()),
+			}
+			.ok_or(ArithmeticError::Overflow)?;
+			let base_reserve = div(k, quote_reserve, true)?;
+			let size = match side {
+				Side::Long => self.base_reserve.checked_sub(base_reserve),
+				Side::Short => base_reserve.checked_sub(self.base_reserve),
+			}
+			.ok_or(ArithmeticError::Underflow)?;
+			self.base_reserve = base_reserve;
+			self.quote_reserve = quote_reserve;
+			Ok(size)
+		}
+
+		/// Trade `size` units of the underlying on the virtual AMM to close `side`: longs sell them
+		/// for native currency, shorts buy them back. Returns the native currency, rounded against
+		/// the trader.
+		fn close(&mut self, side: Side, size: u128) -> Result<u128, ArithmeticError> {
+			let k = self.invariant();
+			let base_reserve = match side {
+				Side::Long => self.base_reserve.checked_add(size),
+				Side::Short => self.base_reserve.checked_sub(size).filter(|r| !r.is_zero()),
+			}
+			.ok_or(ArithmeticError::Overflow)?;
+			let quote_reserve = div(k, base_reserve, true)?;
+			let quote = match side {
+				Side::Long => self.quote_reserve.checked_sub(quote_reserve),
+				Side::Short => quote_reserve.checked_sub(self.quote_reserve),
+			}
+			.ok_or(ArithmeticError::Underflow)?;
+			self.base_reserve = base_reserve;
+			self.quote_reserve = quote_reserve;
+			Ok(quote)
+		}
+
+		/// The size of one side.
+		fn size_mut(&mut self, side: Side) -> &mut u128 {
+			match side {
+				Side::Long => &mut self.long_size,
+				Side::Short => &mut self.short_size,
+			}
+		}
+
+		fn invariant(&self) -> U256 {
+			U256::from(self.base_reserve) * U256::from(self.quote_reserve)
+		}
+	}
+
+	/// A position of an account in a market, with its isolated margin.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Position<Balance> {
+		/// Long or short.
+		pub side: Side,
+		/// Units of the underlying.
+		pub size: u128,
+		/// Native currency the size was opened for: paid for longs, received for shorts.
+		pub open_notional: Balance,
+		/// Margin backing the position, in the native currency.
+		pub margin: Balance,
+		/// `cumulative_funding` of the market when funding was last settled into the margin.
+		pub funding_index: FixedI128,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The native currency: margin, notional value and funding of positions.
+		type Currency: Currency<Self::AccountId>;
+
+		/// Identifier of the assets markets trade.
+		type AssetId: Parameter + Member + Copy;
+
+		/// Index prices of the underlying assets in the native currency, used for funding.
+		type Prices: PriceProvider<Self::AssetId>;
+
+		/// Origin allowed to create markets.
+		type MarketOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Identifier of the account holding margin and the insurance fund.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Maximum number of markets.
+		#[pallet::constant]
+		type MaxMarkets: Get<u32>;
+
+		/// Number of blocks between funding payments.
+		#[pallet::constant]
+		type FundingPeriod: Get<Self::BlockNumber>;
+
+		/// Part of the difference between the mark and index prices paid as funding each period.
+		#[pallet::constant]
+		type FundingFactor: Get<Perbill>;
+
+		/// Fee on the notional value traded, paid to the insurance fund.
+		#[pallet::constant]
+		type TradeFee: Get<Perbill>;
+
+		/// Equity below which a position can be liquidated, relative to its notional value.
+		#[pallet::constant]
+		type MaintenanceMargin: Get<Perbill>;
+
+		/// Reward for liquidating a position, relative to its notional value, paid out of its
+		/// equity.
+		#[pallet::constant]
+		type LiquidationReward: Get<Perbill>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The identifier of the next market to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_market_id)]
+	pub type NextMarketId<T> = StorageValue<_, MarketId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn market)]
+	pub type Markets<T: Config> = StorageMap<_, Blake2_128Concat, MarketId, MarketOf<T>>;
+
+	/// The position of an account in a market.
+	#[pallet::storage]
+	#[pallet::getter(fn position)]
+	pub type Positions<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		MarketId,
+		Blake2_128Concat,
+		T::AccountId,
+		PositionOf<T>,
+	>;
+
+	/// Funds of the insurance fund, held in the pallet account.
+	#[pallet::storage]
+	#[pallet::getter(fn insurance_fund)]
+	pub type InsuranceFund<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	/// Losses the insurance fund could not cover, which the pallet account is short of.
+	#[pallet::storage]
+	#[pallet::getter(fn bad_debt)]
+	pub type BadDebt<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		T::AssetId = "AssetId",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A market was created. [market, underlying]
+		MarketCreated(MarketId, T::AssetId),
+		/// A position was opened or changed. [market, who, side, size, margin]
+		PositionChanged(MarketId, T::AccountId, Side, u128, BalanceOf<T>),
+		/// A position was closed. [market, who, payout]
+		PositionClosed(MarketId, T::AccountId, BalanceOf<T>),
+		/// A position was liquidated. [market, who, liquidator, reward]
+		Liquidated(MarketId, T::AccountId, T::AccountId, BalanceOf<T>),
+		/// The insurance fund absorbed the negative equity of a position. [market, who, covered]
+		ShortfallCovered(MarketId, T::AccountId, BalanceOf<T>),
+		/// A loss larger than the insurance fund was added to the bad debt. [market, uncovered]
+		LossUncovered(MarketId, BalanceOf<T>),
+		/// Funding was charged, paid by longs per unit of the underlying. [market, payment]
+		FundingCharged(MarketId, FixedI128),
+		/// Funds were added to the insurance fund. [who, amount]
+		InsuranceFunded(T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The market does not exist.
+		UnknownMarket,
+		/// Reserves and maximum leverage must be positive.
+		InvalidMarket,
+		/// There are `MaxMarkets` markets already.
+		TooManyMarkets,
+		/// The account has a position in the market already.
+		PositionExists,
+		/// The account has no position in the market.
+		NoPosition,
+		/// The notional value traded must be positive.
+		ZeroNotional,
+		/// The size must be positive and less than the size of the position.
+		InvalidSize,
+		/// The position would exceed the maximum leverage of the market.
+		TooMuchLeverage,
+		/// The margin does not cover the amount.
+		InsufficientMargin,
+		/// The position's losses exceed its margin, so it can only be liquidated.
+		Underwater,
+		/// The position's equity is above the maintenance margin.
+		NotLiquidatable,
+		/// The pallet account does not hold enough to pay out, as losses exceeded the insurance
+		/// fund.
+		InsufficientFunds,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Charge funding on every market at the end of each funding period.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let period = T::FundingPeriod::get();
+			if period.is_zero() || !(now % period).is_zero() {
+				return 0
+			}
+			let markets = NextMarketId::<T>::get();
+			for market in 0..markets {
+				Self::charge_funding(market);
+			}
+			let markets = markets as Weight;
+			// Each market reads the price of its underlying.
+			T::DbWeight::get()
+				.reads_writes(3 + markets, 2 + markets)
+				.saturating_add(markets.saturating_mul(T::Prices::price_weight()))
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a market trading `underlying`, with the given virtual reserves of the underlying
+		/// and the native currency, which set its initial mark price and depth.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn create_market(
+			origin: OriginFor<T>,
+			underlying: T::AssetId,
+			#[pallet::compact] base_reserve: u128,
+			#[pallet::compact] quote_reserve: u128,
+			max_leverage: u32,
+		) -> DispatchResult {
+			T::MarketOrigin::ensure_origin(origin)?;
+			ensure!(
+				!base_reserve.is_zero() && !quote_reserve.is_zero() && max_leverage > 0,
+				Error::<T>::InvalidMarket
+			);
+			let market = NextMarketId::<T>::get();
+			ensure!(market < T::MaxMarkets::get(), Error::<T>::TooManyMarkets);
+
+			Markets::<T>::insert(
+				market,
+				Market {
+					underlying,
+					base_reserve,
+					quote_reserve,
+					max_leverage,
+					cumulative_funding: Zero::zero(),
+					long_size: 0,
+					short_size: 0,
+				},
+			);
+			NextMarketId::<T>::put(market + 1);
+
+			Self::deposit_event(Event::MarketCreated(market, underlying));
+			Ok(())
+		}
+
+		/// Open a position of `notional` value in the native currency, locking `margin` from the
+		/// sender. The trade fee is taken from the margin.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 5))]
+		#[transactional]
+		pub fn open_position(
+			origin: OriginFor<T>,
+			market: MarketId,
+			side: Side,
+			#[pallet::compact] margin: BalanceOf<T>,
+			#[pallet::compact] notional: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!Positions::<T>::contains_key(market, &who), Error::<T>::PositionExists);
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let position = Position {
+				side,
+				size: 0,
+				open_notional: Zero::zero(),
+				margin: Zero::zero(),
+				funding_index: details.cumulative_funding,
+			};
+			Self::increase(market, details, &who, position, margin, notional)
+		}
+
+		/// Add `notional` value to the sender's position, locking `margin` more from the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 5))]
+		#[transactional]
+		pub fn increase_position(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] margin: BalanceOf<T>,
+			#[pallet::compact] notional: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let position = Positions::<T>::get(market, &who).ok_or(Error::<T>::NoPosition)?;
+			Self::increase(market, details, &who, position, margin, notional)
+		}
+
+		/// Close `size` units of the sender's position, realizing their profit or loss into its
+		/// margin.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		#[transactional]
+		pub fn decrease_position(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] size: u128,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let mut position = Positions::<T>::get(market, &who).ok_or(Error::<T>::NoPosition)?;
+			ensure!(!size.is_zero() && size < position.size, Error::<T>::InvalidSize);
+			Self::reduce(&mut details, &mut position, size)?;
+			Markets::<T>::insert(market, details);
+			Positions::<T>::insert(market, &who, &position);
+
+			Self::deposit_event(Event::PositionChanged(
+				market,
+				who,
+				position.side,
+				position.size,
+				position.margin,
+			));
+			Ok(())
+		}
+
+		/// Close the sender's position, paying out its margin plus profit or less loss.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 5))]
+		#[transactional]
+		pub fn close_position(origin: OriginFor<T>, market: MarketId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let mut position = Positions::<T>::take(market, &who).ok_or(Error::<T>::NoPosition)?;
+			let size = position.size;
+			Self::reduce(&mut details, &mut position, size)?;
+			Markets::<T>::insert(market, details);
+			Self::pay(&who, position.margin)?;
+
+			Self::deposit_event(Event::PositionClosed(market, who, position.margin));
+			Ok(())
+		}
+
+		/// Lock `amount` more margin from the sender for their position.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		#[transactional]
+		pub fn add_margin(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let mut position = Positions::<T>::get(market, &who).ok_or(Error::<T>::NoPosition)?;
+			Self::lock(&who, amount)?;
+			position.margin =
+				position.margin.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+			Self::settle_funding(&details, &mut position)?;
+			Positions::<T>::insert(market, &who, &position);
+
+			Self::deposit_event(Event::PositionChanged(
+				market,
+				who,
+				position.side,
+				position.size,
+				position.margin,
+			));
+			Ok(())
+		}
+
+		/// Withdraw `amount` of the margin of the sender's position, within the maximum leverage
+		/// and above the maintenance margin.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		#[transactional]
+		pub fn remove_margin(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let mut position = Positions::<T>::get(market, &who).ok_or(Error::<T>::NoPosition)?;
+			Self::settle_funding(&details, &mut position)?;
+			position.margin =
+				position.margin.checked_sub(&amount).ok_or(Error::<T>::InsufficientMargin)?;
+			ensure!(Self::within_leverage(&details, &position), Error::<T>::TooMuchLeverage);
+			let (equity, value) = Self::equity(&details, &position)?;
+			ensure!(!Self::below_maintenance(equity, value), Error::<T>::TooMuchLeverage);
+			Positions::<T>::insert(market, &who, &position);
+			Self::pay(&who, amount)?;
+
+			Self::deposit_event(Event::PositionChanged(
+				market,
+				who,
+				position.side,
+				position.size,
+				position.margin,
+			));
+			Ok(())
+		}
+
+		/// Liquidate the position of `who`, whose equity is below the maintenance margin. The
+		/// sender is rewarded out of what is left of the position, and the insurance fund takes the
+		/// rest or covers the shortfall, as far as it can.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6, 6))]
+		#[transactional]
+		pub fn liquidate(
+			origin: OriginFor<T>,
+			market: MarketId,
+			who: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let liquidator = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+
+			let mut details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let position = Positions::<T>::take(market, &who).ok_or(Error::<T>::NoPosition)?;
+			let (equity, value) = Self::equity(&details, &position)?;
+			ensure!(Self::below_maintenance(equity, value), Error::<T>::NotLiquidatable);
+
+			details.close(position.side, position.size)?;
+			let size = details.size_mut(position.side);
+			*size = size.saturating_sub(position.size);
+			let reward = if equity >= 0 {
+				let reward = cmp::min(T::LiquidationReward::get().mul_floor(value), equity as u128);
+				Self::add_to_fund((equity as u128).saturating_sub(reward).saturated_into());
+				reward.saturated_into()
+			} else {
+				let shortfall = equity.saturating_neg().saturated_into::<u128>().saturated_into();
+				let covered = Self::cover(market, shortfall);
+				Self::deposit_event(Event::ShortfallCovered(market, who.clone(), covered));
+				Zero::zero()
+			};
+			Markets::<T>::insert(market, details);
+			Self::pay(&liquidator, reward)?;
+
+			Self::deposit_event(Event::Liquidated(market, who, liquidator, reward));
+			Ok(())
+		}
+
+		/// Add `amount` from the sender to the insurance fund, repaying any bad debt first.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 4))]
+		pub fn fund_insurance(
+			origin: OriginFor<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::lock(&who, amount)?;
+			Self::add_to_fund(amount);
+
+			Self::deposit_event(Event::InsuranceFunded(who, amount));
+			Ok(())
+		}
+	}
+
+	/// `x / d`, rounded up or down.
+	fn div(x: U256, d: u128, round_up: bool) -> Result<u128, ArithmeticError> {
+		let d = U256::from(d);
+		let mut result = x.checked_div(d).ok_or(ArithmeticError::DivisionByZero)?;
+		if round_up && !(x % d).is_zero() {
+			result += U256::one();
+		}
+		if result > U256::from(u128::MAX) {
+			return Err(ArithmeticError::Overflow)
+		}
+		Ok(result.low_u128())
+	}
+
+	/// `amount * numerator / denominator`, rounded up or down.
+	fn mul_div(
+		amount: u128,
+		numerator: u128,
+		denominator: u128,
+		round_up: bool,
+	) -> Result<u128, ArithmeticError> {
+		div(U256::from(amount) * U256::from(numerator), denominator, round_up)
+	}
+
+	fn signed<T: Config>(amount: BalanceOf<T>) -> i128 {
+		amount.saturated_into::<u128>().saturated_into()
+	}
+
+	fn unsigned<T: Config>(amount: i128) -> Result<BalanceOf<T>, DispatchError> {
+		let amount = u128::try_from(amount).map_err(|_| ArithmeticError::Underflow)?;
+		amount.try_into().map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	fn to_signed(price: FixedU128) -> FixedI128 {
+		FixedI128::from_inner(price.into_inner().saturated_into())
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding margin and the insurance fund.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// Add margin and notional value to a position and store it.
+		fn increase(
+			market: MarketId,
+			mut details: MarketOf<T>,
+			who: &T::AccountId,
+			mut position: PositionOf<T>,
+			margin: BalanceOf<T>,
+			notional: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(!notional.is_zero(), Error::<T>::ZeroNotional);
+			Self::lock(who, margin)?;
+			position.margin =
+				position.margin.checked_add(&margin).ok_or(ArithmeticError::Overflow)?;
+			Self::settle_funding(&details, &mut position)?;
+
+			let quote = notional.saturated_into::<u128>();
+			let fee = T::TradeFee::get().mul_ceil(quote).saturated_into();
+			position.margin =
+				position.margin.checked_sub(&fee).ok_or(Error::<T>::InsufficientMargin)?;
+			let size = details.open(position.side, quote)?;
+			position.size = position.size.checked_add(size).ok_or(ArithmeticError::Overflow)?;
+			position.open_notional =
+				position.open_notional.checked_add(&notional).ok_or(ArithmeticError::Overflow)?;
+			let total = details.size_mut(position.side);
+			*total = total.saturating_add(size);
+			ensure!(Self::within_leverage(&details, &position), Error::<T>::TooMuchLeverage);
+
+			Self::add_to_fund(fee);
+			Markets::<T>::insert(market, details);
+			Positions::<T>::insert(market, who, &position);
+
+			Self::deposit_event(Event::PositionChanged(
+				market,
+				who.clone(),
+				position.side,
+				position.size,
+				position.margin,
+			));
+			Ok(())
+		}
+
+		/// Close `size` units of a position on the virtual AMM, realizing their profit or loss and
+		/// the trade fee into its margin.
+		fn reduce(
+			details: &mut MarketOf<T>,
+			position: &mut PositionOf<T>,
+			size: u128,
+		) -> DispatchResult {
+			Self::settle_funding(details, position)?;
+			let value = details.close(position.side, size)?;
+			// The share of the open notional of the units closed, rounded against the trader.
+			let cost = mul_div(
+				position.open_notional.saturated_into(),
+				size,
+				position.size,
+				position.side == Side::Long,
+			)?;
+			let fee = T::TradeFee::get().mul_ceil(value);
+			let margin = signed::<T>(position.margin)
+				.saturating_add(Self::pnl(position.side, cost, value))
+				.saturating_sub(fee.saturated_into());
+			ensure!(margin >= 0, Error::<T>::Underwater);
+
+			position.margin = unsigned::<T>(margin)?;
+			position.size -= size;
+			position.open_notional = position.open_notional.saturating_sub(cost.saturated_into());
+			let total = details.size_mut(position.side);
+			*total = total.saturating_sub(size);
+			Self::add_to_fund(fee.saturated_into());
+			Ok(())
+		}
+
+		/// Profit of a position opened for `cost` and closed for `value`.
+		fn pnl(side: Side, cost: u128, value: u128) -> i128 {
+			let (cost, value) = (cost.saturated_into::<i128>(), value.saturated_into::<i128>());
+			match side {
+				Side::Long => value.saturating_sub(cost),
+				Side::Short => cost.saturating_sub(value),
+			}
+		}
+
+		/// Funding owed by a position since it was last settled, negative if it is owed funding.
+		fn funding_owed(details: &MarketOf<T>, position: &PositionOf<T>) -> i128 {
+			let delta = details.cumulative_funding.saturating_sub(position.funding_index);
+			let owed = delta.saturating_mul_int(position.size.saturated_into::<i128>());
+			match position.side {
+				Side::Long => owed,
+				Side::Short => owed.saturating_neg(),
+			}
+		}
+
+		/// Settle the funding a position owes into its margin.
+		fn settle_funding(details: &MarketOf<T>, position: &mut PositionOf<T>) -> DispatchResult {
+			let margin =
+				signed::<T>(position.margin).saturating_sub(Self::funding_owed(details, position));
+			ensure!(margin >= 0, Error::<T>::Underwater);
+			position.margin = unsigned::<T>(margin)?;
+			position.funding_index = details.cumulative_funding;
+			Ok(())
+		}
+
+		/// The equity of a position if it were closed now, and the value its size trades for.
+		fn equity(
+			details: &MarketOf<T>,
+			position: &PositionOf<T>,
+		) -> Result<(i128, u128), DispatchError> {
+			let value = details.clone().close(position.side, position.size)?;
+			let cost = position.open_notional.saturated_into();
+			let equity = signed::<T>(position.margin)
+				.saturating_add(Self::pnl(position.side, cost, value))
+				.saturating_sub(Self::funding_owed(details, position));
+			Ok((equity, value))
+		}
+
+		fn below_maintenance(equity: i128, value: u128) -> bool {
+			equity < T::MaintenanceMargin::get().mul_ceil(value).saturated_into::<i128>()
+		}
+
+		fn within_leverage(details: &MarketOf<T>, position: &PositionOf<T>) -> bool {
+			let margin = position.margin.saturated_into::<u128>();
+			position.open_notional.saturated_into::<u128>() <=
+				margin.saturating_mul(details.max_leverage.into())
+		}
+
+		/// Accrue a period of funding on a market, at the difference between its mark and index
+		/// prices.
+		fn charge_funding(market: MarketId) {
+			let mut details = match Markets::<T>::get(market) {
+				Some(details) => details,
+				None => return,
+			};
+			let (mark, index) = match (details.mark_price(), T::Prices::price(details.underlying)) {
+				(Some(mark), Some(index)) => (mark, index),
+				_ => return,
+			};
+			let premium = to_signed(mark).saturating_sub(to_signed(index));
+			let payment = premium.saturating_mul(T::FundingFactor::get().into());
+			details.cumulative_funding = details.cumulative_funding.saturating_add(payment);
+
+			// Longs pay for their size and shorts are paid for theirs; the fund settles the rest.
+			let imbalance = details
+				.long_size
+				.saturated_into::<i128>()
+				.saturating_sub(details.short_size.saturated_into());
+			let net = payment.saturating_mul_int(imbalance);
+			let amount = net.saturating_abs().saturated_into::<u128>().saturated_into();
+			if net >= 0 {
+				Self::add_to_fund(amount);
+			} else {
+				Self::cover(market, amount);
+			}
+			Markets::<T>::insert(market, details);
+
+			Self::deposit_event(Event::FundingCharged(market, payment));
+		}
+
+		/// Add funds already in the pallet account to the insurance fund, after repaying bad debt.
+		fn add_to_fund(amount: BalanceOf<T>) {
+			let debt = BadDebt::<T>::get();
+			let repaid = cmp::min(debt, amount);
+			if !repaid.is_zero() {
+				BadDebt::<T>::put(debt - repaid);
+			}
+			InsuranceFund::<T>::mutate(|fund| *fund = fund.saturating_add(amount - repaid));
+		}
+
+		/// Cover a loss out of the insurance fund, adding what it can't cover to the bad debt.
+		/// Returns the amount covered.
+		fn cover(market: MarketId, loss: BalanceOf<T>) -> BalanceOf<T> {
+			let fund = InsuranceFund::<T>::get();
+			let covered = cmp::min(fund, loss);
+			InsuranceFund::<T>::put(fund - covered);
+			let uncovered = loss - covered;
+			if !uncovered.is_zero() {
+				BadDebt::<T>::mutate(|debt| *debt = debt.saturating_add(uncovered));
+				Self::deposit_event(Event::LossUncovered(market, uncovered));
+			}
+			covered
+		}
+
+		/// Transfer margin from a trader to the pallet account.
+		fn lock(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			T::Currency::transfer(who, &Self::account_id(), amount, ExistenceRequirement::KeepAlive)
+		}
+
+		/// Transfer a payout from the pallet account, failing if it doesn't hold enough.
+		fn pay(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(())
+			}
+			let account = Self::account_id();
+			ensure!(T::Currency::free_balance(&account) >= amount, Error::<T>::InsufficientFunds);
+			T::Currency::transfer(&account, who, amount, ExistenceRequirement::AllowDeath)
+		}
+	}
+}
@@ -0,0 +1,143 @@
+use crate as pallet_perpetuals;
+use frame_support::{parameter_types, traits::Hooks, weights::Weight, PalletId};
+use frame_system::{self as system, EnsureRoot};
+use pallet_price_feed::PriceProvider;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	FixedU128, Perbill,
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Perpetuals: pallet_perpetuals::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static PRICE: RefCell<Option<FixedU128>> = RefCell::new(None);
+}
+
+/// Serves the price set with `set_price` for every asset.
+pub struct MockPrices;
+
+impl PriceProvider<u32> for MockPrices {
+	fn price(_asset: u32) -> Option<FixedU128> {
+		PRICE.with(|price| *price.borrow())
+	}
+
+	fn price_weight() -> Weight {
+		0
+	}
+}
+
+pub fn set_price(price: Option<FixedU128>) {
+	PRICE.with(|current| *current.borrow_mut() = price);
+}
+
+parameter_types! {
+	pub const PerpetualsPalletId: PalletId = PalletId(*b"py/perps");
+	pub const MaxMarkets: u32 = 2;
+	pub const FundingPeriod: u64 = 10;
+	pub const FundingFactor: Perbill = Perbill::from_percent(50);
+	pub const TradeFee: Perbill = Perbill::from_percent(1);
+	pub const MaintenanceMargin: Perbill = Perbill::from_percent(5);
+	pub const LiquidationReward: Perbill = Perbill::from_percent(2);
+}
+
+impl pallet_perpetuals::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type AssetId = u32;
+	type Prices = MockPrices;
+	type MarketOrigin = EnsureRoot<u64>;
+	type PalletId = PerpetualsPalletId;
+	type MaxMarkets = MaxMarkets;
+	type FundingPeriod = FundingPeriod;
+	type FundingFactor = FundingFactor;
+	type TradeFee = TradeFee;
+	type MaintenanceMargin = MaintenanceMargin;
+	type LiquidationReward = LiquidationReward;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000), (2, 1_000), (3, 1_000), (4, 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Run blocks up to and including `n`, initializing the pallet in each.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Perpetuals::on_initialize(System::block_number());
+	}
+}
@@ -0,0 +1,322 @@
+use crate::{mock::*, Error, Position, Side};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{
+	traits::{One, Zero},
+	DispatchError, FixedI128, FixedPointNumber, FixedU128,
+};
+
+fn free(who: u64) -> u64 {
+	Balances::free_balance(who)
+}
+
+fn position(who: u64) -> Position<u64> {
+	Perpetuals::position(0, who).unwrap()
+}
+
+// Market 0 with reserves of 1_000 of the underlying and the native currency, so the mark price
+// starts at 1, and a maximum leverage of 10.
+fn setup() {
+	assert_ok!(Perpetuals::create_market(Origin::root(), 0, 1_000, 1_000, 10));
+}
+
+// Like `setup`, with 1 long for a notional value of 250 and a margin of 50, moving the reserves to
+// 800 and 1_250.
+fn long() {
+	setup();
+	assert_ok!(Perpetuals::open_position(Origin::signed(1), 0, Side::Long, 50, 250));
+}
+
+#[test]
+fn create_market_checks_reserves_and_leverage() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Perpetuals::create_market(Origin::signed(1), 0, 1_000, 1_000, 10),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Perpetuals::create_market(Origin::root(), 0, 0, 1_000, 10),
+			Error::<Test>::InvalidMarket
+		);
+		assert_noop!(
+			Perpetuals::create_market(Origin::root(), 0, 1_000, 1_000, 0),
+			Error::<Test>::InvalidMarket
+		);
+
+		setup();
+		let market = Perpetuals::market(0).unwrap();
+		assert_eq!(market.mark_price(), Some(FixedU128::one()));
+		assert_eq!(market.max_leverage, 10);
+		assert_eq!(Perpetuals::next_market_id(), 1);
+
+		assert_ok!(Perpetuals::create_market(Origin::root(), 1, 1_000, 2_000, 5));
+		assert_noop!(
+			Perpetuals::create_market(Origin::root(), 2, 1_000, 1_000, 10),
+			Error::<Test>::TooManyMarkets
+		);
+	});
+}
+
+#[test]
+fn opening_trades_on_the_virtual_amm() {
+	new_test_ext().execute_with(|| {
+		long();
+		assert_eq!(
+			position(1),
+			Position {
+				side: Side::Long,
+				size: 200,
+				open_notional: 250,
+				margin: 47,
+				funding_index: FixedI128::zero(),
+			}
+		);
+		assert_eq!(free(1), 950);
+		assert_eq!(Perpetuals::insurance_fund(), 3);
+
+		let market = Perpetuals::market(0).unwrap();
+		assert_eq!((market.base_reserve, market.quote_reserve), (800, 1_250));
+		assert_eq!(market.mark_price(), Some(FixedU128::saturating_from_rational(25, 16)));
+		assert_eq!(market.long_size, 200);
+
+		assert_noop!(
+			Perpetuals::open_position(Origin::signed(1), 0, Side::Short, 50, 100),
+			Error::<Test>::PositionExists
+		);
+		assert_noop!(
+			Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 50, 0),
+			Error::<Test>::ZeroNotional
+		);
+		assert_noop!(
+			Perpetuals::open_position(Origin::signed(2), 1, Side::Short, 50, 100),
+			Error::<Test>::UnknownMarket
+		);
+	});
+}
+
+#[test]
+fn leverage_is_limited() {
+	new_test_ext().execute_with(|| {
+		setup();
+		// The fee of 3 leaves a margin of 17, for at most 170.
+		assert_noop!(
+			Perpetuals::open_position(Origin::signed(1), 0, Side::Long, 20, 250),
+			Error::<Test>::TooMuchLeverage
+		);
+		assert_noop!(
+			Perpetuals::open_position(Origin::signed(1), 0, Side::Long, 2, 250),
+			Error::<Test>::InsufficientMargin
+		);
+		assert_ok!(Perpetuals::open_position(Origin::signed(1), 0, Side::Long, 28, 250));
+		assert_noop!(
+			Perpetuals::increase_position(Origin::signed(1), 0, 0, 10),
+			Error::<Test>::TooMuchLeverage
+		);
+	});
+}
+
+#[test]
+fn closing_a_long_pays_its_profit() {
+	new_test_ext().execute_with(|| {
+		long();
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Long, 50, 250));
+
+		// 200 units sell for 346, a profit of 96, less a fee of 4.
+		assert_ok!(Perpetuals::close_position(Origin::signed(1), 0));
+		assert_eq!(free(1), 1_089);
+		assert_eq!(Perpetuals::insurance_fund(), 10);
+		assert!(Perpetuals::position(0, 1).is_none());
+		assert_eq!(Perpetuals::market(0).unwrap().long_size, 133);
+
+		// The 133 units of 2 now sell for 153, a loss of 97 larger than its margin.
+		assert_noop!(Perpetuals::close_position(Origin::signed(2), 0), Error::<Test>::Underwater);
+	});
+}
+
+#[test]
+fn closing_a_short_pays_its_profit() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Perpetuals::open_position(Origin::signed(1), 0, Side::Short, 50, 200));
+		assert_eq!(position(1).size, 250);
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 50, 200));
+
+		// 250 units are bought back for 106, a profit of 94, less a fee of 2.
+		assert_ok!(Perpetuals::close_position(Origin::signed(1), 0));
+		assert_eq!(free(1), 1_090);
+		assert_eq!(Perpetuals::market(0).unwrap().short_size, 417);
+	});
+}
+
+#[test]
+fn positions_increase_and_decrease() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(Perpetuals::open_position(Origin::signed(1), 0, Side::Long, 50, 100));
+		assert_eq!(position(1).size, 90);
+		assert_noop!(
+			Perpetuals::increase_position(Origin::signed(2), 0, 50, 100),
+			Error::<Test>::NoPosition
+		);
+
+		assert_ok!(Perpetuals::increase_position(Origin::signed(1), 0, 0, 150));
+		let increased = position(1);
+		assert_eq!((increased.size, increased.open_notional, increased.margin), (199, 250, 47));
+
+		assert_noop!(
+			Perpetuals::decrease_position(Origin::signed(1), 0, 0),
+			Error::<Test>::InvalidSize
+		);
+		assert_noop!(
+			Perpetuals::decrease_position(Origin::signed(1), 0, 199),
+			Error::<Test>::InvalidSize
+		);
+
+		// 100 units opened for 126 sell for 138, a profit of 12, less a fee of 2.
+		assert_ok!(Perpetuals::decrease_position(Origin::signed(1), 0, 100));
+		let decreased = position(1);
+		assert_eq!((decreased.size, decreased.open_notional, decreased.margin), (99, 124, 57));
+		assert_eq!(free(1), 950);
+	});
+}
+
+#[test]
+fn margin_can_be_added_and_removed_within_leverage() {
+	new_test_ext().execute_with(|| {
+		long();
+		assert_ok!(Perpetuals::add_margin(Origin::signed(1), 0, 10));
+		assert_eq!(position(1).margin, 57);
+		assert_eq!(free(1), 940);
+
+		// A notional value of 250 needs a margin of 25.
+		assert_noop!(
+			Perpetuals::remove_margin(Origin::signed(1), 0, 33),
+			Error::<Test>::TooMuchLeverage
+		);
+		assert_noop!(
+			Perpetuals::remove_margin(Origin::signed(1), 0, 58),
+			Error::<Test>::InsufficientMargin
+		);
+		assert_ok!(Perpetuals::remove_margin(Origin::signed(1), 0, 32));
+		assert_eq!(position(1).margin, 25);
+		assert_eq!(free(1), 972);
+	});
+}
+
+#[test]
+fn funding_is_charged_every_period() {
+	new_test_ext().execute_with(|| {
+		long();
+
+		// Without an index price, no funding is charged.
+		run_to_block(10);
+		assert_eq!(Perpetuals::market(0).unwrap().cumulative_funding, FixedI128::zero());
+
+		// The mark price of 1.5625 is 0.2 above the index price: longs pay half of it per unit.
+		set_price(Some(FixedU128::saturating_from_rational(13_625, 10_000)));
+		run_to_block(19);
+		assert_eq!(Perpetuals::market(0).unwrap().cumulative_funding, FixedI128::zero());
+		run_to_block(20);
+		let paid = FixedI128::saturating_from_rational(1, 10);
+		assert_eq!(Perpetuals::market(0).unwrap().cumulative_funding, paid);
+		// With no shorts, the insurance fund is paid for all 200 units.
+		assert_eq!(Perpetuals::insurance_fund(), 23);
+
+		// Funding is settled into the margin when the position changes.
+		assert_ok!(Perpetuals::add_margin(Origin::signed(1), 0, 10));
+		assert_eq!(position(1).margin, 37);
+		assert_eq!(position(1).funding_index, paid);
+	});
+}
+
+#[test]
+fn shortfalls_are_covered_by_the_insurance_fund() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Perpetuals::fund_insurance(Origin::signed(4), 100));
+		assert_eq!(free(4), 900);
+		long();
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 50, 250));
+		assert_eq!(Perpetuals::insurance_fund(), 106);
+
+		assert_noop!(
+			Perpetuals::liquidate(Origin::signed(3), 0, 2),
+			Error::<Test>::NotLiquidatable
+		);
+		// The 200 units of 1 now sell for 166, a loss of 84 for a margin of 47.
+		assert_noop!(Perpetuals::close_position(Origin::signed(1), 0), Error::<Test>::Underwater);
+		assert_ok!(Perpetuals::liquidate(Origin::signed(3), 0, 1));
+		assert!(Perpetuals::position(0, 1).is_none());
+		assert_eq!(free(3), 1_000);
+		assert_eq!(Perpetuals::insurance_fund(), 69);
+		assert_eq!(Perpetuals::market(0).unwrap().long_size, 0);
+	});
+}
+
+#[test]
+fn liquidators_are_rewarded_from_the_equity_left() {
+	new_test_ext().execute_with(|| {
+		long();
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 20, 110));
+
+		// The 200 units of 1 now sell for 211: an equity of 8, under the maintenance margin of
+		// 11. The liquidator gets 2% of 211 and the insurance fund the rest.
+		assert_ok!(Perpetuals::liquidate(Origin::signed(3), 0, 1));
+		assert_eq!(free(3), 1_004);
+		assert_eq!(Perpetuals::insurance_fund(), 3 + 2 + 4);
+	});
+}
+
+#[test]
+fn traders_are_paid_no_more_than_they_deposited() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		long();
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 30, 100));
+		assert_ok!(Perpetuals::open_position(Origin::signed(3), 0, Side::Long, 30, 100));
+		assert_ok!(Perpetuals::open_position(Origin::signed(4), 0, Side::Short, 30, 50));
+
+		assert_ok!(Perpetuals::close_position(Origin::signed(3), 0));
+		assert_ok!(Perpetuals::liquidate(Origin::signed(3), 0, 1));
+		assert_ok!(Perpetuals::close_position(Origin::signed(4), 0));
+		assert_ok!(Perpetuals::close_position(Origin::signed(2), 0));
+
+		// Payouts, the insurance fund and rounding dust add up to the margin deposited.
+		let market = Perpetuals::market(0).unwrap();
+		assert_eq!((market.long_size, market.short_size), (0, 0));
+		assert_eq!(Perpetuals::insurance_fund(), 6);
+		assert_eq!(free(Perpetuals::account_id()), Perpetuals::insurance_fund() + 4);
+		assert_eq!(Balances::total_issuance(), issuance);
+	});
+}
+
+#[test]
+fn losses_beyond_the_insurance_fund_become_bad_debt() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		long();
+		assert_ok!(Perpetuals::open_position(Origin::signed(2), 0, Side::Short, 50, 250));
+		assert_eq!(Perpetuals::insurance_fund(), 6);
+		assert_eq!(free(Perpetuals::account_id()), 100);
+
+		// The shortfall of 37 takes the 6 of the insurance fund and leaves 31 uncovered.
+		assert_ok!(Perpetuals::liquidate(Origin::signed(3), 0, 1));
+		assert_eq!(Perpetuals::insurance_fund(), 0);
+		assert_eq!(Perpetuals::bad_debt(), 31);
+		System::assert_has_event(Event::Perpetuals(crate::Event::LossUncovered(0, 31)));
+
+		// The 200 units of 2 are bought back for 167: a margin of 128, more than the 100 held.
+		assert_noop!(
+			Perpetuals::close_position(Origin::signed(2), 0),
+			Error::<Test>::InsufficientFunds
+		);
+		assert_eq!(Balances::total_issuance(), issuance);
+
+		// Topping up the fund repays the bad debt first.
+		assert_ok!(Perpetuals::fund_insurance(Origin::signed(4), 40));
+		assert_eq!((Perpetuals::bad_debt(), Perpetuals::insurance_fund()), (0, 9));
+		assert_ok!(Perpetuals::close_position(Origin::signed(2), 0));
+		assert_eq!(free(2), 1_078);
+		assert_eq!(Perpetuals::insurance_fund(), 11);
+		assert_eq!(free(Perpetuals::account_id()), 12);
+		assert_eq!(Balances::total_issuance(), issuance);
+	});
+}
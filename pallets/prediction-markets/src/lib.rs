@@ -0,0 +1,786 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Binary and categorical prediction markets, collateralized by the native currency.
+//!
+//! A market has two outcomes or more, a close block and an oracle. Until the close block, anyone
+//! can buy complete sets of shares, one share of every outcome for one unit of collateral. A
+//! complete set can be sold back for its collateral at any time before resolution. Shares can be
+//! transferred, or offered for sale at a fixed price.
+//!
+//! Markets move through their states in `on_initialize`:
+//! - at the close block, the market closes and its oracle has `ReportPeriod` blocks to report the
+//!   winning outcome. If it does not, the market escalates to governance and the deposit of its
+//!   creator is slashed. When no block near the report deadline has room for the escalation, it
+//!   is left to `escalate`, which anyone can call once the deadline passed;
+//! - a report opens a dispute window of `DisputePeriod` blocks, at the end of which an undisputed
+//!   report resolves the market;
+//! - anyone can dispute the report during the window by reserving `DisputeBond`, which escalates
+//!   the market to governance. The bond is returned if governance overrules the report, and
+//!   slashed otherwise.
+//!
+//! Each winning share of a resolved market redeems for one unit of collateral. Collateral is
+//! transferred from the buyers of complete sets to the account of `PalletId`, and paid out of it
+//! when sets are sold or shares redeemed.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{Currency, ExistenceRequirement, OnUnbalanced, ReservableCurrency},
+		transactional, BoundedVec, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::{
+		traits::{
+			AccountIdConversion, CheckedAdd, CheckedMul, CheckedSub, One, Saturating, StaticLookup,
+			Zero,
+		},
+		ArithmeticError,
+	};
+
+	/// Identifier of a market.
+	pub type MarketId = u32;
+	/// Index of an outcome within its market.
+	pub type Outcome = u16;
+	/// Identifier of an offer.
+	pub type OfferId = u64;
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+	pub type MarketOf<T> = Market<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	>;
+
+	/// State of a market.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub enum MarketStatus<AccountId, Balance, BlockNumber> {
+		/// Complete sets can be bought until the close block.
+		Active,
+		/// Waiting for the oracle to report before `deadline`.
+		Closed { deadline: BlockNumber },
+		/// The oracle reported `outcome`, which can be disputed before `deadline`.
+		Reported { outcome: Outcome, deadline: BlockNumber },
+		/// `disputer` reserved `bond` to dispute the `reported` outcome in favour of `proposed`.
+		/// Governance resolves the market.
+		Disputed { reported: Outcome, proposed: Outcome, disputer: AccountId, bond: Balance },
+		/// The oracle did not report in time. Governance resolves the market.
+		Escalated,
+		/// The market resolved to the outcome, whose shares can be redeemed.
+		Resolved(Outcome),
+	}
+
+	/// A prediction market.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Market<AccountId, Balance, BlockNumber> {
+		/// The account that created the market and paid its deposit.
+		pub creator: AccountId,
+		/// The account reporting the winning outcome.
+		pub oracle: AccountId,
+		/// Number of outcomes, two for a binary market.
+		pub outcomes: Outcome,
+		/// Block at which the market closes.
+		pub close: BlockNumber,
+		/// Deposit reserved from `creator`, released once the market is resolved.
+		pub deposit: Balance,
+		/// Collateral backing the market, equal to the number of complete sets outstanding.
+		pub collateral: Balance,
+		/// State of the market.
+		pub status: MarketStatus<AccountId, Balance, BlockNumber>,
+	}
+
+	/// Shares offered for sale at a fixed price.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct Offer<AccountId, Balance> {
+		/// The account selling the shares, which are held by the offer until sold or cancelled.
+		pub seller: AccountId,
+		/// The market of the shares.
+		pub market: MarketId,
+		/// The outcome of the shares.
+		pub outcome: Outcome,
+		/// Number of shares left for sale.
+		pub amount: Balance,
+		/// Price of a share.
+		pub price: Balance,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency of collateral, deposits and bonds.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Origin resolving disputed and escalated markets.
+		type ResolveOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Identifier of the account holding collateral.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Handler for slashed deposits and dispute bonds.
+		type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// Deposit reserved for a market.
+		#[pallet::constant]
+		type MarketDeposit: Get<BalanceOf<Self>>;
+
+		/// Bond reserved to dispute a report.
+		#[pallet::constant]
+		type DisputeBond: Get<BalanceOf<Self>>;
+
+		/// Maximum number of outcomes of a market.
+		#[pallet::constant]
+		type MaxOutcomes: Get<Outcome>;
+
+		/// Number of blocks the oracle has to report after a market closes.
+		#[pallet::constant]
+		type ReportPeriod: Get<Self::BlockNumber>;
+
+		/// Number of blocks a report can be disputed for.
+		#[pallet::constant]
+		type DisputePeriod: Get<Self::BlockNumber>;
+
+		/// Maximum number of market transitions due in a block.
+		#[pallet::constant]
+		type MaxScheduledPerBlock: Get<u32>;
+
+		/// Maximum number of blocks a report deadline or the end of a dispute period is pushed
+		/// back by when the blocks it would fall in are full.
+		#[pallet::constant]
+		type MaxScheduleDelay: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The identifier of the next market to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_market_id)]
+	pub type NextMarketId<T> = StorageValue<_, MarketId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn market)]
+	pub type Markets<T: Config> = StorageMap<_, Blake2_128Concat, MarketId, MarketOf<T>>;
+
+	/// Shares held by an account, by market and outcome.
+	#[pallet::storage]
+	#[pallet::getter(fn shares)]
+	pub type Shares<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(MarketId, Outcome),
+		Blake2_128Concat,
+		T::AccountId,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// Markets with a transition due at a block, processed in `on_initialize`.
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled)]
+	pub type Schedule<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<MarketId, T::MaxScheduledPerBlock>,
+		ValueQuery,
+	>;
+
+	/// The identifier of the next offer to be created.
+	#[pallet::storage]
+	#[pallet::getter(fn next_offer_id)]
+	pub type NextOfferId<T> = StorageValue<_, OfferId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn offer)]
+	pub type Offers<T: Config> =
+		StorageMap<_, Blake2_128Concat, OfferId, Offer<T::AccountId, BalanceOf<T>>>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		BalanceOf<T> = "Balance",
+		T::BlockNumber = "BlockNumber"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A market was created. [market, creator, oracle, outcomes, close]
+		MarketCreated(MarketId, T::AccountId, T::AccountId, Outcome, T::BlockNumber),
+		/// A market closed and waits for its oracle to report. [market]
+		MarketClosed(MarketId),
+		/// The oracle reported the winning outcome. [market, outcome]
+		Reported(MarketId, Outcome),
+		/// A report was disputed, escalating the market to governance. [market, disputer, outcome]
+		Disputed(MarketId, T::AccountId, Outcome),
+		/// The oracle did not report in time, escalating the market to governance. [market]
+		Escalated(MarketId),
+		/// A market was resolved. [market, outcome]
+		Resolved(MarketId, Outcome),
+		/// Complete sets were bought. [market, who, amount]
+		CompleteSetsBought(MarketId, T::AccountId, BalanceOf<T>),
+		/// Complete sets were sold back for their collateral. [market, who, amount]
+		CompleteSetsSold(MarketId, T::AccountId, BalanceOf<T>),
+		/// Shares were transferred. [market, outcome, from, to, amount]
+		SharesTransferred(MarketId, Outcome, T::AccountId, T::AccountId, BalanceOf<T>),
+		/// Shares were offered for sale. [offer, seller, market, outcome, amount, price]
+		OfferCreated(OfferId, T::AccountId, MarketId, Outcome, BalanceOf<T>, BalanceOf<T>),
+		/// Shares of an offer were bought. [offer, buyer, amount]
+		OfferFilled(OfferId, T::AccountId, BalanceOf<T>),
+		/// An offer was cancelled, returning its shares to the seller. [offer]
+		OfferCancelled(OfferId),
+		/// Winning shares were redeemed for collateral. [market, who, payout]
+		Redeemed(MarketId, T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The market does not exist.
+		UnknownMarket,
+		/// A market needs two outcomes or more, and at most `MaxOutcomes`.
+		InvalidOutcomeCount,
+		/// The outcome does not exist in the market.
+		UnknownOutcome,
+		/// The close block has passed.
+		CloseInPast,
+		/// Too many markets have a transition due at the close block, or at the end of the
+		/// dispute period and the `MaxScheduleDelay` blocks after it.
+		TooManyMarketsClosing,
+		/// All market or offer identifiers are in use.
+		NoAvailableId,
+		/// The market is not active.
+		MarketNotActive,
+		/// The market is not waiting for a report.
+		MarketNotClosed,
+		/// The oracle of the market can still report.
+		ReportDeadlineNotPassed,
+		/// The market has no report open to disputes.
+		MarketNotReported,
+		/// The market is neither disputed nor escalated.
+		MarketNotEscalated,
+		/// The market is not resolved.
+		MarketNotResolved,
+		/// The market is already resolved.
+		MarketResolved,
+		/// The sender is not the oracle of the market.
+		NotOracle,
+		/// A dispute must propose an outcome other than the reported one.
+		SameOutcome,
+		/// The account does not hold enough shares.
+		InsufficientShares,
+		/// The account holds no shares of the market.
+		NoShares,
+		/// The offer does not exist.
+		UnknownOffer,
+		/// The offer has fewer shares left.
+		OfferTooSmall,
+		/// The sender is not the seller of the offer.
+		NotSeller,
+		/// The buyer is the seller of the offer.
+		BuyerIsSeller,
+		/// The payout is below the existential deposit of an account that does not exist.
+		PayoutBelowMinimum,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Close markets, escalate markets their oracle did not report on, and resolve undisputed
+		/// reports, as scheduled for this block.
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let due = Schedule::<T>::take(now);
+			// Closing a market schedules its report deadline at the first of `MaxScheduleDelay`
+			// blocks with room for it, so the blocks tried are charged too.
+			let mut tried: Weight = 0;
+			for market in due.iter() {
+				tried = tried.saturating_add(Self::transition(*market, now).into());
+			}
+			let transitions = due.len() as Weight;
+			T::DbWeight::get().reads_writes(1 + 2 * transitions + tried, 1 + 3 * transitions)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a market with `outcomes` outcomes, closing at block `close` and reported on by
+		/// `oracle`. `MarketDeposit` is reserved from the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 4))]
+		#[transactional]
+		pub fn create_market(
+			origin: OriginFor<T>,
+			outcomes: Outcome,
+			close: T::BlockNumber,
+			oracle: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let creator = ensure_signed(origin)?;
+			let oracle = T::Lookup::lookup(oracle)?;
+			ensure!(
+				outcomes >= 2 && outcomes <= T::MaxOutcomes::get(),
+				Error::<T>::InvalidOutcomeCount
+			);
+			ensure!(close > frame_system::Pallet::<T>::block_number(), Error::<T>::CloseInPast);
+
+			let market = NextMarketId::<T>::get();
+			let next = market.checked_add(1).ok_or(Error::<T>::NoAvailableId)?;
+			let deposit = T::MarketDeposit::get();
+			T::Currency::reserve(&creator, deposit)?;
+			Schedule::<T>::try_mutate(close, |due| due.try_push(market))
+				.map_err(|_| Error::<T>::TooManyMarketsClosing)?;
+
+			Markets::<T>::insert(
+				market,
+				Market {
+					creator: creator.clone(),
+					oracle: oracle.clone(),
+					outcomes,
+					close,
+					deposit,
+					collateral: Zero::zero(),
+					status: MarketStatus::Active,
+				},
+			);
+			NextMarketId::<T>::put(next);
+
+			Self::deposit_event(Event::MarketCreated(market, creator, oracle, outcomes, close));
+			Ok(())
+		}
+
+		/// Buy `amount` complete sets of an active market, paying `amount` of collateral for
+		/// `amount` shares of every outcome.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(
+				2 + T::MaxOutcomes::get() as Weight,
+				2 + T::MaxOutcomes::get() as Weight,
+			)
+		)]
+		#[transactional]
+		pub fn buy_complete_set(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let outcomes = Markets::<T>::try_mutate(market, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownMarket)?;
+				ensure!(details.status == MarketStatus::Active, Error::<T>::MarketNotActive);
+				details.collateral =
+					details.collateral.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+				Ok::<_, DispatchError>(details.outcomes)
+			})?;
+			T::Currency::transfer(
+				&who,
+				&Self::account_id(),
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			for outcome in 0..outcomes {
+				Self::give_shares(market, outcome, &who, amount);
+			}
+
+			Self::deposit_event(Event::CompleteSetsBought(market, who, amount));
+			Ok(())
+		}
+
+		/// Sell `amount` complete sets of a market that is not resolved yet, burning `amount`
+		/// shares of every outcome for `amount` of collateral.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(
+				2 + T::MaxOutcomes::get() as Weight,
+				2 + T::MaxOutcomes::get() as Weight,
+			)
+		)]
+		#[transactional]
+		pub fn sell_complete_set(
+			origin: OriginFor<T>,
+			market: MarketId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let outcomes = Markets::<T>::try_mutate(market, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownMarket)?;
+				ensure!(
+					!matches!(details.status, MarketStatus::Resolved(_)),
+					Error::<T>::MarketResolved
+				);
+				details.collateral = details.collateral.saturating_sub(amount);
+				Ok::<_, DispatchError>(details.outcomes)
+			})?;
+			for outcome in 0..outcomes {
+				Self::take_shares(market, outcome, &who, amount)?;
+			}
+			Self::pay(&who, amount)?;
+
+			Self::deposit_event(Event::CompleteSetsSold(market, who, amount));
+			Ok(())
+		}
+
+		/// Transfer `amount` shares of an outcome to `dest`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn transfer_shares(
+			origin: OriginFor<T>,
+			market: MarketId,
+			outcome: Outcome,
+			dest: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			Self::ensure_outcome(market, outcome)?;
+			Self::take_shares(market, outcome, &who, amount)?;
+			Self::give_shares(market, outcome, &dest, amount);
+
+			Self::deposit_event(Event::SharesTransferred(market, outcome, who, dest, amount));
+			Ok(())
+		}
+
+		/// Offer `amount` shares of an outcome for sale at `price` per share. The offer holds the
+		/// shares until they are bought or the offer is cancelled.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn create_offer(
+			origin: OriginFor<T>,
+			market: MarketId,
+			outcome: Outcome,
+			#[pallet::compact] amount: BalanceOf<T>,
+			#[pallet::compact] price: BalanceOf<T>,
+		) -> DispatchResult {
+			let seller = ensure_signed(origin)?;
+
+			Self::ensure_outcome(market, outcome)?;
+			let offer = NextOfferId::<T>::get();
+			let next = offer.checked_add(1).ok_or(Error::<T>::NoAvailableId)?;
+			Self::take_shares(market, outcome, &seller, amount)?;
+
+			Offers::<T>::insert(
+				offer,
+				Offer { seller: seller.clone(), market, outcome, amount, price },
+			);
+			NextOfferId::<T>::put(next);
+
+			Self::deposit_event(Event::OfferCreated(offer, seller, market, outcome, amount, price));
+			Ok(())
+		}
+
+		/// Buy `amount` shares of an offer at its price.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		#[transactional]
+		pub fn fill_offer(
+			origin: OriginFor<T>,
+			offer: OfferId,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+
+			let mut details = Offers::<T>::get(offer).ok_or(Error::<T>::UnknownOffer)?;
+			ensure!(details.seller != buyer, Error::<T>::BuyerIsSeller);
+			details.amount =
+				details.amount.checked_sub(&amount).ok_or(Error::<T>::OfferTooSmall)?;
+
+			let cost = details.price.checked_mul(&amount).ok_or(ArithmeticError::Overflow)?;
+			T::Currency::transfer(&buyer, &details.seller, cost, ExistenceRequirement::KeepAlive)?;
+			Self::give_shares(details.market, details.outcome, &buyer, amount);
+			if details.amount.is_zero() {
+				Offers::<T>::remove(offer);
+			} else {
+				Offers::<T>::insert(offer, details);
+			}
+
+			Self::deposit_event(Event::OfferFilled(offer, buyer, amount));
+			Ok(())
+		}
+
+		/// Cancel an offer, returning its shares. Only the seller can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn cancel_offer(origin: OriginFor<T>, offer: OfferId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let details = Offers::<T>::get(offer).ok_or(Error::<T>::UnknownOffer)?;
+			ensure!(details.seller == who, Error::<T>::NotSeller);
+			Self::give_shares(details.market, details.outcome, &who, details.amount);
+			Offers::<T>::remove(offer);
+
+			Self::deposit_event(Event::OfferCancelled(offer));
+			Ok(())
+		}
+
+		/// Report the winning outcome of a closed market, opening it to disputes for
+		/// `DisputePeriod` blocks. Only the oracle of the market can do this.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(2 + T::MaxScheduleDelay::get() as Weight, 2)
+		)]
+		pub fn report(origin: OriginFor<T>, market: MarketId, outcome: Outcome) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Markets::<T>::try_mutate(market, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownMarket)?;
+				ensure!(details.oracle == who, Error::<T>::NotOracle);
+				ensure!(
+					matches!(details.status, MarketStatus::Closed { .. }),
+					Error::<T>::MarketNotClosed
+				);
+				ensure!(outcome < details.outcomes, Error::<T>::UnknownOutcome);
+
+				let deadline = frame_system::Pallet::<T>::block_number()
+					.saturating_add(T::DisputePeriod::get());
+				details.status = MarketStatus::Reported { outcome, deadline };
+				Self::schedule(deadline, market, T::MaxScheduleDelay::get())
+					.map_err(|_| Error::<T>::TooManyMarketsClosing)?;
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::Reported(market, outcome));
+			Ok(())
+		}
+
+		/// Dispute the report of a market in favour of another outcome, reserving `DisputeBond`
+		/// from the sender. The market escalates to governance.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn dispute(origin: OriginFor<T>, market: MarketId, outcome: Outcome) -> DispatchResult {
+			let disputer = ensure_signed(origin)?;
+
+			Markets::<T>::try_mutate(market, |details| {
+				let details = details.as_mut().ok_or(Error::<T>::UnknownMarket)?;
+				let reported = match details.status {
+					MarketStatus::Reported { outcome: reported, .. } => reported,
+					_ => return Err(Error::<T>::MarketNotReported.into()),
+				};
+				ensure!(outcome < details.outcomes, Error::<T>::UnknownOutcome);
+				ensure!(outcome != reported, Error::<T>::SameOutcome);
+
+				let bond = T::DisputeBond::get();
+				T::Currency::reserve(&disputer, bond)?;
+				details.status = MarketStatus::Disputed {
+					reported,
+					proposed: outcome,
+					disputer: disputer.clone(),
+					bond,
+				};
+				Ok::<_, DispatchError>(())
+			})?;
+
+			Self::deposit_event(Event::Disputed(market, disputer, outcome));
+			Ok(())
+		}
+
+		/// Resolve a disputed or escalated market to `outcome`. The bond of a dispute is returned
+		/// if `outcome` overrules the report, and slashed otherwise.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn resolve(origin: OriginFor<T>, market: MarketId, outcome: Outcome) -> DispatchResult {
+			T::ResolveOrigin::ensure_origin(origin)?;
+
+			let mut details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			ensure!(outcome < details.outcomes, Error::<T>::UnknownOutcome);
+			match details.status {
+				MarketStatus::Disputed { reported, ref disputer, bond, .. } =>
+					if outcome == reported {
+						let (imbalance, _) = T::Currency::slash_reserved(disputer, bond);
+						T::Slash::on_unbalanced(imbalance);
+					} else {
+						T::Currency::unreserve(disputer, bond);
+					},
+				MarketStatus::Escalated => {},
+				_ => return Err(Error::<T>::MarketNotEscalated.into()),
+			}
+			Self::do_resolve(market, &mut details, outcome);
+			Markets::<T>::insert(market, details);
+
+			Ok(())
+		}
+
+		/// Escalate a closed market whose oracle did not report before the deadline, slashing the
+		/// deposit of its creator. `on_initialize` does this at the deadline unless no block had
+		/// room for it. Anyone can do this.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn escalate(origin: OriginFor<T>, market: MarketId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			match details.status {
+				MarketStatus::Closed { deadline } =>
+					ensure!(deadline <= now, Error::<T>::ReportDeadlineNotPassed),
+				_ => return Err(Error::<T>::MarketNotClosed.into()),
+			}
+			Self::transition(market, now);
+
+			Ok(())
+		}
+
+		/// Redeem all shares the sender holds in a resolved market, each winning share for one
+		/// unit of collateral. Losing shares are burned.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(
+				2 + T::MaxOutcomes::get() as Weight,
+				2 + T::MaxOutcomes::get() as Weight,
+			)
+		)]
+		#[transactional]
+		pub fn redeem(origin: OriginFor<T>, market: MarketId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			let winner = match details.status {
+				MarketStatus::Resolved(outcome) => outcome,
+				_ => return Err(Error::<T>::MarketNotResolved.into()),
+			};
+			let mut held = false;
+			for outcome in 0..details.outcomes {
+				held |= Shares::<T>::contains_key((market, outcome), &who);
+			}
+			ensure!(held, Error::<T>::NoShares);
+
+			let mut payout = Zero::zero();
+			for outcome in 0..details.outcomes {
+				let shares = Shares::<T>::take((market, outcome), &who);
+				if outcome == winner {
+					payout = shares;
+				}
+			}
+			details.collateral = details.collateral.saturating_sub(payout);
+			Self::pay(&who, payout)?;
+			Markets::<T>::insert(market, details);
+
+			Self::deposit_event(Event::Redeemed(market, who, payout));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding collateral.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// Pay collateral out of the pallet account.
+		fn pay(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			if amount.is_zero() {
+				return Ok(())
+			}
+			// A transfer below the existential deposit to an account that does not exist fails;
+			// report it as such rather than as an error of the currency.
+			ensure!(
+				amount >= T::Currency::minimum_balance() ||
+					!T::Currency::total_balance(who).is_zero(),
+				Error::<T>::PayoutBelowMinimum
+			);
+			T::Currency::transfer(
+				&Self::account_id(),
+				who,
+				amount,
+				ExistenceRequirement::AllowDeath,
+			)
+		}
+
+		/// Apply the transition of a market due at `now`, if any. Markets that left the state
+		/// the transition was scheduled for are left untouched. Returns the number of blocks tried
+		/// to schedule the next transition.
+		fn transition(market: MarketId, now: T::BlockNumber) -> u32 {
+			let mut details = match Markets::<T>::get(market) {
+				Some(details) => details,
+				None => return 0,
+			};
+			let mut tried = 0;
+			match details.status {
+				MarketStatus::Active if details.close <= now => {
+					let deadline = now.saturating_add(T::ReportPeriod::get());
+					details.status = MarketStatus::Closed { deadline };
+					// Without a block with room for the report deadline, the market stays closed
+					// until its oracle reports or anyone escalates it.
+					tried = Self::schedule(deadline, market, T::MaxScheduleDelay::get())
+						.unwrap_or_else(|tried| tried);
+					Self::deposit_event(Event::MarketClosed(market));
+				},
+				MarketStatus::Closed { deadline } if deadline <= now => {
+					let (imbalance, _) =
+						T::Currency::slash_reserved(&details.creator, details.deposit);
+					T::Slash::on_unbalanced(imbalance);
+					details.deposit = Zero::zero();
+					details.status = MarketStatus::Escalated;
+					Self::deposit_event(Event::Escalated(market));
+				},
+				MarketStatus::Reported { outcome, deadline } if deadline <= now =>
+					Self::do_resolve(market, &mut details, outcome),
+				_ => return 0,
+			}
+			Markets::<T>::insert(market, details);
+			tried
+		}
+
+		/// Resolve a market to `outcome`, releasing the deposit of its creator.
+		fn do_resolve(market: MarketId, details: &mut MarketOf<T>, outcome: Outcome) {
+			T::Currency::unreserve(&details.creator, details.deposit);
+			details.deposit = Zero::zero();
+			details.status = MarketStatus::Resolved(outcome);
+			Self::deposit_event(Event::Resolved(market, outcome));
+		}
+
+		/// Schedule a transition of `market` at block `at`, or at the first of the next
+		/// `max_delay` blocks with room for it. Returns the number of blocks tried, as an error if
+		/// none had room.
+		fn schedule(mut at: T::BlockNumber, market: MarketId, max_delay: u32) -> Result<u32, u32> {
+			let mut tried = 0u32;
+			loop {
+				tried = tried.saturating_add(1);
+				if Schedule::<T>::try_mutate(at, |due| due.try_push(market)).is_ok() {
+					return Ok(tried)
+				}
+				if tried > max_delay {
+					return Err(tried)
+				}
+				at = at.saturating_add(One::one());
+			}
+		}
+
+		fn ensure_outcome(market: MarketId, outcome: Outcome) -> DispatchResult {
+			let details = Markets::<T>::get(market).ok_or(Error::<T>::UnknownMarket)?;
+			ensure!(outcome < details.outcomes, Error::<T>::UnknownOutcome);
+			Ok(())
+		}
+
+		fn take_shares(
+			market: MarketId,
+			outcome: Outcome,
+			who: &T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			Shares::<T>::try_mutate_exists((market, outcome), who, |shares| {
+				let left = shares
+					.unwrap_or_default()
+					.checked_sub(&amount)
+					.ok_or(Error::<T>::InsufficientShares)?;
+				*shares = Some(left).filter(|left| !left.is_zero());
+				Ok(())
+			})
+		}
+
+		fn give_shares(
+			market: MarketId,
+			outcome: Outcome,
+			who: &T::AccountId,
+			amount: BalanceOf<T>,
+		) {
+			if !amount.is_zero() {
+				Shares::<T>::mutate((market, outcome), who, |shares| {
+					*shares = shares.saturating_add(amount)
+				});
+			}
+		}
+	}
+}
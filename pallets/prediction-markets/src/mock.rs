@@ -0,0 +1,125 @@
+use crate as pallet_prediction_markets;
+use frame_support::{parameter_types, traits::Hooks, PalletId};
+use frame_system::{self as system, EnsureRoot};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		PredictionMarkets: pallet_prediction_markets::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 5;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const PredictionMarketsPalletId: PalletId = PalletId(*b"py/prdmk");
+	pub const MarketDeposit: u64 = 10;
+	pub const DisputeBond: u64 = 50;
+	pub const MaxOutcomes: u16 = 4;
+	pub const ReportPeriod: u64 = 5;
+	pub const DisputePeriod: u64 = 5;
+	pub const MaxScheduledPerBlock: u32 = 2;
+	pub const MaxScheduleDelay: u32 = 1;
+}
+
+impl pallet_prediction_markets::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type ResolveOrigin = EnsureRoot<u64>;
+	type PalletId = PredictionMarketsPalletId;
+	type Slash = ();
+	type MarketDeposit = MarketDeposit;
+	type DisputeBond = DisputeBond;
+	type MaxOutcomes = MaxOutcomes;
+	type ReportPeriod = ReportPeriod;
+	type DisputePeriod = DisputePeriod;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type MaxScheduleDelay = MaxScheduleDelay;
+}
+
+/// Creates markets.
+pub const CREATOR: u64 = 1;
+/// Reports on the markets of `CREATOR`.
+pub const ORACLE: u64 = 2;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(CREATOR, 1_000), (ORACLE, 1_000), (3, 1_000), (4, 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Run blocks up to and including `n`, initializing the pallet in each.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		PredictionMarkets::on_initialize(System::block_number());
+	}
+}
@@ -0,0 +1,477 @@
+use crate::{mock::*, Error, MarketStatus};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+fn reserved(who: u64) -> u64 {
+	Balances::reserved_balance(who)
+}
+
+fn free(who: u64) -> u64 {
+	Balances::free_balance(who)
+}
+
+fn status(market: u32) -> MarketStatus<u64, u64, u64> {
+	PredictionMarkets::market(market).unwrap().status
+}
+
+// Binary market 0 closing at block 10, with 100 complete sets bought by 3.
+fn setup() {
+	assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE));
+	assert_ok!(PredictionMarkets::buy_complete_set(Origin::signed(3), 0, 100));
+}
+
+// Market 0 reported at block 11 to resolve to outcome 0, disputable until block 16.
+fn reported() {
+	setup();
+	run_to_block(11);
+	assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 0));
+}
+
+#[test]
+fn create_market_checks_outcomes_and_close() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			PredictionMarkets::create_market(Origin::signed(CREATOR), 1, 10, ORACLE),
+			Error::<Test>::InvalidOutcomeCount
+		);
+		assert_noop!(
+			PredictionMarkets::create_market(Origin::signed(CREATOR), 5, 10, ORACLE),
+			Error::<Test>::InvalidOutcomeCount
+		);
+		assert_noop!(
+			PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 1, ORACLE),
+			Error::<Test>::CloseInPast
+		);
+
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 4, 10, ORACLE));
+		let market = PredictionMarkets::market(0).unwrap();
+		assert_eq!(market.oracle, ORACLE);
+		assert_eq!(market.outcomes, 4);
+		assert_eq!(market.status, MarketStatus::Active);
+		assert_eq!(PredictionMarkets::next_market_id(), 1);
+		assert_eq!(PredictionMarkets::scheduled(10).into_inner(), vec![0]);
+		assert_eq!(reserved(CREATOR), 10);
+	});
+}
+
+#[test]
+fn markets_closing_in_a_block_are_limited() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE));
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE));
+		assert_noop!(
+			PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE),
+			Error::<Test>::TooManyMarketsClosing
+		);
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 11, ORACLE));
+	});
+}
+
+#[test]
+fn complete_sets_are_backed_by_collateral() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		setup();
+		assert_eq!(free(3), 900);
+		assert_eq!(PredictionMarkets::shares((0, 0), 3), 100);
+		assert_eq!(PredictionMarkets::shares((0, 1), 3), 100);
+		assert_eq!(PredictionMarkets::market(0).unwrap().collateral, 100);
+		assert_eq!(free(PredictionMarkets::account_id()), 100);
+		assert_eq!(Balances::total_issuance(), issuance);
+
+		assert_ok!(PredictionMarkets::sell_complete_set(Origin::signed(3), 0, 40));
+		assert_eq!(free(3), 940);
+		assert_eq!(PredictionMarkets::shares((0, 1), 3), 60);
+		assert_eq!(PredictionMarkets::market(0).unwrap().collateral, 60);
+
+		// A set needs a share of every outcome.
+		assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, 1, 4, 10));
+		assert_noop!(
+			PredictionMarkets::sell_complete_set(Origin::signed(3), 0, 60),
+			Error::<Test>::InsufficientShares
+		);
+		assert_noop!(
+			PredictionMarkets::buy_complete_set(Origin::signed(3), 1, 10),
+			Error::<Test>::UnknownMarket
+		);
+	});
+}
+
+#[test]
+fn market_closes_at_close_block() {
+	new_test_ext().execute_with(|| {
+		setup();
+		run_to_block(9);
+		assert_eq!(status(0), MarketStatus::Active);
+
+		run_to_block(10);
+		assert_eq!(status(0), MarketStatus::Closed { deadline: 15 });
+		assert!(PredictionMarkets::scheduled(10).is_empty());
+		assert_eq!(PredictionMarkets::scheduled(15).into_inner(), vec![0]);
+		assert_noop!(
+			PredictionMarkets::buy_complete_set(Origin::signed(3), 0, 10),
+			Error::<Test>::MarketNotActive
+		);
+		// Sets can still be sold back until resolution.
+		assert_ok!(PredictionMarkets::sell_complete_set(Origin::signed(3), 0, 10));
+	});
+}
+
+#[test]
+fn shares_are_transferred() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, 0, 4, 30));
+		assert_eq!(PredictionMarkets::shares((0, 0), 3), 70);
+		assert_eq!(PredictionMarkets::shares((0, 0), 4), 30);
+		assert_eq!(PredictionMarkets::shares((0, 1), 4), 0);
+
+		assert_noop!(
+			PredictionMarkets::transfer_shares(Origin::signed(4), 0, 0, 3, 31),
+			Error::<Test>::InsufficientShares
+		);
+		assert_noop!(
+			PredictionMarkets::transfer_shares(Origin::signed(3), 0, 2, 4, 1),
+			Error::<Test>::UnknownOutcome
+		);
+	});
+}
+
+#[test]
+fn offers_hold_shares_until_filled_or_cancelled() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(PredictionMarkets::create_offer(Origin::signed(3), 0, 0, 50, 3));
+		assert_eq!(PredictionMarkets::shares((0, 0), 3), 50);
+		assert_noop!(
+			PredictionMarkets::create_offer(Origin::signed(3), 0, 0, 51, 3),
+			Error::<Test>::InsufficientShares
+		);
+
+		assert_noop!(
+			PredictionMarkets::fill_offer(Origin::signed(3), 0, 10),
+			Error::<Test>::BuyerIsSeller
+		);
+		assert_noop!(
+			PredictionMarkets::fill_offer(Origin::signed(4), 0, 51),
+			Error::<Test>::OfferTooSmall
+		);
+		assert_ok!(PredictionMarkets::fill_offer(Origin::signed(4), 0, 20));
+		assert_eq!(free(4), 940);
+		assert_eq!(free(3), 960);
+		assert_eq!(PredictionMarkets::shares((0, 0), 4), 20);
+		assert_eq!(PredictionMarkets::offer(0).unwrap().amount, 30);
+
+		assert_noop!(
+			PredictionMarkets::cancel_offer(Origin::signed(4), 0),
+			Error::<Test>::NotSeller
+		);
+		assert_ok!(PredictionMarkets::cancel_offer(Origin::signed(3), 0));
+		assert_eq!(PredictionMarkets::shares((0, 0), 3), 80);
+		assert!(PredictionMarkets::offer(0).is_none());
+	});
+}
+
+#[test]
+fn filled_offer_is_removed() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_ok!(PredictionMarkets::create_offer(Origin::signed(3), 0, 1, 50, 2));
+		assert_ok!(PredictionMarkets::fill_offer(Origin::signed(4), 0, 50));
+		assert_eq!(PredictionMarkets::shares((0, 1), 4), 50);
+		assert!(PredictionMarkets::offer(0).is_none());
+		assert_noop!(
+			PredictionMarkets::fill_offer(Origin::signed(4), 0, 1),
+			Error::<Test>::UnknownOffer
+		);
+	});
+}
+
+#[test]
+fn only_oracle_reports_closed_markets() {
+	new_test_ext().execute_with(|| {
+		setup();
+		assert_noop!(
+			PredictionMarkets::report(Origin::signed(ORACLE), 0, 0),
+			Error::<Test>::MarketNotClosed
+		);
+
+		run_to_block(11);
+		assert_noop!(PredictionMarkets::report(Origin::signed(3), 0, 0), Error::<Test>::NotOracle);
+		assert_noop!(
+			PredictionMarkets::report(Origin::signed(ORACLE), 0, 2),
+			Error::<Test>::UnknownOutcome
+		);
+		assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 1));
+		assert_eq!(status(0), MarketStatus::Reported { outcome: 1, deadline: 16 });
+		assert_noop!(
+			PredictionMarkets::report(Origin::signed(ORACLE), 0, 0),
+			Error::<Test>::MarketNotClosed
+		);
+	});
+}
+
+#[test]
+fn undisputed_report_resolves_after_dispute_period() {
+	new_test_ext().execute_with(|| {
+		reported();
+		// The report deadline of the closed market passes without effect.
+		run_to_block(15);
+		assert_eq!(status(0), MarketStatus::Reported { outcome: 0, deadline: 16 });
+
+		run_to_block(16);
+		assert_eq!(status(0), MarketStatus::Resolved(0));
+		assert_eq!(reserved(CREATOR), 0);
+		assert_eq!(free(CREATOR), 1_000);
+		assert_noop!(
+			PredictionMarkets::dispute(Origin::signed(4), 0, 1),
+			Error::<Test>::MarketNotReported
+		);
+		assert_noop!(
+			PredictionMarkets::sell_complete_set(Origin::signed(3), 0, 10),
+			Error::<Test>::MarketResolved
+		);
+	});
+}
+
+#[test]
+fn missing_report_escalates_and_slashes_deposit() {
+	new_test_ext().execute_with(|| {
+		setup();
+		run_to_block(15);
+		assert_eq!(status(0), MarketStatus::Escalated);
+		assert_eq!(reserved(CREATOR), 0);
+		assert_eq!(free(CREATOR), 990);
+		assert_noop!(
+			PredictionMarkets::report(Origin::signed(ORACLE), 0, 0),
+			Error::<Test>::MarketNotClosed
+		);
+
+		assert_noop!(
+			PredictionMarkets::resolve(Origin::signed(ORACLE), 0, 1),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(PredictionMarkets::resolve(Origin::root(), 0, 1));
+		assert_eq!(status(0), MarketStatus::Resolved(1));
+		assert_eq!(free(CREATOR), 990);
+	});
+}
+
+#[test]
+fn disputes_are_bonded_and_escalate() {
+	new_test_ext().execute_with(|| {
+		reported();
+		assert_noop!(
+			PredictionMarkets::dispute(Origin::signed(4), 0, 0),
+			Error::<Test>::SameOutcome
+		);
+		assert_noop!(
+			PredictionMarkets::dispute(Origin::signed(4), 0, 2),
+			Error::<Test>::UnknownOutcome
+		);
+		assert_ok!(PredictionMarkets::dispute(Origin::signed(4), 0, 1));
+		assert_eq!(reserved(4), 50);
+		assert_eq!(
+			status(0),
+			MarketStatus::Disputed { reported: 0, proposed: 1, disputer: 4, bond: 50 }
+		);
+		assert_noop!(
+			PredictionMarkets::dispute(Origin::signed(3), 0, 1),
+			Error::<Test>::MarketNotReported
+		);
+
+		// Only governance resolves a disputed market.
+		run_to_block(20);
+		assert!(matches!(status(0), MarketStatus::Disputed { .. }));
+	});
+}
+
+#[test]
+fn upheld_report_slashes_dispute_bond() {
+	new_test_ext().execute_with(|| {
+		reported();
+		assert_ok!(PredictionMarkets::dispute(Origin::signed(4), 0, 1));
+		assert_ok!(PredictionMarkets::resolve(Origin::root(), 0, 0));
+		assert_eq!(status(0), MarketStatus::Resolved(0));
+		assert_eq!(reserved(4), 0);
+		assert_eq!(free(4), 950);
+		assert_eq!(reserved(CREATOR), 0);
+	});
+}
+
+#[test]
+fn overruled_report_returns_dispute_bond() {
+	new_test_ext().execute_with(|| {
+		reported();
+		assert_ok!(PredictionMarkets::dispute(Origin::signed(4), 0, 1));
+		assert_ok!(PredictionMarkets::resolve(Origin::root(), 0, 1));
+		assert_eq!(status(0), MarketStatus::Resolved(1));
+		assert_eq!(reserved(4), 0);
+		assert_eq!(free(4), 1_000);
+	});
+}
+
+#[test]
+fn resolve_requires_escalated_market() {
+	new_test_ext().execute_with(|| {
+		reported();
+		assert_noop!(
+			PredictionMarkets::resolve(Origin::root(), 0, 1),
+			Error::<Test>::MarketNotEscalated
+		);
+		assert_ok!(PredictionMarkets::dispute(Origin::signed(4), 0, 1));
+		assert_noop!(
+			PredictionMarkets::resolve(Origin::root(), 0, 2),
+			Error::<Test>::UnknownOutcome
+		);
+		assert_ok!(PredictionMarkets::resolve(Origin::root(), 0, 1));
+		assert_noop!(
+			PredictionMarkets::resolve(Origin::root(), 0, 0),
+			Error::<Test>::MarketNotEscalated
+		);
+	});
+}
+
+#[test]
+fn winning_shares_redeem_for_collateral() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		setup();
+		assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, 0, 4, 30));
+		assert_noop!(
+			PredictionMarkets::redeem(Origin::signed(3), 0),
+			Error::<Test>::MarketNotResolved
+		);
+		run_to_block(11);
+		assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 0));
+		run_to_block(16);
+
+		assert_ok!(PredictionMarkets::redeem(Origin::signed(3), 0));
+		assert_eq!(free(3), 970);
+		assert_eq!(PredictionMarkets::shares((0, 0), 3), 0);
+		assert_eq!(PredictionMarkets::shares((0, 1), 3), 0);
+		assert_ok!(PredictionMarkets::redeem(Origin::signed(4), 0));
+		assert_eq!(free(4), 1_030);
+		assert_noop!(PredictionMarkets::redeem(Origin::signed(4), 0), Error::<Test>::NoShares);
+
+		assert_eq!(PredictionMarkets::market(0).unwrap().collateral, 0);
+		assert_eq!(free(PredictionMarkets::account_id()), 0);
+		assert_eq!(Balances::total_issuance(), issuance);
+	});
+}
+
+#[test]
+fn categorical_market_pays_single_winner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 4, 10, ORACLE));
+		assert_ok!(PredictionMarkets::buy_complete_set(Origin::signed(3), 0, 100));
+		assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, 3, 4, 100));
+		run_to_block(10);
+		assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 3));
+		run_to_block(15);
+		assert_eq!(status(0), MarketStatus::Resolved(3));
+
+		assert_ok!(PredictionMarkets::redeem(Origin::signed(3), 0));
+		assert_eq!(free(3), 900);
+		assert_ok!(PredictionMarkets::redeem(Origin::signed(4), 0));
+		assert_eq!(free(4), 1_100);
+	});
+}
+
+#[test]
+fn full_block_defers_transitions() {
+	new_test_ext().execute_with(|| {
+		// Markets 0 and 1 fill block 10, where market 2 would reach its report deadline.
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE));
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 10, ORACLE));
+		assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, 5, ORACLE));
+		run_to_block(5);
+		assert_eq!(status(2), MarketStatus::Closed { deadline: 10 });
+		assert_eq!(PredictionMarkets::scheduled(11).into_inner(), vec![2]);
+
+		run_to_block(10);
+		assert_eq!(status(0), MarketStatus::Closed { deadline: 15 });
+		assert_eq!(status(2), MarketStatus::Closed { deadline: 10 });
+		run_to_block(11);
+		assert_eq!(status(2), MarketStatus::Escalated);
+	});
+}
+
+#[test]
+fn unscheduled_report_deadlines_are_escalated_by_anyone() {
+	new_test_ext().execute_with(|| {
+		// Markets 0 to 3 fill blocks 10 and 11, so market 4 closing at block 5 finds no room for
+		// its report deadline.
+		for close in vec![10, 10, 11, 11, 5] {
+			assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, close, ORACLE));
+		}
+		run_to_block(5);
+		assert_eq!(status(4), MarketStatus::Closed { deadline: 10 });
+		assert!(!PredictionMarkets::scheduled(10).contains(&4));
+		assert!(!PredictionMarkets::scheduled(11).contains(&4));
+
+		run_to_block(12);
+		assert_eq!(status(4), MarketStatus::Closed { deadline: 10 });
+		// Market 0 closed at block 10, and its oracle can report until block 15.
+		assert_noop!(
+			PredictionMarkets::escalate(Origin::signed(3), 0),
+			Error::<Test>::ReportDeadlineNotPassed
+		);
+
+		assert_ok!(PredictionMarkets::escalate(Origin::signed(3), 4));
+		assert_eq!(status(4), MarketStatus::Escalated);
+		assert_noop!(
+			PredictionMarkets::escalate(Origin::signed(3), 4),
+			Error::<Test>::MarketNotClosed
+		);
+	});
+}
+
+#[test]
+fn reports_are_refused_when_the_dispute_deadline_is_full() {
+	new_test_ext().execute_with(|| {
+		setup();
+		for close in vec![16, 16, 17, 17] {
+			assert_ok!(PredictionMarkets::create_market(Origin::signed(CREATOR), 2, close, ORACLE));
+		}
+
+		// The dispute period would end in block 16, and both it and block 17 are full.
+		run_to_block(11);
+		assert_noop!(
+			PredictionMarkets::report(Origin::signed(ORACLE), 0, 0),
+			Error::<Test>::TooManyMarketsClosing
+		);
+
+		run_to_block(12);
+		assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 0));
+		assert_eq!(status(0), MarketStatus::Reported { outcome: 0, deadline: 17 });
+		assert_eq!(PredictionMarkets::scheduled(18).into_inner(), vec![0]);
+	});
+}
+
+#[test]
+fn payouts_below_the_existential_deposit_need_an_existing_account() {
+	new_test_ext().execute_with(|| {
+		setup();
+		for outcome in 0..2 {
+			assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, outcome, 5, 3));
+			assert_ok!(PredictionMarkets::transfer_shares(Origin::signed(3), 0, outcome, 4, 3));
+		}
+		assert_noop!(
+			PredictionMarkets::sell_complete_set(Origin::signed(5), 0, 3),
+			Error::<Test>::PayoutBelowMinimum
+		);
+
+		run_to_block(11);
+		assert_ok!(PredictionMarkets::report(Origin::signed(ORACLE), 0, 0));
+		run_to_block(16);
+		assert_noop!(
+			PredictionMarkets::redeem(Origin::signed(5), 0),
+			Error::<Test>::PayoutBelowMinimum
+		);
+		assert_eq!(PredictionMarkets::shares((0, 0), 5), 3);
+
+		assert_ok!(PredictionMarkets::redeem(Origin::signed(4), 0));
+		assert_eq!(free(4), 1_003);
+	});
+}
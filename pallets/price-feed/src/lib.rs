@@ -0,0 +1,177 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Prices of assets in units of the native currency, fed by trusted accounts.
+//!
+//! A feeder's `feed` replaces their own price of an asset. Other pallets read prices through
+//! [`PriceProvider`], which this pallet implements by serving the median of the prices fed by the
+//! current feeders in the last `MaxPriceAge` blocks, once there are `MinFeeds` of them, so no
+//! single feeder sets the price.
+
+use frame_support::weights::Weight;
+pub use pallet::*;
+use sp_runtime::FixedU128;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// A source of asset prices.
+pub trait PriceProvider<AssetId> {
+	/// The price of one unit of `asset` in units of the native currency, if it is known.
+	fn price(asset: AssetId) -> Option<FixedU128>;
+
+	/// The most weight a call to `price` can take.
+	fn price_weight() -> Weight;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::PriceProvider;
+	use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::{
+		traits::{Saturating, Zero},
+		FixedU128,
+	};
+	use sp_std::vec::Vec;
+
+	/// A price and the block it was fed in.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct PricePoint<BlockNumber> {
+		/// Units of the native currency per unit of the asset.
+		pub price: FixedU128,
+		/// The block the price was fed in.
+		pub updated: BlockNumber,
+	}
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Identifier of the assets prices are fed for.
+		type AssetId: Parameter + Member + Copy;
+
+		/// Origin allowed to change the feeders.
+		type FeedersOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of feeders.
+		#[pallet::constant]
+		type MaxFeeders: Get<u32>;
+
+		/// Number of prices fed that are needed to serve a price.
+		#[pallet::constant]
+		type MinFeeds: Get<u32>;
+
+		/// Number of blocks after which a price fed no longer counts.
+		#[pallet::constant]
+		type MaxPriceAge: Get<Self::BlockNumber>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Accounts allowed to feed prices, sorted.
+	#[pallet::storage]
+	#[pallet::getter(fn feeders)]
+	pub type Feeders<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+
+	/// The last price fed by each feeder for each asset.
+	#[pallet::storage]
+	#[pallet::getter(fn price_point)]
+	pub type Prices<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AssetId,
+		Blake2_128Concat,
+		T::AccountId,
+		PricePoint<T::BlockNumber>,
+	>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The feeders changed. [feeders]
+		FeedersSet(Vec<T::AccountId>),
+		/// A feeder updated the price of an asset. [asset, price, feeder]
+		PriceFed(T::AssetId, FixedU128, T::AccountId),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// More feeders than `MaxFeeders`.
+		TooManyFeeders,
+		/// The sender is not a feeder.
+		NotFeeder,
+		/// Prices must be positive.
+		ZeroPrice,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the accounts allowed to feed prices.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_feeders(origin: OriginFor<T>, mut feeders: Vec<T::AccountId>) -> DispatchResult {
+			T::FeedersOrigin::ensure_origin(origin)?;
+			feeders.sort();
+			feeders.dedup();
+			ensure!(feeders.len() as u32 <= T::MaxFeeders::get(), Error::<T>::TooManyFeeders);
+
+			Feeders::<T>::put(&feeders);
+
+			Self::deposit_event(Event::FeedersSet(feeders));
+			Ok(())
+		}
+
+		/// Set the sender's price of `asset`, in units of the native currency per unit of the
+		/// asset.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn feed(origin: OriginFor<T>, asset: T::AssetId, price: FixedU128) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::feeders().binary_search(&who).is_ok(), Error::<T>::NotFeeder);
+			ensure!(!price.is_zero(), Error::<T>::ZeroPrice);
+
+			let updated = frame_system::Pallet::<T>::block_number();
+			Prices::<T>::insert(asset, &who, PricePoint { price, updated });
+
+			Self::deposit_event(Event::PriceFed(asset, price, who));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> PriceProvider<T::AssetId> for Pallet<T> {
+		fn price(asset: T::AssetId) -> Option<FixedU128> {
+			let now = frame_system::Pallet::<T>::block_number();
+			// Only the current feeders' prices are read, so the reads are bounded by `MaxFeeders`.
+			let mut prices: Vec<_> = Self::feeders()
+				.iter()
+				.filter_map(|feeder| Prices::<T>::get(asset, feeder))
+				.filter(|point| now.saturating_sub(point.updated) <= T::MaxPriceAge::get())
+				.map(|point| point.price)
+				.collect();
+			if prices.is_empty() || (prices.len() as u32) < T::MinFeeds::get() {
+				return None
+			}
+			prices.sort();
+			Some(median(&prices))
+		}
+
+		fn price_weight() -> Weight {
+			T::DbWeight::get().reads(1 + T::MaxFeeders::get() as Weight)
+		}
+	}
+
+	/// The median of sorted prices, the mean of the two middle ones for an even number.
+	fn median(prices: &[FixedU128]) -> FixedU128 {
+		let upper = prices[prices.len() / 2];
+		if prices.len() % 2 == 1 {
+			return upper
+		}
+		let lower = prices[prices.len() / 2 - 1];
+		FixedU128::from_inner(lower.into_inner() + (upper.into_inner() - lower.into_inner()) / 2)
+	}
+}
@@ -0,0 +1,94 @@
+use crate::{mock::*, Error, PriceProvider};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128};
+
+fn price(asset: u32) -> Option<FixedU128> {
+	<PriceFeed as PriceProvider<u32>>::price(asset)
+}
+
+fn feed(who: u64, price: u128) {
+	assert_ok!(PriceFeed::feed(Origin::signed(who), 0, FixedU128::from(price)));
+}
+
+#[test]
+fn feeders_are_set_by_feeders_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(PriceFeed::set_feeders(Origin::signed(1), vec![3]), DispatchError::BadOrigin);
+		assert_noop!(
+			PriceFeed::set_feeders(Origin::root(), vec![1, 2, 3, 4]),
+			Error::<Test>::TooManyFeeders
+		);
+
+		// Duplicates count once.
+		assert_ok!(PriceFeed::set_feeders(Origin::root(), vec![3, 1, 3, 2]));
+		assert_eq!(PriceFeed::feeders(), vec![1, 2, 3]);
+	});
+}
+
+#[test]
+fn only_feeders_feed_positive_prices() {
+	new_test_ext().execute_with(|| {
+		let fed = FixedU128::saturating_from_rational(3, 2);
+		assert_noop!(PriceFeed::feed(Origin::signed(3), 0, fed), Error::<Test>::NotFeeder);
+		assert_noop!(
+			PriceFeed::feed(Origin::signed(1), 0, FixedU128::from(0)),
+			Error::<Test>::ZeroPrice
+		);
+
+		assert_ok!(PriceFeed::feed(Origin::signed(1), 0, fed));
+		assert_eq!(PriceFeed::price_point(0, 1).unwrap().price, fed);
+		// A single feed is not enough to serve a price.
+		assert_eq!(price(0), None);
+
+		// The median of two prices is their mean.
+		feed(2, 2);
+		assert_eq!(price(0), Some(FixedU128::saturating_from_rational(7, 4)));
+		assert_eq!(price(1), None);
+
+		// A feeder replaces only their own price.
+		feed(1, 3);
+		assert_eq!(price(0), Some(FixedU128::saturating_from_rational(5, 2)));
+	});
+}
+
+#[test]
+fn a_single_feeder_does_not_set_the_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PriceFeed::set_feeders(Origin::root(), vec![1, 2, 3]));
+		feed(1, 2);
+		feed(2, 3);
+		feed(3, 4);
+		assert_eq!(price(0), Some(FixedU128::from(3)));
+
+		feed(3, 1_000_000);
+		assert_eq!(price(0), Some(FixedU128::from(3)));
+		feed(1, 1);
+		assert_eq!(price(0), Some(FixedU128::from(3)));
+
+		// Prices of removed feeders no longer count.
+		assert_ok!(PriceFeed::set_feeders(Origin::root(), vec![1, 2]));
+		assert_eq!(price(0), Some(FixedU128::from(2)));
+	});
+}
+
+#[test]
+fn stale_prices_are_not_served() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PriceFeed::set_feeders(Origin::root(), vec![1, 2, 3]));
+		feed(1, 2);
+		feed(2, 4);
+		System::set_block_number(5);
+		feed(3, 9);
+
+		System::set_block_number(11);
+		assert_eq!(price(0), Some(FixedU128::from(4)));
+
+		// Without the prices fed at block 1, the one left is not enough.
+		System::set_block_number(12);
+		assert_eq!(price(0), None);
+		assert_eq!(PriceFeed::price_point(0, 1).unwrap().updated, 1);
+
+		feed(1, 6);
+		assert_eq!(price(0), Some(FixedU128::saturating_from_rational(15, 2)));
+	});
+}
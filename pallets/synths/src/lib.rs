@@ -0,0 +1,435 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Synthetic assets tracking the price of off-chain assets, minted against staked native tokens.
+//!
+//! Governance registers synths: assets whose price in units of the native currency is given by
+//! the price provider. Registering a synth creates its asset, owned by the account of `PalletId`
+//! so that only this pallet mints it. Stakers reserve native tokens as collateral and mint synths against them,
+//! as long as their collateral stays at least `CollateralRatio` times their debt.
+//!
+//! Debt is pooled. The global debt is the value of all synths in circulation, and each staker owes
+//! the part of it given by their debt shares, which are issued when they mint and cancelled when
+//! they burn. The debt of a staker therefore follows the prices of all synths, not only of those
+//! they minted, and burning synths worth their whole debt clears it.
+//!
+//! Anyone holding synths can exchange them for another synth at oracle prices, less an
+//! `ExchangeFee` that is not minted. As the global debt is the value of the synths in
+//! circulation, the fee lowers the debt of every staker in proportion to their shares.
+//!
+//! Debt is valued in units of 10^-18 of the native currency, so only debt shares and synth
+//! amounts are rounded, always in favour of the other stakers: shares are rounded up when minting
+//! and down when burning.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		dispatch::DispatchResult,
+		pallet_prelude::*,
+		traits::{
+			tokens::fungibles::{Create, Inspect, Mutate},
+			Currency, ReservableCurrency,
+		},
+		transactional, BoundedVec, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_price_feed::PriceProvider;
+	use sp_core::U256;
+	use sp_runtime::{
+		traits::{AccountIdConversion, CheckedSub, Saturating, Zero},
+		ArithmeticError, FixedPointNumber, FixedU128, Perbill,
+	};
+	use sp_std::convert::{TryFrom, TryInto};
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type AssetIdOf<T> =
+		<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+
+	/// The collateral and debt of a staker.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, Default)]
+	pub struct Staker<Balance> {
+		/// Native tokens reserved as collateral.
+		pub collateral: Balance,
+		/// Shares of the global debt owed.
+		pub debt_shares: u128,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The native currency, reserved as collateral.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The assets synths are minted as.
+		type Assets: Create<Self::AccountId> + Mutate<Self::AccountId, Balance = BalanceOf<Self>>;
+
+		/// Prices of the synths in the native currency.
+		type Prices: PriceProvider<AssetIdOf<Self>>;
+
+		/// Origin allowed to register synths.
+		type SynthOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Identifier of the account owning the synth assets.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Maximum number of synths.
+		#[pallet::constant]
+		type MaxSynths: Get<u32>;
+
+		/// Minimum ratio of the collateral of a staker to their debt after minting or unstaking.
+		#[pallet::constant]
+		type CollateralRatio: Get<FixedU128>;
+
+		/// Part of the value of an exchange that is not minted.
+		#[pallet::constant]
+		type ExchangeFee: Get<Perbill>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The registered synths.
+	#[pallet::storage]
+	#[pallet::getter(fn synths)]
+	pub type Synths<T: Config> =
+		StorageValue<_, BoundedVec<AssetIdOf<T>, T::MaxSynths>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn staker)]
+	pub type Stakers<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Staker<BalanceOf<T>>>;
+
+	/// The debt shares of all stakers.
+	#[pallet::storage]
+	#[pallet::getter(fn total_debt_shares)]
+	pub type TotalDebtShares<T> = StorageValue<_, u128, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		AssetIdOf<T> = "AssetId",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A synth was registered. [synth]
+		SynthRegistered(AssetIdOf<T>),
+		/// Collateral was staked. [staker, amount]
+		Staked(T::AccountId, BalanceOf<T>),
+		/// Collateral was unstaked. [staker, amount]
+		Unstaked(T::AccountId, BalanceOf<T>),
+		/// A staker minted synths, taking on debt. [staker, synth, amount]
+		Minted(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+		/// A staker burned synths, paying back debt. [staker, synth, amount]
+		Burned(T::AccountId, AssetIdOf<T>, BalanceOf<T>),
+		/// Synths were exchanged. [account, from, amount, to, received]
+		Exchanged(T::AccountId, AssetIdOf<T>, BalanceOf<T>, AssetIdOf<T>, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The asset is already a synth.
+		AlreadyRegistered,
+		/// More synths than `MaxSynths`.
+		TooManySynths,
+		/// The asset is not a synth.
+		UnknownSynth,
+		/// The price provider has no price for a synth.
+		NoPrice,
+		/// The collateral would be less than `CollateralRatio` times the debt.
+		InsufficientCollateral,
+		/// The account has not staked that much.
+		InsufficientStake,
+		/// The account owes no debt.
+		NoDebt,
+		/// Synths can only be exchanged for another synth.
+		SameSynth,
+		/// The exchange would return nothing.
+		ExchangeTooSmall,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register `synth` as a synth, creating its asset with `min_balance` and the pallet
+		/// account as owner. The asset must not exist. Its price must be known to the price
+		/// provider for any synth to be minted or burned, as these change the global debt.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		#[transactional]
+		pub fn register_synth(
+			origin: OriginFor<T>,
+			synth: AssetIdOf<T>,
+			#[pallet::compact] min_balance: BalanceOf<T>,
+		) -> DispatchResult {
+			T::SynthOrigin::ensure_origin(origin)?;
+
+			Synths::<T>::try_mutate(|synths| -> DispatchResult {
+				ensure!(!synths.contains(&synth), Error::<T>::AlreadyRegistered);
+				synths.try_push(synth).map_err(|_| Error::<T>::TooManySynths)?;
+				Ok(())
+			})?;
+			// Every unit in circulation counts towards the debt, so no account may mint synths.
+			T::Assets::create(synth, Self::account_id(), true, min_balance)?;
+
+			Self::deposit_event(Event::SynthRegistered(synth));
+			Ok(())
+		}
+
+		/// Reserve `amount` of the sender's native tokens as collateral.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn stake(
+			origin: OriginFor<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			T::Currency::reserve(&who, amount)?;
+			Stakers::<T>::mutate(&who, |staker| {
+				let staker = staker.get_or_insert_with(Default::default);
+				staker.collateral = staker.collateral.saturating_add(amount);
+			});
+
+			Self::deposit_event(Event::Staked(who, amount));
+			Ok(())
+		}
+
+		/// Unreserve `amount` of the sender's collateral, keeping it at least `CollateralRatio`
+		/// times their debt.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(3 + 2 * T::MaxSynths::get() as Weight, 2)
+		)]
+		pub fn unstake(
+			origin: OriginFor<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut staker = Stakers::<T>::get(&who).ok_or(Error::<T>::InsufficientStake)?;
+			staker.collateral =
+				staker.collateral.checked_sub(&amount).ok_or(Error::<T>::InsufficientStake)?;
+			if staker.debt_shares > 0 {
+				let debt = Self::debt_value(
+					staker.debt_shares,
+					Self::global_debt()?,
+					TotalDebtShares::<T>::get(),
+				)?;
+				Self::ensure_collateralized(staker.collateral, debt)?;
+			}
+			T::Currency::unreserve(&who, amount);
+			Self::put_staker(&who, staker);
+
+			Self::deposit_event(Event::Unstaked(who, amount));
+			Ok(())
+		}
+
+		/// Mint `amount` of a synth to the sender, adding its value to their debt.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4 + 2 * T::MaxSynths::get() as Weight, 4)
+		)]
+		#[transactional]
+		pub fn mint(
+			origin: OriginFor<T>,
+			synth: AssetIdOf<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let value = Self::synth_price(synth)? * U256::from(into_u128::<T>(amount)?);
+			let debt = Self::global_debt()?;
+			let total_shares = TotalDebtShares::<T>::get();
+			// Shares left when the debt is zero are rounding dust; they are valued anew with the
+			// first shares issued.
+			let shares: u128 = if total_shares == 0 || debt.is_zero() {
+				from_u256(mul_div(value, SHARES_PER_UNIT.into(), DIV.into(), true)?)?
+			} else {
+				from_u256(mul_div(value, total_shares.into(), debt, true)?)?
+			};
+
+			let mut staker = Stakers::<T>::get(&who).unwrap_or_default();
+			staker.debt_shares =
+				staker.debt_shares.checked_add(shares).ok_or(ArithmeticError::Overflow)?;
+			let total_shares = total_shares.checked_add(shares).ok_or(ArithmeticError::Overflow)?;
+			let owed = Self::debt_value(staker.debt_shares, debt + value, total_shares)?;
+			Self::ensure_collateralized(staker.collateral, owed)?;
+
+			T::Assets::mint_into(synth, &who, amount)?;
+			Stakers::<T>::insert(&who, staker);
+			TotalDebtShares::<T>::put(total_shares);
+
+			Self::deposit_event(Event::Minted(who, synth, amount));
+			Ok(())
+		}
+
+		/// Burn `amount` of a synth from the sender, removing its value from their debt. If it is
+		/// worth at least their debt, only the synths needed to clear the debt are burned.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4 + 2 * T::MaxSynths::get() as Weight, 4)
+		)]
+		#[transactional]
+		pub fn burn(
+			origin: OriginFor<T>,
+			synth: AssetIdOf<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let price = Self::synth_price(synth)?;
+			let mut staker = Stakers::<T>::get(&who)
+				.filter(|staker| staker.debt_shares > 0)
+				.ok_or(Error::<T>::NoDebt)?;
+			let debt = Self::global_debt()?;
+			let total_shares = TotalDebtShares::<T>::get();
+			let owed = Self::debt_value(staker.debt_shares, debt, total_shares)?;
+
+			let value = price * U256::from(into_u128::<T>(amount)?);
+			let (amount, shares) = if value >= owed {
+				(from_u256(mul_div(owed, U256::one(), price, true)?)?, staker.debt_shares)
+			} else {
+				(amount, from_u256(mul_div(value, total_shares.into(), debt, false)?)?)
+			};
+			staker.debt_shares -= shares;
+
+			T::Assets::burn_from(synth, &who, amount)?;
+			Self::put_staker(&who, staker);
+			TotalDebtShares::<T>::put(total_shares.saturating_sub(shares));
+
+			Self::deposit_event(Event::Burned(who, synth, amount));
+			Ok(())
+		}
+
+		/// Exchange `amount` of the synth `from` for the synth `to` at their prices, less the
+		/// exchange fee.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 4))]
+		#[transactional]
+		pub fn exchange(
+			origin: OriginFor<T>,
+			from: AssetIdOf<T>,
+			to: AssetIdOf<T>,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(from != to, Error::<T>::SameSynth);
+
+			let value = Self::synth_price(from)? * U256::from(into_u128::<T>(amount)?);
+			let fee = T::ExchangeFee::get();
+			let fee = mul_div(value, fee.deconstruct().into(), Perbill::ACCURACY.into(), true)?;
+			let received: BalanceOf<T> =
+				from_u256(mul_div(value - fee, U256::one(), Self::synth_price(to)?, false)?)?;
+			ensure!(!received.is_zero(), Error::<T>::ExchangeTooSmall);
+
+			T::Assets::burn_from(from, &who, amount)?;
+			T::Assets::mint_into(to, &who, received)?;
+
+			Self::deposit_event(Event::Exchanged(who, from, amount, to, received));
+			Ok(())
+		}
+	}
+
+	/// Denominator of the inner value of a `FixedU128`.
+	const DIV: u128 = <FixedU128 as FixedPointNumber>::DIV;
+
+	/// Debt shares issued per unit of debt when there is none, finer than units so that the
+	/// rounding of shares moves debts by a millionth of a unit at most.
+	const SHARES_PER_UNIT: u128 = 1_000_000;
+
+	fn into_u128<T: Config>(amount: BalanceOf<T>) -> Result<u128, DispatchError> {
+		amount.try_into().map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	fn from_u256<N: TryFrom<u128>>(value: U256) -> Result<N, DispatchError> {
+		if value > U256::from(u128::MAX) {
+			return Err(ArithmeticError::Overflow.into())
+		}
+		N::try_from(value.low_u128()).map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	/// `value * numerator / denominator`, rounded up or down.
+	fn mul_div(
+		value: U256,
+		numerator: U256,
+		denominator: U256,
+		round_up: bool,
+	) -> Result<U256, DispatchError> {
+		let product = value.checked_mul(numerator).ok_or(ArithmeticError::Overflow)?;
+		let mut result = product.checked_div(denominator).ok_or(ArithmeticError::DivisionByZero)?;
+		if round_up && !(product % denominator).is_zero() {
+			result += U256::one();
+		}
+		Ok(result)
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account owning the synth assets.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// The debt of `who` in units of the native currency, rounded up.
+		pub fn debt_of(who: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+			let shares = Self::staker(who).map_or(0, |staker| staker.debt_shares);
+			let debt = Self::debt_value(shares, Self::global_debt()?, Self::total_debt_shares())?;
+			from_u256(mul_div(debt, U256::one(), DIV.into(), true)?)
+		}
+
+		/// The value of all synths in circulation, in units of 10^-18 of the native currency.
+		fn global_debt() -> Result<U256, DispatchError> {
+			let mut debt = U256::zero();
+			for synth in Self::synths().iter() {
+				let issued = into_u128::<T>(T::Assets::total_issuance(*synth))?;
+				let value = Self::price(*synth)? * U256::from(issued);
+				debt = debt.checked_add(value).ok_or(ArithmeticError::Overflow)?;
+			}
+			Ok(debt)
+		}
+
+		/// The value of `shares` of a global debt of `debt`, rounded up.
+		fn debt_value(shares: u128, debt: U256, total_shares: u128) -> Result<U256, DispatchError> {
+			if shares == 0 {
+				return Ok(U256::zero())
+			}
+			mul_div(shares.into(), debt, total_shares.into(), true)
+		}
+
+		/// The price of a synth in units of 10^-18 of the native currency.
+		fn price(synth: AssetIdOf<T>) -> Result<U256, DispatchError> {
+			let price = T::Prices::price(synth).filter(|price| !price.is_zero());
+			Ok(price.ok_or(Error::<T>::NoPrice)?.into_inner().into())
+		}
+
+		/// The price of `synth`, checking that it is registered.
+		fn synth_price(synth: AssetIdOf<T>) -> Result<U256, DispatchError> {
+			ensure!(Self::synths().contains(&synth), Error::<T>::UnknownSynth);
+			Self::price(synth)
+		}
+
+		fn ensure_collateralized(collateral: BalanceOf<T>, debt: U256) -> DispatchResult {
+			// Both sides are in units of 10^-36 of the native currency.
+			let ratio = T::CollateralRatio::get().into_inner();
+			let required = debt.checked_mul(ratio.into()).ok_or(ArithmeticError::Overflow)?;
+			let collateral =
+				U256::from(into_u128::<T>(collateral)?) * U256::from(DIV) * U256::from(DIV);
+			ensure!(collateral >= required, Error::<T>::InsufficientCollateral);
+			Ok(())
+		}
+
+		fn put_staker(who: &T::AccountId, staker: Staker<BalanceOf<T>>) {
+			if staker.collateral.is_zero() && staker.debt_shares == 0 {
+				Stakers::<T>::remove(who);
+			} else {
+				Stakers::<T>::insert(who, staker);
+			}
+		}
+	}
+}
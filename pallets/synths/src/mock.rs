@@ -0,0 +1,169 @@
+use crate as pallet_synths;
+use frame_support::{parameter_types, weights::Weight, PalletId};
+use frame_system::{self as system, EnsureRoot};
+use pallet_price_feed::PriceProvider;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup, One},
+	FixedPointNumber, FixedU128, Perbill,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+		Synths: pallet_synths::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = MaxLocks;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const AssetDeposit: u64 = 0;
+	pub const ApprovalDeposit: u64 = 0;
+	pub const StringLimit: u32 = 16;
+	pub const MetadataDepositBase: u64 = 0;
+	pub const MetadataDepositPerByte: u64 = 0;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<u64>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static PRICES: RefCell<BTreeMap<u32, FixedU128>> = RefCell::new(BTreeMap::new());
+}
+
+/// Serves the prices set with `set_price`.
+pub struct MockPrices;
+
+impl PriceProvider<u32> for MockPrices {
+	fn price(asset: u32) -> Option<FixedU128> {
+		PRICES.with(|prices| prices.borrow().get(&asset).copied())
+	}
+
+	fn price_weight() -> Weight {
+		0
+	}
+}
+
+pub fn set_price(asset: u32, price: Option<FixedU128>) {
+	PRICES.with(|prices| match price {
+		Some(price) => prices.borrow_mut().insert(asset, price),
+		None => prices.borrow_mut().remove(&asset),
+	});
+}
+
+parameter_types! {
+	pub const MaxSynths: u32 = 3;
+	pub CollateralRatio: FixedU128 = FixedU128::saturating_from_integer(5);
+	pub const ExchangeFee: Perbill = Perbill::from_percent(1);
+	pub const SynthsPalletId: PalletId = PalletId(*b"py/synth");
+}
+
+impl pallet_synths::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type Assets = Assets;
+	type Prices = MockPrices;
+	type SynthOrigin = EnsureRoot<u64>;
+	type PalletId = SynthsPalletId;
+	type MaxSynths = MaxSynths;
+	type CollateralRatio = CollateralRatio;
+	type ExchangeFee = ExchangeFee;
+}
+
+/// A synth priced at 1.
+pub const SUSD: u32 = 0;
+/// A synth priced at 2.
+pub const SBTC: u32 = 1;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000), (2, 1_000), (3, 1_000), (4, 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		set_price(SUSD, Some(FixedU128::one()));
+		set_price(SBTC, Some(FixedU128::saturating_from_integer(2)));
+		for synth in [SUSD, SBTC].iter() {
+			Synths::register_synth(Origin::root(), *synth, 1).unwrap();
+		}
+	});
+	ext
+}
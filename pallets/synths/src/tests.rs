@@ -0,0 +1,196 @@
+use crate::{mock::*, Error, Staker};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128};
+
+fn synth(who: u64, asset: u32) -> u64 {
+	Assets::balance(asset, who)
+}
+
+fn debt(who: u64) -> u64 {
+	Synths::debt_of(&who).unwrap()
+}
+
+#[test]
+fn register_synth_checks_origin_and_limit() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Synths::synths().into_inner(), vec![SUSD, SBTC]);
+		assert_noop!(Synths::register_synth(Origin::signed(1), 2, 1), DispatchError::BadOrigin);
+		assert_noop!(
+			Synths::register_synth(Origin::root(), SBTC, 1),
+			Error::<Test>::AlreadyRegistered
+		);
+		assert_ok!(Synths::register_synth(Origin::root(), 2, 1));
+		assert_noop!(Synths::register_synth(Origin::root(), 3, 1), Error::<Test>::TooManySynths);
+	});
+}
+
+#[test]
+fn registering_a_synth_creates_an_asset_only_the_pallet_mints() {
+	new_test_ext().execute_with(|| {
+		// An existing asset may have an issuer minting outside of the debt.
+		assert_ok!(Assets::force_create(Origin::root(), 2, 1, true, 1));
+		assert_noop!(
+			Synths::register_synth(Origin::root(), 2, 1),
+			pallet_assets::Error::<Test>::InUse
+		);
+		assert_eq!(Synths::synths().into_inner(), vec![SUSD, SBTC]);
+
+		assert_ok!(Synths::register_synth(Origin::root(), 3, 1));
+		assert_noop!(
+			Assets::mint(Origin::signed(1), 3, 1, 10),
+			pallet_assets::Error::<Test>::NoPermission
+		);
+	});
+}
+
+#[test]
+fn staking_reserves_collateral() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Synths::stake(Origin::signed(1), 600));
+		assert_eq!(Balances::reserved_balance(1), 600);
+		assert_eq!(Synths::staker(1), Some(Staker { collateral: 600, debt_shares: 0 }));
+		assert_noop!(
+			Synths::stake(Origin::signed(1), 401),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+
+		assert_noop!(Synths::unstake(Origin::signed(1), 601), Error::<Test>::InsufficientStake);
+		assert_noop!(Synths::unstake(Origin::signed(2), 1), Error::<Test>::InsufficientStake);
+		assert_ok!(Synths::unstake(Origin::signed(1), 600));
+		assert_eq!(Balances::free_balance(1), 1_000);
+		assert!(Synths::staker(1).is_none());
+	});
+}
+
+#[test]
+fn minting_is_limited_by_the_collateral_ratio() {
+	new_test_ext().execute_with(|| {
+		// A collateral of 500 backs a debt of 100.
+		assert_ok!(Synths::stake(Origin::signed(1), 500));
+		assert_noop!(
+			Synths::mint(Origin::signed(1), SUSD, 101),
+			Error::<Test>::InsufficientCollateral
+		);
+		assert_ok!(Synths::mint(Origin::signed(1), SUSD, 60));
+		assert_ok!(Synths::mint(Origin::signed(1), SBTC, 20));
+		assert_eq!((synth(1, SUSD), synth(1, SBTC)), (60, 20));
+		assert_eq!(debt(1), 100);
+
+		assert_noop!(
+			Synths::mint(Origin::signed(1), SUSD, 1),
+			Error::<Test>::InsufficientCollateral
+		);
+		assert_noop!(Synths::unstake(Origin::signed(1), 1), Error::<Test>::InsufficientCollateral);
+		assert_noop!(
+			Synths::mint(Origin::signed(2), SUSD, 1),
+			Error::<Test>::InsufficientCollateral
+		);
+		assert_noop!(Synths::mint(Origin::signed(1), 2, 1), Error::<Test>::UnknownSynth);
+
+		// The global debt can't be valued without the price of every synth.
+		set_price(SBTC, None);
+		assert_noop!(Synths::mint(Origin::signed(1), SUSD, 1), Error::<Test>::NoPrice);
+	});
+}
+
+#[test]
+fn debt_is_shared_across_stakers() {
+	new_test_ext().execute_with(|| {
+		for who in 1..=2 {
+			assert_ok!(Synths::stake(Origin::signed(who), 1_000));
+		}
+		assert_ok!(Synths::mint(Origin::signed(1), SUSD, 100));
+		assert_ok!(Synths::mint(Origin::signed(2), SBTC, 50));
+		assert_eq!((debt(1), debt(2)), (100, 100));
+
+		// sBTC doubles: the global debt of 300 is still owed half each.
+		set_price(SBTC, Some(FixedU128::saturating_from_integer(4)));
+		assert_eq!((debt(1), debt(2)), (150, 150));
+
+		assert_ok!(Synths::burn(Origin::signed(1), SUSD, 60));
+		assert_eq!(synth(1, SUSD), 40);
+		assert_eq!((debt(1), debt(2)), (90, 150));
+
+		// 50 sBTC are worth more than the debt of 2, so only 38 are burned. Rounding them up
+		// leaves 1 with a debt of 40 + 12 * 4.
+		assert_ok!(Synths::burn(Origin::signed(2), SBTC, 50));
+		assert_eq!(synth(2, SBTC), 12);
+		assert_eq!((debt(1), debt(2)), (88, 0));
+		assert_eq!(Synths::staker(2), Some(Staker { collateral: 1_000, debt_shares: 0 }));
+		assert_eq!(Synths::total_debt_shares(), Synths::staker(1).unwrap().debt_shares);
+		assert_noop!(Synths::burn(Origin::signed(2), SBTC, 12), Error::<Test>::NoDebt);
+	});
+}
+
+#[test]
+fn exchange_fee_lowers_the_debt_of_stakers() {
+	new_test_ext().execute_with(|| {
+		for who in 1..=2 {
+			assert_ok!(Synths::stake(Origin::signed(who), 1_000));
+			assert_ok!(Synths::mint(Origin::signed(who), SUSD, 100));
+		}
+		assert_noop!(Synths::exchange(Origin::signed(1), SUSD, SUSD, 10), Error::<Test>::SameSynth);
+		assert_noop!(Synths::exchange(Origin::signed(1), SUSD, 2, 10), Error::<Test>::UnknownSynth);
+		assert_noop!(
+			Synths::exchange(Origin::signed(1), SUSD, SBTC, 1),
+			Error::<Test>::ExchangeTooSmall
+		);
+
+		// 100 sUSD less a fee of 1 buy 49 sBTC. Neither the fee nor the rounding is minted, so
+		// the global debt falls to 198.
+		assert_ok!(Synths::exchange(Origin::signed(1), SUSD, SBTC, 100));
+		assert_eq!((synth(1, SUSD), synth(1, SBTC)), (0, 49));
+		assert_eq!((debt(1), debt(2)), (99, 99));
+	});
+}
+
+#[test]
+fn debts_do_not_drift_over_many_mints_and_burns() {
+	new_test_ext().execute_with(|| {
+		// At 7/3, the value of sBTC is rounded in most mints and burns.
+		set_price(SBTC, Some(FixedU128::saturating_from_rational(7, 3)));
+		for who in 1..=3 {
+			assert_ok!(Synths::stake(Origin::signed(who), 1_000));
+		}
+		// Three times the value of the synths held by `who`.
+		let held = |who| 3 * synth(who, SUSD) + 7 * synth(who, SBTC);
+
+		let mut seed = 42u64;
+		let mut next = |n: u64| {
+			seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+			(seed >> 33) % n
+		};
+		for _ in 0..500 {
+			let who = 1 + next(3);
+			let asset = next(2) as u32;
+			let amount = 1 + next(20);
+			let balance = synth(who, asset);
+			if next(2) == 0 && debt(who) + 50 <= 200 {
+				assert_ok!(Synths::mint(Origin::signed(who), asset, amount));
+			} else if balance >= 2 {
+				// Burning at most half of a holding never clears a debt.
+				assert_ok!(Synths::burn(Origin::signed(who), asset, amount.min(balance / 2)));
+			}
+
+			// With constant prices, every debt stays within a unit of the value of the synths
+			// its staker minted and did not burn.
+			for staker in 1..=3 {
+				let difference = 3 * debt(staker) as i64 - held(staker) as i64;
+				assert!(difference.abs() <= 3, "staker {} drifted by {}/3", staker, difference);
+			}
+		}
+
+		// Burning every synth clears every debt.
+		for who in 1..=3 {
+			for asset in [SUSD, SBTC].iter() {
+				let balance = synth(who, *asset);
+				if balance > 0 && debt(who) > 0 {
+					assert_ok!(Synths::burn(Origin::signed(who), *asset, balance));
+				}
+			}
+			assert_eq!(debt(who), 0);
+		}
+		assert_eq!(Synths::total_debt_shares(), 0);
+		assert_eq!(Assets::total_supply(SUSD) + Assets::total_supply(SBTC), 0);
+	});
+}
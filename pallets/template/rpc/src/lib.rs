@@ -0,0 +1,108 @@
+//! RPC interface for the template pallet.
+//!
+//! Every custom pallet on this chain exposes its storage to clients the same way: a runtime API
+//! crate declares the read functions, the runtime implements them in `impl_runtime_apis!`, and an
+//! RPC crate like this one calls them at a given block and is mounted in the node's `create_full`.
+//! Light clients have no state to run the runtime API on, so the RPC crate also provides a light
+//! variant that has a full node execute the call with `light_rpc::remote_call`, mounted in
+//! `create_light`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use futures::TryFutureExt;
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use light_rpc::RUNTIME_ERROR;
+use sc_client_api::light::{Fetcher, RemoteBlockchain};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_template_runtime_api::TemplateApi as TemplateRuntimeApi;
+
+/// Template pallet RPC methods.
+#[rpc]
+pub trait TemplateApi<BlockHash> {
+	/// Returns the value stored in `Something` at the given block, or at the best block if
+	/// none is given.
+	#[rpc(name = "template_getSomething")]
+	fn get_something(&self, at: Option<BlockHash>) -> Result<Option<u32>>;
+}
+
+/// Template pallet RPC methods served by light clients.
+#[rpc]
+pub trait LightTemplateApi<BlockHash> {
+	/// Returns the value stored in `Something` at the given block, or at the best block if
+	/// none is given.
+	#[rpc(name = "template_getSomething")]
+	fn get_something(&self, at: Option<BlockHash>) -> BoxFuture<Option<u32>>;
+}
+
+/// Implements the [`TemplateApi`] RPC trait for interacting with the template pallet.
+pub struct Template<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Template<C, Block> {
+	/// Create a new `Template` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> TemplateApi<<Block as BlockT>::Hash> for Template<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: TemplateRuntimeApi<Block>,
+{
+	fn get_something(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Option<u32>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.get_something(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query `Something`.".into(),
+			data: Some(e.to_string().into()),
+		})
+	}
+}
+
+/// Implements the [`LightTemplateApi`] RPC trait by fetching results from full nodes.
+pub struct LightTemplate<C, F, Block: BlockT> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F, Block: BlockT> LightTemplate<C, F, Block> {
+	/// Create a new `LightTemplate` requesting calls through `fetcher`.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		Self { client, remote_blockchain, fetcher }
+	}
+}
+
+impl<C, F, Block> LightTemplateApi<<Block as BlockT>::Hash> for LightTemplate<C, F, Block>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+{
+	fn get_something(&self, at: Option<<Block as BlockT>::Hash>) -> BoxFuture<Option<u32>> {
+		let result = light_rpc::remote_call(
+			&*self.remote_blockchain,
+			self.fetcher.clone(),
+			at.unwrap_or_else(|| self.client.info().best_hash),
+			"TemplateApi_get_something",
+			Vec::new(),
+			"Unable to query `Something`.",
+		);
+
+		Box::new(Box::pin(result).compat())
+	}
+}
@@ -0,0 +1,14 @@
+//! Runtime API definition for the template pallet.
+//!
+//! The node calls into this API from `pallet-template-rpc` to read the pallet's storage without
+//! decoding raw storage keys.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+sp_api::decl_runtime_apis! {
+	/// Read access to the storage of the template pallet.
+	pub trait TemplateApi {
+		/// The value currently held in `Something`, if any.
+		fn get_something() -> Option<u32>;
+	}
+}
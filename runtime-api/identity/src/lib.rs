@@ -0,0 +1,21 @@
+//! Runtime API definition for `pallet_identity`.
+//!
+//! Lets clients show the name registrars vouched for next to an account, without reimplementing
+//! how judgements and sub-accounts combine.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Read access to verified identities.
+	pub trait IdentityApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// The display name of `who`, if a registrar judged its identity `Reasonable` or
+		/// `KnownGood` and none judged it `Erroneous`. The name of a sub-account is its parent's
+		/// verified name followed by `/` and the sub-account's own name.
+		fn verified_display_name(who: AccountId) -> Option<Vec<u8>>;
+	}
+}
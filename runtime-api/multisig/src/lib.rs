@@ -0,0 +1,47 @@
+//! Runtime API definition for `pallet_multisig`.
+//!
+//! Lets wallets show an account the operations of its multisig accounts still waiting for
+//! approvals, from the index kept by `pallet_multisig_index`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::vec::Vec;
+
+/// A multisig operation waiting for approvals.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PendingOperation<AccountId, Balance, BlockNumber> {
+	/// The multisig account the call is dispatched from.
+	pub multisig: AccountId,
+	/// Hash of the call to dispatch once enough signatories approved it.
+	pub call_hash: [u8; 32],
+	/// Block of the extrinsic opening the operation. Approvals must refer to it.
+	pub height: BlockNumber,
+	/// Index of that extrinsic in its block.
+	pub index: u32,
+	/// Deposit reserved from the depositor until the operation is executed or cancelled.
+	pub deposit: Balance,
+	/// The signatory who opened the operation.
+	pub depositor: AccountId,
+	/// Signatories who approved the operation so far, sorted.
+	pub approvals: Vec<AccountId>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Read access to the pending operations of multisig accounts, by signatory.
+	pub trait MultisigApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The operations waiting for approvals of the multisig accounts `signatory` is a
+		/// signatory of, whether or not it approved them yet.
+		fn pending_operations(
+			signatory: AccountId,
+		) -> Vec<PendingOperation<AccountId, Balance, BlockNumber>>;
+	}
+}
@@ -0,0 +1,91 @@
+//! Chain extension giving contracts access to native balances and to assets.
+//!
+//! Contracts call it through `seal_call_chain_extension` with one of the [`func_id`]s. Inputs and
+//! outputs are SCALE encoded.
+
+use crate::{AccountId, Assets, Balance, Balances, Runtime};
+use codec::Encode;
+use frame_support::{
+	storage::with_transaction,
+	traits::{Currency, Get},
+};
+use frame_system::RawOrigin;
+use pallet_assets::WeightInfo as _;
+use pallet_contracts::chain_extension::{
+	ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use sp_runtime::{DispatchError, MultiAddress, TransactionOutcome};
+
+/// Functions of the extension, with their input and output.
+pub mod func_id {
+	/// `AccountId -> Balance`: the free native balance of an account.
+	pub const BALANCE: u32 = 1;
+	/// `(AssetId, AccountId) -> Balance`: the balance of an asset held by an account.
+	pub const ASSET_BALANCE: u32 = 2;
+	/// `(AssetId, AccountId, Balance) -> ()`: transfer an asset from the contract to an account.
+	pub const ASSET_TRANSFER: u32 = 3;
+}
+
+/// Status code of a successful call.
+pub const SUCCESS: u32 = 0;
+/// Status code of an asset transfer that failed, for example for lack of balance. The transfer
+/// leaves no changes behind, and the contract keeps executing.
+pub const TRANSFER_FAILED: u32 = 1;
+
+/// The runtime's chain extension, see the [module docs](self).
+pub struct DefiExtension;
+
+impl ChainExtension<Runtime> for DefiExtension {
+	fn call<E>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+	where
+		E: Ext<T = Runtime>,
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		let read = <Runtime as frame_system::Config>::DbWeight::get().reads(1);
+		let mut env = env.buf_in_buf_out();
+
+		match func_id {
+			func_id::BALANCE => {
+				let who: AccountId = env.read_as()?;
+				env.charge_weight(read)?;
+				env.write(&balance(&who).encode(), false, None)?;
+			},
+			func_id::ASSET_BALANCE => {
+				let (asset, who): (u32, AccountId) = env.read_as()?;
+				env.charge_weight(read)?;
+				env.write(&asset_balance(asset, who).encode(), false, None)?;
+			},
+			func_id::ASSET_TRANSFER => {
+				let (asset, to, amount): (u32, AccountId, Balance) = env.read_as()?;
+				env.charge_weight(<Runtime as pallet_assets::Config>::WeightInfo::transfer())?;
+				let contract = env.ext().address().clone();
+				return Ok(RetVal::Converging(asset_transfer(contract, asset, to, amount)))
+			},
+			_ => return Err(DispatchError::Other("Unknown chain extension function.")),
+		}
+
+		Ok(RetVal::Converging(SUCCESS))
+	}
+}
+
+/// [`func_id::BALANCE`]: the free native balance of `who`.
+pub fn balance(who: &AccountId) -> Balance {
+	Balances::free_balance(who)
+}
+
+/// [`func_id::ASSET_BALANCE`]: the balance of `asset` held by `who`.
+pub fn asset_balance(asset: u32, who: AccountId) -> Balance {
+	Assets::balance(asset, who)
+}
+
+/// [`func_id::ASSET_TRANSFER`]: transfer `amount` of `asset` from `contract` to `to`, returning
+/// the status code for the contract. A failed transfer is rolled back.
+pub fn asset_transfer(contract: AccountId, asset: u32, to: AccountId, amount: Balance) -> u32 {
+	with_transaction(|| {
+		let origin = RawOrigin::Signed(contract).into();
+		match Assets::transfer(origin, asset, MultiAddress::Id(to), amount) {
+			Ok(()) => TransactionOutcome::Commit(SUCCESS),
+			Err(_) => TransactionOutcome::Rollback(TRANSFER_FAILED),
+		}
+	})
+}
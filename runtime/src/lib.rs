@@ -6,19 +6,34 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod chain_extension;
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, DecodeLimit, Encode};
+use fp_rpc::TransactionStatus;
+use frame_support::{traits::FindAuthor, weights::DispatchClass, ConsensusEngineId, PalletId};
+use frame_system::EnsureRoot;
+use max_encoded_len::MaxEncodedLen;
+use pallet_ethereum::{Call::transact, Transaction as EthereumTransaction};
+use pallet_evm::{
+	Account as EVMAccount, EnsureAddressTruncated, FeeCalculator, HashedAddressMapping, Runner,
+};
 use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
 };
 use sp_api::impl_runtime_apis;
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
-	transaction_validity::{TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, MultiSignature,
+	traits::{
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
+		IdentifyAccount, NumberFor, StaticLookup, Verify,
+	},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
+	ApplyExtrinsicResult, FixedPointNumber, FixedU128, MultiSignature, RuntimeDebug,
 };
-use sp_std::prelude::*;
+use sp_std::{marker::PhantomData, prelude::*};
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
@@ -26,7 +41,7 @@ use sp_version::RuntimeVersion;
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
 	construct_runtime, parameter_types,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{InstanceFilter, KeyOwnerProofSystem},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
@@ -38,7 +53,7 @@ pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::CurrencyAdapter;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{Perbill, Percent, Permill};
 
 /// Import the template pallet.
 pub use pallet_template;
@@ -80,7 +95,7 @@ pub mod opaque {
 
 	impl_opaque_keys! {
 		pub struct SessionKeys {
-			pub aura: Aura,
+			pub babe: Babe,
 			pub grandpa: Grandpa,
 		}
 	}
@@ -96,31 +111,62 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	//   the compatible custom types, and is bumped whenever the runtime logic changes.
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Bumped whenever the encoding or index of a call changes, which invalidates signed
+	//   transactions.
+	transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
 /// Blocks will be produced at a minimum duration defined by `SLOT_DURATION`.
 /// `SLOT_DURATION` is picked up by `pallet_timestamp` which is in turn picked
-/// up by `pallet_aura` to implement `fn slot_duration()`.
+/// up by `pallet_babe` to implement `fn slot_duration()`.
 ///
 /// Change this to adjust the block time.
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
 
 // NOTE: Currently it is not possible to change the slot duration after the chain has started.
-//       Attempting to do so will brick block production.
+//       Attempting to do so will brick block production. Chain specs can override it in genesis,
+//       which BABE's `EpochDuration` and `ExpectedBlockTime` follow, but `MINUTES`, `HOURS` and
+//       `DAYS` below keep assuming `MILLISECS_PER_BLOCK`.
 pub const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
 
+/// The token symbol wallets display. Claim statements name it too, see `ClaimsPrefix`.
+pub const TOKEN_SYMBOL: &str = "DEFI";
+
+/// One token, with the 12 decimals wallets display.
+pub const UNIT: Balance = 1_000_000_000_000;
+pub const MILLIUNIT: Balance = UNIT / 1_000;
+
+/// Deposit for keeping `items` storage items of `bytes` bytes in total on chain.
+pub const fn deposit(items: u32, bytes: u32) -> Balance {
+	items as Balance * 100 * MILLIUNIT + (bytes as Balance) * MILLIUNIT
+}
+
 // Time is measured by number of blocks.
 pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+/// Length of a BABE epoch in milliseconds. Its randomness comes from the VRF outputs of the
+/// blocks of the epoch before, so it can only be known once that epoch is over.
+pub const EPOCH_DURATION_IN_MILLISECS: u64 = 10 * 60_000;
+
+/// The probability of a slot having a primary author, `c` in the BABE paper.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+/// The BABE epoch configuration at genesis. Authors of secondary slots also produce a VRF
+/// output, so every block contributes to the randomness of the next epochs.
+pub const BABE_GENESIS_EPOCH_CONFIG: sp_consensus_babe::BabeEpochConfiguration =
+	sp_consensus_babe::BabeEpochConfiguration {
+		c: PRIMARY_PROBABILITY,
+		allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryVRFSlots,
+	};
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -193,12 +239,42 @@ impl frame_system::Config for Runtime {
 	type OnSetCode = ();
 }
 
-impl pallet_randomness_collective_flip::Config for Runtime {}
+parameter_types! {
+	/// Slots of an epoch, from the slot duration in genesis. Secondary slots let every slot have
+	/// a block, so an epoch has as many slots as blocks.
+	pub EpochDuration: u64 = EPOCH_DURATION_IN_MILLISECS / (2 * MinimumPeriod::get());
+	pub ExpectedBlockTime: u64 = 2 * MinimumPeriod::get();
+}
+
+impl pallet_babe::Config for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	/// There is no session pallet: the authorities of the genesis epoch author every epoch.
+	type EpochChangeTrigger = pallet_babe::SameAuthoritiesForever;
+
+	type KeyOwnerProofSystem = ();
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
 
-impl pallet_aura::Config for Runtime {
-	type AuthorityId = AuraId;
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = ();
+
+	type WeightInfo = ();
 }
 
+/// Randomness from the VRF outputs of the previous BABE epoch, returned with the first block of
+/// the current epoch: the block since which it is known. Authors can only bias it by withholding
+/// blocks, so it is fair for outcomes committed to before that block, such as lottery tickets
+/// bought in an earlier epoch.
+pub type EpochRandomness = pallet_babe::RandomnessFromOneEpochAgo<Runtime>;
+
 impl pallet_grandpa::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -219,13 +295,15 @@ impl pallet_grandpa::Config for Runtime {
 }
 
 parameter_types! {
-	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
+	/// Half the slot duration. Kept in storage so a chain spec can set the slot duration in
+	/// genesis, see the node's `chain_spec::Extensions`.
+	pub storage MinimumPeriod: u64 = SLOT_DURATION / 2;
 }
 
 impl pallet_timestamp::Config for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	type OnTimestampSet = Babe;
 	type MinimumPeriod = MinimumPeriod;
 	type WeightInfo = ();
 }
@@ -265,6 +343,649 @@ impl pallet_sudo::Config for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	// One storage item; key size is 32; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const DepositBase: Balance = deposit(1, 88);
+	// Additional storage item size of 32 bytes.
+	pub const DepositFactor: Balance = deposit(0, 32);
+	pub const MaxSignatories: u16 = 100;
+}
+
+impl pallet_multisig::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type DepositBase = DepositBase;
+	type DepositFactor = DepositFactor;
+	type MaxSignatories = MaxSignatories;
+	type WeightInfo = pallet_multisig::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_multisig_index::Config for Runtime {
+	type FindMultisigCalls = MultisigCalls;
+}
+
+/// How deep `MultisigCalls` looks into calls dispatched by other calls.
+const MAX_MULTISIG_CALL_DEPTH: u32 = 8;
+
+/// Finds the multisig calls of extrinsics for the multisig index, including those dispatched by
+/// batches, proxies, derivative accounts, `sudo_as` and the operations they execute.
+pub struct MultisigCalls;
+
+impl pallet_multisig_index::FindMultisigCalls<Call, AccountId> for MultisigCalls {
+	fn find(who: &AccountId, call: &Call) -> Vec<pallet_multisig_index::MultisigCall<AccountId>> {
+		let mut found = Vec::new();
+		find_multisig_calls(who, call, MAX_MULTISIG_CALL_DEPTH, &mut found);
+		found
+	}
+}
+
+fn find_multisig_calls(
+	who: &AccountId,
+	call: &Call,
+	depth: u32,
+	found: &mut Vec<pallet_multisig_index::MultisigCall<AccountId>>,
+) {
+	use pallet_multisig_index::MultisigCall;
+
+	let depth = match depth.checked_sub(1) {
+		Some(depth) => depth,
+		None => return,
+	};
+	match call {
+		Call::Multisig(pallet_multisig::Call::as_multi(threshold, others, _, data, ..)) => {
+			let multisig_call = MultisigCall {
+				signatory: who.clone(),
+				other_signatories: others.clone(),
+				threshold: *threshold,
+			};
+			// The operation dispatches its call from the multisig account once approved.
+			let multisig = MultisigIndex::multisig_account(&multisig_call);
+			found.push(multisig_call);
+			if let Ok(call) = Call::decode_with_depth_limit(sp_api::MAX_EXTRINSIC_DEPTH, data) {
+				find_multisig_calls(&multisig, &call, depth, found);
+			}
+		},
+		Call::Multisig(pallet_multisig::Call::approve_as_multi(threshold, others, ..)) |
+		Call::Multisig(pallet_multisig::Call::cancel_as_multi(threshold, others, ..)) =>
+			found.push(MultisigCall {
+				signatory: who.clone(),
+				other_signatories: others.clone(),
+				threshold: *threshold,
+			}),
+		// Operations of 1-of-N multisig accounts execute at once, without being indexed.
+		Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(others, call)) => {
+			let multisig = MultisigIndex::multisig_account(&MultisigCall {
+				signatory: who.clone(),
+				other_signatories: others.clone(),
+				threshold: 1,
+			});
+			find_multisig_calls(&multisig, call, depth, found);
+		},
+		Call::Utility(pallet_utility::Call::batch(calls)) |
+		Call::Utility(pallet_utility::Call::batch_all(calls)) =>
+			for call in calls {
+				find_multisig_calls(who, call, depth, found);
+			},
+		Call::Utility(pallet_utility::Call::as_derivative(index, call)) => {
+			let derivative = Utility::derivative_account_id(who.clone(), *index);
+			find_multisig_calls(&derivative, call, depth, found);
+		},
+		Call::Proxy(pallet_proxy::Call::proxy(real, _, call)) |
+		Call::Proxy(pallet_proxy::Call::proxy_announced(_, real, _, call)) =>
+			find_multisig_calls(real, call, depth, found),
+		Call::Sudo(pallet_sudo::Call::sudo_as(who, call)) =>
+			if let Ok(who) = <Runtime as frame_system::Config>::Lookup::lookup(who.clone()) {
+				find_multisig_calls(&who, call, depth, found);
+			},
+		_ => {},
+	}
+}
+
+impl pallet_utility::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type WeightInfo = pallet_utility::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	// Scheduled calls may use up to 80% of the block; tasks that do not fit are postponed to the
+	// next block.
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) *
+		BlockWeights::get().max_block;
+	pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+impl pallet_scheduler::Config for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	// Minimum 258 bytes of information.
+	pub const BasicDeposit: Balance = deposit(1, 258);
+	pub const FieldDeposit: Balance = deposit(0, 66);
+	// A sub-account id of 32 bytes and a name of up to 21 bytes.
+	pub const SubAccountDeposit: Balance = deposit(1, 53);
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl pallet_identity::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	// Deposits of identities killed by root are burnt.
+	type Slashed = ();
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type RegistrarOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = pallet_identity::weights::SubstrateWeight<Runtime>;
+}
+
+/// The bytes of identity data stored in the clear.
+fn raw_identity_data(data: pallet_identity::Data) -> Option<Vec<u8>> {
+	match data {
+		pallet_identity::Data::Raw(bytes) => Some(bytes.to_vec()),
+		_ => None,
+	}
+}
+
+/// The display name of `who`, if a registrar judged its identity, or that of the account it is a
+/// sub-account of, `Reasonable` or `KnownGood` and none judged it `Erroneous`. Sub-accounts are
+/// named `parent/sub`. Names not stored in the clear are not returned.
+fn verified_display_name(who: AccountId) -> Option<Vec<u8>> {
+	use pallet_identity::Judgement;
+
+	let (owner, sub_name) = match Identity::super_of(&who) {
+		Some((parent, name)) => (parent, raw_identity_data(name)),
+		None => (who, None),
+	};
+	let registration = Identity::identity(&owner)?;
+	let judged = |f: fn(&Judgement<Balance>) -> bool| {
+		registration.judgements.iter().any(|(_, judgement)| f(judgement))
+	};
+	if !judged(|j| matches!(j, Judgement::Reasonable | Judgement::KnownGood)) ||
+		judged(|j| matches!(j, Judgement::Erroneous))
+	{
+		return None
+	}
+
+	let mut name = raw_identity_data(registration.info.display)?;
+	if let Some(sub_name) = sub_name {
+		name.push(b'/');
+		name.extend(sub_name);
+	}
+	Some(name)
+}
+
+/// The kinds of calls a proxy may make on behalf of the account it was added for.
+#[derive(
+	Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug, MaxEncodedLen,
+)]
+pub enum ProxyType {
+	/// Every call.
+	Any,
+	/// Every call except those that can move the account's funds or items:
+	/// - balance and asset calls;
+	/// - NFT transfers, transfer approvals, listings and purchases;
+	/// - bridge withdrawals;
+	/// - contract and EVM calls, which can send value;
+	/// - vested transfers;
+	/// - prediction market share transfers, and creating and filling share offers;
+	/// - option transfers, and creating and filling option offers;
+	/// - sudo, which can dispatch any call as root.
+	NonTransfer,
+	/// Sudo calls, the chain's only governance. Useful to keep the sudo key offline.
+	Governance,
+	/// Listing, buying and withdrawing items on the markets.
+	Trading,
+}
+
+impl Default for ProxyType {
+	fn default() -> Self {
+		Self::Any
+	}
+}
+
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			ProxyType::NonTransfer => !matches!(
+				c,
+				Call::Balances(..) |
+					Call::Assets(..) |
+					Call::Bridge(pallet_bridge::Call::withdraw(..)) |
+					Call::Contracts(..) |
+					Call::EVM(..) | Call::Sudo(..) |
+					Call::Nft(pallet_nft::Call::transfer(..)) |
+					Call::Nft(pallet_nft::Call::approve_transfer(..)) |
+					Call::Nft(pallet_nft::Call::list(..)) |
+					Call::Nft(pallet_nft::Call::buy(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::transfer_shares(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::create_offer(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::fill_offer(..)) |
+					Call::Options(pallet_options::Call::transfer(..)) |
+					Call::Options(pallet_options::Call::create_offer(..)) |
+					Call::Options(pallet_options::Call::fill_offer(..)) |
+					Call::Vesting(pallet_vesting::Call::vested_transfer(..))
+			),
+			// Calls batched by `Utility` are checked against the filter again on dispatch.
+			ProxyType::Governance => matches!(c, Call::Sudo(..) | Call::Utility(..)),
+			ProxyType::Trading => matches!(
+				c,
+				Call::Nft(pallet_nft::Call::list(..)) |
+					Call::Nft(pallet_nft::Call::cancel_listing(..)) |
+					Call::Nft(pallet_nft::Call::buy(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::buy_complete_set(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::sell_complete_set(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::create_offer(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::cancel_offer(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::fill_offer(..)) |
+					Call::PredictionMarkets(pallet_prediction_markets::Call::redeem(..)) |
+					Call::Options(pallet_options::Call::write(..)) |
+					Call::Options(pallet_options::Call::close(..)) |
+					Call::Options(pallet_options::Call::create_offer(..)) |
+					Call::Options(pallet_options::Call::cancel_offer(..)) |
+					Call::Options(pallet_options::Call::fill_offer(..)) |
+					Call::Options(pallet_options::Call::exercise(..)) |
+					Call::Options(pallet_options::Call::release(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::open_position(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::increase_position(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::decrease_position(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::close_position(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::add_margin(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::remove_margin(..)) |
+					Call::Perpetuals(pallet_perpetuals::Call::liquidate(..)) |
+					Call::Synths(pallet_synths::Call::mint(..)) |
+					Call::Synths(pallet_synths::Call::burn(..)) |
+					Call::Synths(pallet_synths::Call::exchange(..)) |
+					Call::Utility(..)
+			),
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			_ => false,
+		}
+	}
+}
+
+parameter_types! {
+	// One storage item; key size 32, value size 8.
+	pub const ProxyDepositBase: Balance = deposit(1, 8);
+	// Additional storage item size of 33 bytes.
+	pub const ProxyDepositFactor: Balance = deposit(0, 33);
+	pub const MaxProxies: u32 = 32;
+	// One storage item; key size 32, value size 8.
+	pub const AnnouncementDepositBase: Balance = deposit(1, 8);
+	// Additional storage item size of 68 bytes.
+	pub const AnnouncementDepositFactor: Balance = deposit(0, 68);
+	pub const MaxPending: u32 = 32;
+}
+
+impl pallet_proxy::Config for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+	type WeightInfo = pallet_proxy::weights::SubstrateWeight<Runtime>;
+	type MaxPending = MaxPending;
+	type CallHasher = BlakeTwo256;
+	type AnnouncementDepositBase = AnnouncementDepositBase;
+	type AnnouncementDepositFactor = AnnouncementDepositFactor;
+}
+
+parameter_types! {
+	pub const CollectionDeposit: Balance = 10 * UNIT;
+	pub const ItemDeposit: Balance = deposit(1, 90);
+	pub const MetadataDepositBase: Balance = deposit(1, 16);
+	pub const AttributeDepositBase: Balance = deposit(1, 16);
+	pub const DepositPerByte: Balance = deposit(0, 1);
+	pub const StringLimit: u32 = 128;
+	pub const MaxAttributes: u32 = 32;
+	pub const MaxRoyalty: Percent = Percent::from_percent(50);
+}
+
+impl pallet_nft::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CollectionDeposit = CollectionDeposit;
+	type ItemDeposit = ItemDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type AttributeDepositBase = AttributeDepositBase;
+	type DepositPerByte = DepositPerByte;
+	type StringLimit = StringLimit;
+	type MaxAttributes = MaxAttributes;
+	type MaxRoyalty = MaxRoyalty;
+}
+
+parameter_types! {
+	pub const AssetDeposit: Balance = 100 * UNIT;
+	pub const ApprovalDeposit: Balance = UNIT;
+	pub const AssetsStringLimit: u32 = 50;
+	pub const AssetMetadataDepositBase: Balance = deposit(1, 68);
+	pub const AssetMetadataDepositPerByte: Balance = deposit(0, 1);
+}
+
+impl pallet_assets::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = AssetMetadataDepositBase;
+	type MetadataDepositPerByte = AssetMetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = AssetsStringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const MaxRelayers: u32 = 16;
+	pub const LimitPeriod: BlockNumber = DAYS;
+	pub const ProposalLifetime: BlockNumber = HOURS;
+}
+
+impl pallet_bridge::Config for Runtime {
+	type Event = Event;
+	type Assets = Assets;
+	type BridgeOrigin = EnsureRoot<AccountId>;
+	type MaxRelayers = MaxRelayers;
+	type LimitPeriod = LimitPeriod;
+	type ProposalLifetime = ProposalLifetime;
+}
+
+parameter_types! {
+	pub const PredictionMarketsPalletId: PalletId = PalletId(*b"py/prdmk");
+	pub const MarketDeposit: Balance = 100 * UNIT;
+	pub const DisputeBond: Balance = 500 * UNIT;
+	pub const MaxOutcomes: u16 = 32;
+	pub const ReportPeriod: BlockNumber = DAYS;
+	pub const DisputePeriod: BlockNumber = DAYS;
+	pub const MaxMarketTransitionsPerBlock: u32 = 64;
+	pub const MaxMarketScheduleDelay: u32 = 16;
+}
+
+impl pallet_prediction_markets::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type ResolveOrigin = EnsureRoot<AccountId>;
+	type PalletId = PredictionMarketsPalletId;
+	type Slash = ();
+	type MarketDeposit = MarketDeposit;
+	type DisputeBond = DisputeBond;
+	type MaxOutcomes = MaxOutcomes;
+	type ReportPeriod = ReportPeriod;
+	type DisputePeriod = DisputePeriod;
+	type MaxScheduledPerBlock = MaxMarketTransitionsPerBlock;
+	type MaxScheduleDelay = MaxMarketScheduleDelay;
+}
+
+parameter_types! {
+	pub const MaxFeeders: u32 = 16;
+	pub const MinFeeds: u32 = 3;
+	pub const MaxPriceAge: BlockNumber = HOURS;
+}
+
+impl pallet_price_feed::Config for Runtime {
+	type Event = Event;
+	type AssetId = u32;
+	type FeedersOrigin = EnsureRoot<AccountId>;
+	type MaxFeeders = MaxFeeders;
+	type MinFeeds = MinFeeds;
+	type MaxPriceAge = MaxPriceAge;
+}
+
+parameter_types! {
+	pub const OptionsPalletId: PalletId = PalletId(*b"py/optns");
+	pub const MaxExpiringPerBlock: u32 = 64;
+	pub const MaxOptionsSettlementDelay: u32 = 10 * MINUTES;
+}
+
+impl pallet_options::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Assets = Assets;
+	type Prices = PriceFeed;
+	type SeriesOrigin = EnsureRoot<AccountId>;
+	type PalletId = OptionsPalletId;
+	type MaxExpiringPerBlock = MaxExpiringPerBlock;
+	type MaxSettlementDelay = MaxOptionsSettlementDelay;
+}
+
+parameter_types! {
+	pub const PerpetualsPalletId: PalletId = PalletId(*b"py/perps");
+	pub const MaxMarkets: u32 = 32;
+	pub const FundingPeriod: BlockNumber = HOURS;
+	/// The mark price converges to the index price over a day of funding.
+	pub FundingFactor: Perbill = Perbill::from_rational(1u32, 24);
+	pub const TradeFee: Perbill = Perbill::from_perthousand(1);
+	pub const MaintenanceMargin: Perbill = Perbill::from_percent(5);
+	pub const LiquidationReward: Perbill = Perbill::from_percent(1);
+}
+
+impl pallet_perpetuals::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type AssetId = u32;
+	type Prices = PriceFeed;
+	type MarketOrigin = EnsureRoot<AccountId>;
+	type PalletId = PerpetualsPalletId;
+	type MaxMarkets = MaxMarkets;
+	type FundingPeriod = FundingPeriod;
+	type FundingFactor = FundingFactor;
+	type TradeFee = TradeFee;
+	type MaintenanceMargin = MaintenanceMargin;
+	type LiquidationReward = LiquidationReward;
+}
+
+parameter_types! {
+	pub const MaxSynths: u32 = 16;
+	pub CollateralRatio: FixedU128 = FixedU128::saturating_from_integer(5);
+	pub const ExchangeFee: Perbill = Perbill::from_perthousand(3);
+	pub const SynthsPalletId: PalletId = PalletId(*b"py/synth");
+}
+
+impl pallet_synths::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Assets = Assets;
+	type Prices = PriceFeed;
+	type SynthOrigin = EnsureRoot<AccountId>;
+	type PalletId = SynthsPalletId;
+	type MaxSynths = MaxSynths;
+	type CollateralRatio = CollateralRatio;
+	type ExchangeFee = ExchangeFee;
+}
+
+parameter_types! {
+	pub const MinVestedTransfer: Balance = UNIT;
+}
+
+impl pallet_vesting::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+	/// Holds unclaimed airdrops, to be spent by governance.
+	pub TreasuryAccount: AccountId = TreasuryPalletId::get().into_account();
+	pub const ClaimsPrefix: &'static [u8] = b"Pay DEFI to the account:";
+	/// Enough for airdrops to four billion accounts.
+	pub const MaxProofLength: u32 = 32;
+	pub const ClaimsPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
+impl pallet_claims::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type AirdropOrigin = EnsureRoot<AccountId>;
+	type Treasury = TreasuryAccount;
+	type Prefix = ClaimsPrefix;
+	type MaxProofLength = MaxProofLength;
+	type UnsignedPriority = ClaimsPriority;
+}
+
+parameter_types! {
+	pub TombstoneDeposit: Balance =
+		deposit(1, <pallet_contracts::Pallet<Runtime>>::contract_info_size());
+	pub DepositPerContract: Balance = TombstoneDeposit::get();
+	pub const DepositPerStorageByte: Balance = deposit(0, 1);
+	pub const DepositPerStorageItem: Balance = deposit(1, 0);
+	/// A contract holding exactly its deposit pays it all as rent within 30 days.
+	pub RentFraction: Perbill = Perbill::from_rational(1u32, 30 * DAYS);
+	pub const SurchargeReward: Balance = 150 * MILLIUNIT;
+	pub const SignedClaimHandicap: u32 = 2;
+	/// Lazy deletion of contract storage runs in `on_initialize`, using up to a tenth of a block.
+	pub DeletionWeightLimit: Weight = Perbill::from_percent(10) * BlockWeights::get().max_block;
+	/// Decoding the deletion queue uses at most a fifth of `DeletionWeightLimit`.
+	pub DeletionQueueDepth: u32 = ((DeletionWeightLimit::get() / (
+			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(1) -
+			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(0)
+		)) / 5) as u32;
+	/// Instruction and host function costs, derived from the pallet's benchmarked `WeightInfo` on
+	/// this runtime's database weights. The schedule is part of the runtime code, so costs only
+	/// change with a runtime upgrade.
+	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+impl pallet_contracts::Config for Runtime {
+	type Time = Timestamp;
+	type Randomness = EpochRandomness;
+	type Currency = Balances;
+	type Event = Event;
+	/// Rent is burned.
+	type RentPayment = ();
+	type SignedClaimHandicap = SignedClaimHandicap;
+	type TombstoneDeposit = TombstoneDeposit;
+	type DepositPerContract = DepositPerContract;
+	type DepositPerStorageByte = DepositPerStorageByte;
+	type DepositPerStorageItem = DepositPerStorageItem;
+	type RentFraction = RentFraction;
+	type SurchargeReward = SurchargeReward;
+	/// Gas is paid at the price of weight in substrate transactions.
+	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
+	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+	type ChainExtension = chain_extension::DefiExtension;
+	type DeletionQueueDepth = DeletionQueueDepth;
+	type DeletionWeightLimit = DeletionWeightLimit;
+	type Schedule = Schedule;
+	type CallStack = [pallet_contracts::Frame<Self>; 31];
+}
+
+/// Gas the EVM executes per second of block execution time.
+pub const GAS_PER_SECOND: u64 = 40_000_000;
+
+/// Weight of one unit of gas, so that gas and weight measure the same execution time.
+pub const WEIGHT_PER_GAS: u64 = WEIGHT_PER_SECOND / GAS_PER_SECOND;
+
+/// Converts gas to weight and back at `WEIGHT_PER_GAS`.
+pub struct GasWeightMapping;
+
+impl pallet_evm::GasWeightMapping for GasWeightMapping {
+	fn gas_to_weight(gas: u64) -> Weight {
+		gas.saturating_mul(WEIGHT_PER_GAS)
+	}
+
+	fn weight_to_gas(weight: Weight) -> u64 {
+		weight / WEIGHT_PER_GAS
+	}
+}
+
+/// Charges gas at the price of its weight, so EVM calls cost what substrate calls of the same
+/// weight do.
+pub struct FixedGasPrice;
+
+impl FeeCalculator for FixedGasPrice {
+	fn min_gas_price() -> U256 {
+		U256::from(WEIGHT_PER_GAS)
+	}
+}
+
+/// The block author as an EVM address: the first 20 bytes of its BABE key, after the four bytes
+/// Frontier skips.
+pub struct FindAuthorTruncated<F>(PhantomData<F>);
+
+impl<F: FindAuthor<u32>> FindAuthor<H160> for FindAuthorTruncated<F> {
+	fn find_author<'a, I>(digests: I) -> Option<H160>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		let index = F::find_author(digests)?;
+		let (authority, _) = Babe::authorities().get(index as usize)?.clone();
+		Some(H160::from_slice(&authority.as_ref()[4..24]))
+	}
+}
+
+parameter_types! {
+	/// The EIP-155 chain id wallets sign Ethereum transactions for. It must differ from every
+	/// other network's, so transactions signed here can't be replayed there; check it against
+	/// the registry at https://chainid.network and register it before a public launch.
+	pub const ChainId: u64 = 425_425;
+	/// Gas of the largest EVM transaction that fits the weight limit of a normal extrinsic.
+	pub BlockGasLimit: U256 = {
+		let normal = BlockWeights::get().get(DispatchClass::Normal).max_extrinsic;
+		U256::from(normal.unwrap_or(BlockWeights::get().max_block) / WEIGHT_PER_GAS)
+	};
+}
+
+impl pallet_evm::Config for Runtime {
+	type FeeCalculator = FixedGasPrice;
+	type GasWeightMapping = GasWeightMapping;
+	type BlockHashMapping = pallet_ethereum::EthereumBlockHashMapping<Self>;
+	/// Only the account whose first 20 bytes are the address may call and withdraw as it.
+	type CallOrigin = EnsureAddressTruncated;
+	type WithdrawOrigin = EnsureAddressTruncated;
+	/// EVM addresses hold their balance in the account `blake2_256("evm:" ++ address)`.
+	type AddressMapping = HashedAddressMapping<BlakeTwo256>;
+	type Currency = Balances;
+	type Event = Event;
+	type Runner = pallet_evm::runner::stack::Runner<Self>;
+	type Precompiles = (
+		pallet_evm_precompile_simple::ECRecover,
+		pallet_evm_precompile_simple::Sha256,
+		pallet_evm_precompile_simple::Ripemd160,
+		pallet_evm_precompile_simple::Identity,
+	);
+	type ChainId = ChainId;
+	type BlockGasLimit = BlockGasLimit;
+	type OnChargeTransaction = ();
+	type FindAuthor = FindAuthorTruncated<Babe>;
+}
+
+impl pallet_ethereum::Config for Runtime {
+	type Event = Event;
+	type StateRoot = pallet_ethereum::IntermediateStateRoot;
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
 	type Event = Event;
@@ -278,13 +999,32 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage},
+		Babe: pallet_babe::{Pallet, Call, Storage, Config, ValidateUnsigned},
 		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
-		Aura: pallet_aura::{Pallet, Config<T>},
 		Grandpa: pallet_grandpa::{Pallet, Call, Storage, Config, Event},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
 		Sudo: pallet_sudo::{Pallet, Call, Config<T>, Storage, Event<T>},
+		Multisig: pallet_multisig::{Pallet, Call, Storage, Event<T>},
+		Proxy: pallet_proxy::{Pallet, Call, Storage, Event<T>},
+		Utility: pallet_utility::{Pallet, Call, Event},
+		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Event<T>},
+		Identity: pallet_identity::{Pallet, Call, Storage, Event<T>},
+		Nft: pallet_nft::{Pallet, Call, Storage, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+		Bridge: pallet_bridge::{Pallet, Call, Storage, Event<T>},
+		Contracts: pallet_contracts::{Pallet, Call, Storage, Event<T>},
+		// Frontier's RPC reads the storage of these two pallets under these names.
+		EVM: pallet_evm::{Pallet, Config, Call, Storage, Event<T>},
+		Ethereum: pallet_ethereum::{Pallet, Call, Storage, Event, Config, ValidateUnsigned},
+		PredictionMarkets: pallet_prediction_markets::{Pallet, Call, Storage, Event<T>},
+		PriceFeed: pallet_price_feed::{Pallet, Call, Storage, Event<T>},
+		Options: pallet_options::{Pallet, Call, Storage, Event<T>},
+		Perpetuals: pallet_perpetuals::{Pallet, Call, Storage, Event<T>},
+		Synths: pallet_synths::{Pallet, Call, Storage, Event<T>},
+		Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Claims: pallet_claims::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
+		MultisigIndex: pallet_multisig_index::{Pallet, Storage},
 		// Include the custom logic from the pallet-template in the runtime.
 		TemplateModule: pallet_template::{Pallet, Call, Storage, Event<T>},
 	}
@@ -305,6 +1045,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_multisig_index::IndexMultisigs<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -317,6 +1058,23 @@ pub type Executive = frame_executive::Executive<
 	AllPallets,
 >;
 
+/// Wraps Ethereum transactions submitted through `eth_sendRawTransaction` into extrinsics.
+pub struct TransactionConverter;
+
+impl fp_rpc::ConvertTransaction<UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: EthereumTransaction) -> UncheckedExtrinsic {
+		UncheckedExtrinsic::new_unsigned(transact(transaction).into())
+	}
+}
+
+impl fp_rpc::ConvertTransaction<opaque::UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: EthereumTransaction) -> opaque::UncheckedExtrinsic {
+		let extrinsic = UncheckedExtrinsic::new_unsigned(transact(transaction).into());
+		opaque::UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..])
+			.expect("Encoded extrinsic is always valid; qed")
+	}
+}
+
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -374,13 +1132,42 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
-		fn slot_duration() -> sp_consensus_aura::SlotDuration {
-			sp_consensus_aura::SlotDuration::from_millis(Aura::slot_duration())
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeGenesisConfiguration {
+			sp_consensus_babe::BabeGenesisConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: BABE_GENESIS_EPOCH_CONFIG.c,
+				genesis_authorities: Babe::authorities(),
+				randomness: Babe::randomness(),
+				allowed_slots: BABE_GENESIS_EPOCH_CONFIG.allowed_slots,
+			}
+		}
+
+		fn current_epoch_start() -> sp_consensus_babe::Slot {
+			Babe::current_epoch_start()
+		}
+
+		fn current_epoch() -> sp_consensus_babe::Epoch {
+			Babe::current_epoch()
+		}
+
+		fn next_epoch() -> sp_consensus_babe::Epoch {
+			Babe::next_epoch()
 		}
 
-		fn authorities() -> Vec<AuraId> {
-			Aura::authorities()
+		fn generate_key_ownership_proof(
+			_slot: sp_consensus_babe::Slot,
+			_authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			_key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
 		}
 	}
 
@@ -443,6 +1230,188 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_template_runtime_api::TemplateApi<Block> for Runtime {
+		fn get_something() -> Option<u32> {
+			TemplateModule::something()
+		}
+	}
+
+	impl identity_runtime_api::IdentityApi<Block, AccountId> for Runtime {
+		fn verified_display_name(who: AccountId) -> Option<Vec<u8>> {
+			verified_display_name(who)
+		}
+	}
+
+	impl multisig_runtime_api::MultisigApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn pending_operations(
+			signatory: AccountId,
+		) -> Vec<multisig_runtime_api::PendingOperation<AccountId, Balance, BlockNumber>> {
+			MultisigIndex::pending_operations(&signatory)
+				.into_iter()
+				.map(|(multisig, call_hash, operation)| multisig_runtime_api::PendingOperation {
+					multisig,
+					call_hash,
+					height: operation.when.height,
+					index: operation.when.index,
+					deposit: operation.deposit,
+					depositor: operation.depositor,
+					approvals: operation.approvals,
+				})
+				.collect()
+		}
+	}
+
+	impl fp_rpc::EthereumRuntimeRPCApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			ChainId::get()
+		}
+
+		fn account_basic(address: H160) -> EVMAccount {
+			EVM::account_basic(&address)
+		}
+
+		fn gas_price() -> U256 {
+			FixedGasPrice::min_gas_price()
+		}
+
+		fn account_code_at(address: H160) -> Vec<u8> {
+			EVM::account_codes(address)
+		}
+
+		fn author() -> H160 {
+			EVM::find_author()
+		}
+
+		fn storage_at(address: H160, index: U256) -> H256 {
+			let mut key = [0u8; 32];
+			index.to_big_endian(&mut key);
+			EVM::account_storages(address, H256::from(key))
+		}
+
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<pallet_evm::CallInfo, sp_runtime::DispatchError> {
+			let mut config = <Runtime as pallet_evm::Config>::config().clone();
+			config.estimate = estimate;
+
+			<Runtime as pallet_evm::Config>::Runner::call(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.low_u64(),
+				gas_price,
+				nonce,
+				&config,
+			)
+			.map_err(|err| err.into())
+		}
+
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<pallet_evm::CreateInfo, sp_runtime::DispatchError> {
+			let mut config = <Runtime as pallet_evm::Config>::config().clone();
+			config.estimate = estimate;
+
+			<Runtime as pallet_evm::Config>::Runner::create(
+				from,
+				data,
+				value,
+				gas_limit.low_u64(),
+				gas_price,
+				nonce,
+				&config,
+			)
+			.map_err(|err| err.into())
+		}
+
+		fn current_transaction_statuses() -> Option<Vec<TransactionStatus>> {
+			Ethereum::current_transaction_statuses()
+		}
+
+		fn current_block() -> Option<pallet_ethereum::Block> {
+			Ethereum::current_block()
+		}
+
+		fn current_receipts() -> Option<Vec<pallet_ethereum::Receipt>> {
+			Ethereum::current_receipts()
+		}
+
+		fn current_all() -> (
+			Option<pallet_ethereum::Block>,
+			Option<Vec<pallet_ethereum::Receipt>>,
+			Option<Vec<TransactionStatus>>,
+		) {
+			(
+				Ethereum::current_block(),
+				Ethereum::current_receipts(),
+				Ethereum::current_transaction_statuses(),
+			)
+		}
+
+		fn extrinsic_filter(
+			xts: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<EthereumTransaction> {
+			xts.into_iter()
+				.filter_map(|xt| match xt.function {
+					Call::Ethereum(transact(transaction)) => Some(transaction),
+					_ => None,
+				})
+				.collect()
+		}
+	}
+
+	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash>
+		for Runtime
+	{
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractExecResult {
+			Contracts::bare_call(origin, dest, value, gas_limit, input_data, true)
+		}
+
+		fn instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code: pallet_contracts_primitives::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractInstantiateResult<AccountId, BlockNumber> {
+			Contracts::bare_instantiate(origin, endowment, gas_limit, code, data, salt, true, true)
+		}
+
+		fn get_storage(
+			address: AccountId,
+			key: [u8; 32],
+		) -> pallet_contracts_primitives::GetStorageResult {
+			Contracts::get_storage(address, key)
+		}
+
+		fn rent_projection(
+			address: AccountId,
+		) -> pallet_contracts_primitives::RentProjectionResult<BlockNumber> {
+			Contracts::rent_projection(address)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -470,7 +1439,14 @@ impl_runtime_apis! {
 			let params = (&config, &whitelist);
 
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
+			add_benchmark!(params, batches, pallet_assets, Assets);
 			add_benchmark!(params, batches, pallet_balances, Balances);
+			add_benchmark!(params, batches, pallet_contracts, Contracts);
+			add_benchmark!(params, batches, pallet_identity, Identity);
+			add_benchmark!(params, batches, pallet_multisig, Multisig);
+			add_benchmark!(params, batches, pallet_proxy, Proxy);
+			add_benchmark!(params, batches, pallet_scheduler, Scheduler);
+			add_benchmark!(params, batches, pallet_utility, Utility);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
 			add_benchmark!(params, batches, pallet_template, TemplateModule);
 
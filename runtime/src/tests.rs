@@ -0,0 +1,524 @@
+use crate::{
+	chain_extension::{self, SUCCESS, TRANSFER_FAILED},
+	verified_display_name, AccountId, Assets, Balance, BlockGasLimit, BlockWeights, Call,
+	ClaimsPrefix, EpochDuration, ExpectedBlockTime, GasWeightMapping, Identity, MinimumPeriod,
+	MultisigCalls, MultisigIndex, Origin, ProxyType, Runtime, System, Utility, SLOT_DURATION,
+	TOKEN_SYMBOL, UNIT, WEIGHT_PER_GAS,
+};
+use codec::Encode;
+use frame_support::{assert_ok, sp_io, traits::InstanceFilter, weights::DispatchClass};
+use pallet_evm::GasWeightMapping as _;
+use pallet_identity::{Data, IdentityInfo, Judgement};
+use pallet_multisig_index::FindMultisigCalls;
+use sp_core::H160;
+use sp_runtime::MultiAddress;
+use std::convert::TryInto;
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark(Vec::new()))
+}
+
+fn transfer() -> Call {
+	Call::Balances(pallet_balances::Call::transfer(
+		MultiAddress::Id(AccountId::from([1; 32])),
+		UNIT,
+	))
+}
+
+fn sudo() -> Call {
+	Call::Sudo(pallet_sudo::Call::sudo(Box::new(remark())))
+}
+
+fn template() -> Call {
+	Call::TemplateModule(pallet_template::Call::do_something(42))
+}
+
+fn remove_proxies() -> Call {
+	Call::Proxy(pallet_proxy::Call::remove_proxies())
+}
+
+fn nft_transfer() -> Call {
+	Call::Nft(pallet_nft::Call::transfer(0, 0, MultiAddress::Id(AccountId::from([1; 32]))))
+}
+
+fn nft_list() -> Call {
+	Call::Nft(pallet_nft::Call::list(0, 0, UNIT))
+}
+
+fn nft_buy() -> Call {
+	Call::Nft(pallet_nft::Call::buy(0, 0, UNIT))
+}
+
+fn nft_cancel_listing() -> Call {
+	Call::Nft(pallet_nft::Call::cancel_listing(0, 0))
+}
+
+fn nft_set_metadata() -> Call {
+	Call::Nft(pallet_nft::Call::set_metadata(0, 0, Vec::new()))
+}
+
+fn asset_transfer() -> Call {
+	Call::Assets(pallet_assets::Call::transfer(0, MultiAddress::Id(AccountId::from([1; 32])), UNIT))
+}
+
+fn bridge_withdraw() -> Call {
+	Call::Bridge(pallet_bridge::Call::withdraw([0; 20], UNIT, [1; 20]))
+}
+
+fn evm_withdraw() -> Call {
+	Call::EVM(pallet_evm::Call::withdraw(H160::repeat_byte(1), UNIT))
+}
+
+fn market_buy_complete_set() -> Call {
+	Call::PredictionMarkets(pallet_prediction_markets::Call::buy_complete_set(0, UNIT))
+}
+
+fn market_transfer_shares() -> Call {
+	Call::PredictionMarkets(pallet_prediction_markets::Call::transfer_shares(
+		0,
+		0,
+		MultiAddress::Id(AccountId::from([1; 32])),
+		UNIT,
+	))
+}
+
+fn market_fill_offer() -> Call {
+	Call::PredictionMarkets(pallet_prediction_markets::Call::fill_offer(0, UNIT))
+}
+
+fn market_report() -> Call {
+	Call::PredictionMarkets(pallet_prediction_markets::Call::report(0, 0))
+}
+
+fn options_write() -> Call {
+	Call::Options(pallet_options::Call::write(0, UNIT))
+}
+
+fn options_transfer() -> Call {
+	Call::Options(pallet_options::Call::transfer(
+		0,
+		MultiAddress::Id(AccountId::from([1; 32])),
+		UNIT,
+	))
+}
+
+fn options_exercise() -> Call {
+	Call::Options(pallet_options::Call::exercise(0, UNIT))
+}
+
+fn feed_price() -> Call {
+	Call::PriceFeed(pallet_price_feed::Call::feed(0, Default::default()))
+}
+
+fn perp_open() -> Call {
+	Call::Perpetuals(pallet_perpetuals::Call::open_position(
+		0,
+		pallet_perpetuals::Side::Long,
+		UNIT,
+		UNIT,
+	))
+}
+
+fn perp_fund_insurance() -> Call {
+	Call::Perpetuals(pallet_perpetuals::Call::fund_insurance(UNIT))
+}
+
+fn synths_exchange() -> Call {
+	Call::Synths(pallet_synths::Call::exchange(0, 1, UNIT))
+}
+
+fn synths_stake() -> Call {
+	Call::Synths(pallet_synths::Call::stake(UNIT))
+}
+
+fn vest() -> Call {
+	Call::Vesting(pallet_vesting::Call::vest())
+}
+
+fn vested_transfer() -> Call {
+	Call::Vesting(pallet_vesting::Call::vested_transfer(
+		MultiAddress::Id(AccountId::from([1; 32])),
+		pallet_vesting::VestingInfo { locked: UNIT, per_block: UNIT / 10, starting_block: 0 },
+	))
+}
+
+fn batch() -> Call {
+	Call::Utility(pallet_utility::Call::batch(vec![remark(), transfer()]))
+}
+
+const ALL: [ProxyType; 4] =
+	[ProxyType::Any, ProxyType::NonTransfer, ProxyType::Governance, ProxyType::Trading];
+
+#[test]
+fn any_allows_every_call() {
+	for call in [remark(), transfer(), sudo(), template(), remove_proxies(), batch()].iter() {
+		assert!(ProxyType::Any.filter(call));
+	}
+	for call in [nft_transfer(), nft_list(), nft_buy(), nft_cancel_listing()].iter() {
+		assert!(ProxyType::Any.filter(call));
+	}
+}
+
+#[test]
+fn non_transfer_rejects_calls_moving_funds() {
+	assert!(!ProxyType::NonTransfer.filter(&transfer()));
+	assert!(!ProxyType::NonTransfer.filter(&sudo()));
+	assert!(!ProxyType::NonTransfer.filter(&nft_transfer()));
+	assert!(!ProxyType::NonTransfer.filter(&nft_list()));
+	assert!(!ProxyType::NonTransfer.filter(&nft_buy()));
+	assert!(!ProxyType::NonTransfer.filter(&asset_transfer()));
+	assert!(!ProxyType::NonTransfer.filter(&bridge_withdraw()));
+	assert!(!ProxyType::NonTransfer.filter(&evm_withdraw()));
+	assert!(!ProxyType::NonTransfer.filter(&market_transfer_shares()));
+	assert!(!ProxyType::NonTransfer.filter(&market_fill_offer()));
+	assert!(!ProxyType::NonTransfer.filter(&options_transfer()));
+	assert!(!ProxyType::NonTransfer.filter(&vested_transfer()));
+
+	assert!(ProxyType::NonTransfer.filter(&remark()));
+	assert!(ProxyType::NonTransfer.filter(&template()));
+	assert!(ProxyType::NonTransfer.filter(&remove_proxies()));
+	assert!(ProxyType::NonTransfer.filter(&batch()));
+	assert!(ProxyType::NonTransfer.filter(&nft_cancel_listing()));
+	assert!(ProxyType::NonTransfer.filter(&nft_set_metadata()));
+	assert!(ProxyType::NonTransfer.filter(&market_buy_complete_set()));
+	assert!(ProxyType::NonTransfer.filter(&market_report()));
+	assert!(ProxyType::NonTransfer.filter(&options_write()));
+	assert!(ProxyType::NonTransfer.filter(&options_exercise()));
+	assert!(ProxyType::NonTransfer.filter(&perp_open()));
+	assert!(ProxyType::NonTransfer.filter(&synths_exchange()));
+	assert!(ProxyType::NonTransfer.filter(&vest()));
+}
+
+#[test]
+fn governance_allows_only_sudo() {
+	assert!(ProxyType::Governance.filter(&sudo()));
+	assert!(ProxyType::Governance.filter(&batch()));
+
+	assert!(!ProxyType::Governance.filter(&remark()));
+	assert!(!ProxyType::Governance.filter(&transfer()));
+	assert!(!ProxyType::Governance.filter(&template()));
+	assert!(!ProxyType::Governance.filter(&remove_proxies()));
+	assert!(!ProxyType::Governance.filter(&nft_buy()));
+}
+
+#[test]
+fn trading_allows_only_market_calls() {
+	for call in [nft_list(), nft_buy(), nft_cancel_listing(), batch()].iter() {
+		assert!(ProxyType::Trading.filter(call));
+	}
+	assert!(ProxyType::Trading.filter(&market_buy_complete_set()));
+	assert!(ProxyType::Trading.filter(&market_fill_offer()));
+	assert!(ProxyType::Trading.filter(&options_write()));
+	assert!(ProxyType::Trading.filter(&options_exercise()));
+	assert!(ProxyType::Trading.filter(&perp_open()));
+	assert!(ProxyType::Trading.filter(&synths_exchange()));
+
+	for call in [remark(), transfer(), sudo(), template(), remove_proxies()].iter() {
+		assert!(!ProxyType::Trading.filter(call));
+	}
+	assert!(!ProxyType::Trading.filter(&nft_transfer()));
+	assert!(!ProxyType::Trading.filter(&asset_transfer()));
+	assert!(!ProxyType::Trading.filter(&bridge_withdraw()));
+	assert!(!ProxyType::Trading.filter(&nft_set_metadata()));
+	assert!(!ProxyType::Trading.filter(&market_transfer_shares()));
+	assert!(!ProxyType::Trading.filter(&market_report()));
+	assert!(!ProxyType::Trading.filter(&options_transfer()));
+	assert!(!ProxyType::Trading.filter(&feed_price()));
+	assert!(!ProxyType::Trading.filter(&perp_fund_insurance()));
+	assert!(!ProxyType::Trading.filter(&synths_stake()));
+}
+
+#[test]
+fn any_is_superset_of_every_type() {
+	for proxy_type in ALL.iter() {
+		assert!(ProxyType::Any.is_superset(proxy_type));
+		assert!(proxy_type.is_superset(proxy_type));
+	}
+}
+
+#[test]
+fn restricted_types_are_not_supersets_of_broader_ones() {
+	assert!(!ProxyType::NonTransfer.is_superset(&ProxyType::Trading));
+	assert!(!ProxyType::NonTransfer.is_superset(&ProxyType::Any));
+	assert!(!ProxyType::NonTransfer.is_superset(&ProxyType::Governance));
+
+	assert!(!ProxyType::Governance.is_superset(&ProxyType::Any));
+	assert!(!ProxyType::Governance.is_superset(&ProxyType::NonTransfer));
+	assert!(!ProxyType::Governance.is_superset(&ProxyType::Trading));
+
+	assert!(!ProxyType::Trading.is_superset(&ProxyType::Any));
+	assert!(!ProxyType::Trading.is_superset(&ProxyType::NonTransfer));
+	assert!(!ProxyType::Trading.is_superset(&ProxyType::Governance));
+}
+
+#[test]
+fn default_is_any() {
+	assert_eq!(ProxyType::default(), ProxyType::Any);
+}
+
+#[test]
+fn gas_converts_to_weight_and_back() {
+	assert_eq!(GasWeightMapping::gas_to_weight(21_000), 21_000 * WEIGHT_PER_GAS);
+	assert_eq!(GasWeightMapping::weight_to_gas(21_000 * WEIGHT_PER_GAS), 21_000);
+	assert_eq!(GasWeightMapping::gas_to_weight(u64::MAX), u64::MAX);
+}
+
+#[test]
+fn block_gas_limit_fits_a_normal_extrinsic() {
+	let weights = BlockWeights::get();
+	let max_extrinsic = weights.get(DispatchClass::Normal).max_extrinsic.unwrap();
+	let gas_limit = BlockGasLimit::get().low_u64();
+
+	assert!(GasWeightMapping::gas_to_weight(gas_limit) <= max_extrinsic);
+	assert!(GasWeightMapping::gas_to_weight(gas_limit + 1) > max_extrinsic);
+	// Room for contract deployments of several million gas.
+	assert!(gas_limit > 15_000_000);
+}
+
+#[test]
+fn claim_statements_name_the_token() {
+	let prefix = String::from_utf8(ClaimsPrefix::get().to_vec()).unwrap();
+	assert_eq!(prefix, format!("Pay {} to the account:", TOKEN_SYMBOL));
+}
+
+fn account(byte: u8) -> AccountId {
+	AccountId::from([byte; 32])
+}
+
+fn approve_as_multi(other_signatories: Vec<AccountId>) -> Call {
+	Call::Multisig(pallet_multisig::Call::approve_as_multi(2, other_signatories, None, [0; 32], 0))
+}
+
+/// The accounts the multisig calls `call` makes are dispatched from.
+fn multisig_callers(who: u8, call: &Call) -> Vec<AccountId> {
+	MultisigCalls::find(&account(who), call)
+		.into_iter()
+		.map(|call| call.signatory)
+		.collect()
+}
+
+#[test]
+fn multisig_calls_are_found_in_batches_proxies_and_sudo() {
+	let call = Call::Utility(pallet_utility::Call::batch(vec![
+		remark(),
+		approve_as_multi(vec![account(2)]),
+		Call::Proxy(pallet_proxy::Call::proxy(
+			account(3),
+			None,
+			Box::new(approve_as_multi(vec![account(2)])),
+		)),
+		Call::Utility(pallet_utility::Call::as_derivative(
+			0,
+			Box::new(approve_as_multi(vec![account(2)])),
+		)),
+		Call::Sudo(pallet_sudo::Call::sudo_as(
+			MultiAddress::Id(account(4)),
+			Box::new(approve_as_multi(vec![account(2)])),
+		)),
+	]));
+
+	assert_eq!(
+		multisig_callers(1, &call),
+		vec![account(1), account(3), Utility::derivative_account_id(account(1), 0), account(4)]
+	);
+	assert!(multisig_callers(1, &batch()).is_empty());
+}
+
+#[test]
+fn multisig_calls_of_operations_are_found_from_the_multisig_account() {
+	let call = Call::Multisig(pallet_multisig::Call::as_multi(
+		2,
+		vec![account(2)],
+		None,
+		approve_as_multi(vec![account(3)]).encode(),
+		false,
+		0,
+	));
+
+	let found = MultisigCalls::find(&account(1), &call);
+	let multisig = MultisigIndex::multisig_account(&found[0]);
+	assert_eq!(found[0].other_signatories, vec![account(2)]);
+	assert_eq!(multisig_callers(1, &call), vec![account(1), multisig]);
+}
+
+#[test]
+fn multisig_calls_are_only_searched_to_a_depth() {
+	let mut call = approve_as_multi(vec![account(2)]);
+	for _ in 0..8 {
+		call = Call::Utility(pallet_utility::Call::batch(vec![call]));
+	}
+	assert!(multisig_callers(1, &call).is_empty());
+
+	let call = Call::Utility(pallet_utility::Call::batch(vec![approve_as_multi(vec![account(2)])]));
+	assert_eq!(multisig_callers(1, &call), vec![account(1)]);
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: (1..=4).map(|byte| (account(byte), 1_000 * UNIT)).collect(),
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn raw(name: &[u8]) -> Data {
+	Data::Raw(name.to_vec().try_into().unwrap())
+}
+
+/// Give account 1 the display name `display`, and have a new registrar provide each of
+/// `judgements`.
+fn set_identity(display: Data, judgements: &[Judgement<Balance>]) {
+	let info = IdentityInfo {
+		additional: Default::default(),
+		display,
+		legal: Data::None,
+		web: Data::None,
+		riot: Data::None,
+		email: Data::None,
+		pgp_fingerprint: None,
+		image: Data::None,
+		twitter: Data::None,
+	};
+	assert_ok!(Identity::set_identity(Origin::signed(account(1)), info));
+	for (index, judgement) in judgements.iter().enumerate() {
+		let registrar = account(2 + index as u8);
+		assert_ok!(Identity::add_registrar(Origin::root(), registrar.clone()));
+		assert_ok!(Identity::provide_judgement(
+			Origin::signed(registrar),
+			index as u32,
+			MultiAddress::Id(account(1)),
+			*judgement,
+		));
+	}
+}
+
+#[test]
+fn display_names_are_verified_by_reasonable_or_known_good_judgements() {
+	let cases: &[(&[Judgement<Balance>], bool)] = &[
+		(&[], false),
+		(&[Judgement::Reasonable], true),
+		(&[Judgement::KnownGood], true),
+		(&[Judgement::Unknown], false),
+		(&[Judgement::LowQuality], false),
+		(&[Judgement::OutOfDate, Judgement::KnownGood], true),
+	];
+	for (judgements, verified) in cases {
+		new_test_ext().execute_with(|| {
+			set_identity(raw(b"Alice"), judgements);
+
+			let expected = if *verified { Some(b"Alice".to_vec()) } else { None };
+			assert_eq!(verified_display_name(account(1)), expected, "{:?}", judgements);
+		});
+	}
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(verified_display_name(account(1)), None);
+	});
+}
+
+#[test]
+fn erroneous_judgements_override_others() {
+	for judgements in [
+		[Judgement::Reasonable, Judgement::Erroneous],
+		[Judgement::Erroneous, Judgement::KnownGood],
+	] {
+		new_test_ext().execute_with(|| {
+			set_identity(raw(b"Alice"), &judgements);
+
+			assert_eq!(verified_display_name(account(1)), None);
+		});
+	}
+}
+
+#[test]
+fn display_names_not_stored_in_the_clear_are_not_returned() {
+	for display in [Data::None, Data::BlakeTwo256([1; 32]), Data::Sha256([1; 32])] {
+		new_test_ext().execute_with(|| {
+			set_identity(display, &[Judgement::KnownGood]);
+
+			assert_eq!(verified_display_name(account(1)), None);
+		});
+	}
+}
+
+#[test]
+fn sub_accounts_are_named_after_their_verified_parent() {
+	let subs = || vec![(account(5), raw(b"bot")), (account(6), Data::Keccak256([1; 32]))];
+
+	new_test_ext().execute_with(|| {
+		set_identity(raw(b"Alice"), &[Judgement::Reasonable]);
+		assert_ok!(Identity::set_subs(Origin::signed(account(1)), subs()));
+
+		assert_eq!(verified_display_name(account(5)), Some(b"Alice/bot".to_vec()));
+		// A sub-account named by a hash goes by its parent's name.
+		assert_eq!(verified_display_name(account(6)), Some(b"Alice".to_vec()));
+	});
+
+	new_test_ext().execute_with(|| {
+		set_identity(raw(b"Alice"), &[Judgement::LowQuality]);
+		assert_ok!(Identity::set_subs(Origin::signed(account(1)), subs()));
+
+		assert_eq!(verified_display_name(account(5)), None);
+	});
+}
+
+#[test]
+fn babe_timing_follows_the_genesis_slot_duration() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(ExpectedBlockTime::get(), SLOT_DURATION);
+		assert_eq!(EpochDuration::get(), 100);
+
+		MinimumPeriod::set(&1_000);
+		assert_eq!(ExpectedBlockTime::get(), 2_000);
+		assert_eq!(EpochDuration::get(), 300);
+	});
+}
+
+/// Asset 0, held by 1 as the contract's balance.
+fn new_asset() {
+	assert_ok!(Assets::force_create(Origin::root(), 0, MultiAddress::Id(account(1)), true, 1));
+	assert_ok!(Assets::mint(Origin::signed(account(1)), 0, MultiAddress::Id(account(1)), 100));
+}
+
+#[test]
+fn chain_extension_reads_balances() {
+	new_test_ext().execute_with(|| {
+		new_asset();
+
+		assert_eq!(chain_extension::balance(&account(1)), 1_000 * UNIT);
+		assert_eq!(chain_extension::asset_balance(0, account(1)), 100);
+		assert_eq!(chain_extension::asset_balance(0, account(2)), 0);
+		assert_eq!(chain_extension::asset_balance(1, account(1)), 0);
+	});
+}
+
+#[test]
+fn chain_extension_transfers_assets() {
+	new_test_ext().execute_with(|| {
+		new_asset();
+
+		assert_eq!(chain_extension::asset_transfer(account(1), 0, account(2), 30), SUCCESS);
+		assert_eq!(Assets::balance(0, account(1)), 70);
+		assert_eq!(Assets::balance(0, account(2)), 30);
+	});
+}
+
+#[test]
+fn failed_chain_extension_transfers_leave_no_changes() {
+	new_test_ext().execute_with(|| {
+		new_asset();
+		let root = sp_io::storage::root();
+
+		assert_eq!(
+			chain_extension::asset_transfer(account(1), 0, account(2), 101),
+			TRANSFER_FAILED
+		);
+		assert_eq!(chain_extension::asset_transfer(account(1), 1, account(2), 10), TRANSFER_FAILED);
+		assert_eq!(sp_io::storage::root(), root);
+	});
+}
@@ -0,0 +1,359 @@
+//! The node side: a keystore forwarding every request to a signing service.
+
+use std::{
+	convert::TryFrom,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use jsonrpc_core::{Id, MethodCall, Output, Params, Version};
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519, Bytes,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+	CryptoStore, Error, SyncCryptoStore,
+};
+
+use crate::{key_type_name, Crypto, PublicKey, VrfSignature, VrfTranscript, VrfTranscriptValue};
+
+/// Time allowed to connect to the signing service.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time allowed for a whole request, after which the service is treated as unavailable, so one
+/// that hangs can't stall block authoring and finality.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A keystore whose keys live in a signing service implementing [`crate::SignerApi`].
+///
+/// The [`SyncCryptoStore`] methods send requests synchronously. The async [`CryptoStore`] methods
+/// send them from Tokio's blocking thread pool, so waiting for the service doesn't block the
+/// executor.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+	url: String,
+	authorization: Option<String>,
+	agent: ureq::Agent,
+	next_id: Arc<AtomicU64>,
+}
+
+impl RemoteKeystore {
+	/// Connect to the signing service at `url`, e.g. `http://10.0.0.2:9955`, and check that it
+	/// answers. Requests carry `token` as a bearer token, if given.
+	pub fn open(url: &str, token: Option<&str>) -> Result<Self, Error> {
+		let agent = ureq::AgentBuilder::new()
+			.timeout_connect(CONNECT_TIMEOUT)
+			.timeout(REQUEST_TIMEOUT)
+			.build();
+		let keystore = Self {
+			url: url.into(),
+			authorization: token.map(|token| format!("Bearer {}", token)),
+			agent,
+			next_id: Default::default(),
+		};
+		keystore.call::<bool>("signer_hasKeys", json!([[]]))?;
+		Ok(keystore)
+	}
+
+	/// Run `request` on the blocking thread pool.
+	async fn spawn_blocking<R, F>(&self, request: F) -> R
+	where
+		R: Send + 'static,
+		F: FnOnce(&Self) -> R + Send + 'static,
+	{
+		let keystore = self.clone();
+		tokio::task::spawn_blocking(move || request(&keystore))
+			.await
+			.expect("Keystore requests don't panic and are never cancelled; qed")
+	}
+
+	fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R, Error> {
+		let params = match params {
+			Value::Array(params) => Params::Array(params),
+			_ => unreachable!("All requests are built with positional parameters; qed"),
+		};
+		let request = MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params,
+			id: Id::Num(self.next_id.fetch_add(1, Ordering::Relaxed)),
+		};
+
+		let mut http_request = self.agent.post(&self.url);
+		if let Some(authorization) = &self.authorization {
+			http_request = http_request.set("Authorization", authorization);
+		}
+		let response: Output = http_request
+			.send_json(serde_json::to_value(request).map_err(|e| Error::Other(e.to_string()))?)
+			.map_err(|e| {
+				log::warn!(target: "remote-keystore", "`{}` request failed: {}", method, e);
+				Error::Unavailable
+			})?
+			.into_json()
+			.map_err(|e| Error::Other(e.to_string()))?;
+
+		match response {
+			Output::Success(success) =>
+				serde_json::from_value(success.result).map_err(|e| Error::Other(e.to_string())),
+			Output::Failure(failure) => Err(Error::Other(match failure.error.data {
+				Some(data) => format!("{}: {}", failure.error.message, data),
+				None => failure.error.message,
+			})),
+		}
+	}
+
+	fn public_keys<T: for<'a> TryFrom<&'a [u8]>>(&self, id: KeyTypeId, crypto: Crypto) -> Vec<T> {
+		match self.call::<Vec<PublicKey>>("signer_keys", json!([key_type_name(id)])) {
+			Ok(keys) => keys
+				.into_iter()
+				.filter(|key| key.crypto == crypto)
+				.filter_map(|key| T::try_from(&key.public[..]).ok())
+				.collect(),
+			Err(e) => {
+				log::warn!(target: "remote-keystore", "Failed to list {:?} keys: {}", id, e);
+				Vec::new()
+			},
+		}
+	}
+
+	fn generate_new<T: for<'a> TryFrom<&'a [u8]>>(
+		&self,
+		id: KeyTypeId,
+		crypto: Crypto,
+		seed: Option<&str>,
+	) -> Result<T, Error> {
+		let public: Bytes =
+			self.call("signer_generate", json!([key_type_name(id), crypto, seed]))?;
+		T::try_from(&public[..]).map_err(|_| Error::ValidationError("Invalid public key.".into()))
+	}
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, Crypto::Sr25519)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		self.generate_new(id, Crypto::Sr25519, seed)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, Crypto::Ed25519)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		self.generate_new(id, Crypto::Ed25519, seed)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, Crypto::Ecdsa)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		self.generate_new(id, Crypto::Ecdsa, seed)
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call::<()>("signer_insert", json!([key_type_name(id), suri, Bytes(public.to_vec())]))
+			.map_err(|e| log::warn!(target: "remote-keystore", "Failed to insert key: {}", e))
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let held = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| held.contains(key)).collect())
+	}
+
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let keys: Vec<PublicKey> = self.call("signer_keys", json!([key_type_name(id)]))?;
+		Ok(keys.into_iter().map(Into::into).collect())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let keys = public_keys
+			.iter()
+			.map(|(public, id)| (Bytes(public.clone()), key_type_name(*id)))
+			.collect::<Vec<_>>();
+
+		self.call("signer_hasKeys", json!([keys])).unwrap_or_else(|e| {
+			log::warn!(target: "remote-keystore", "Failed to look up keys: {}", e);
+			false
+		})
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let key = PublicKey::try_from(key.clone()).map_err(|_| Error::KeyNotSupported(id))?;
+		let signature: Option<Bytes> =
+			self.call("signer_sign", json!([key_type_name(id), key, Bytes(msg.to_vec())]))?;
+
+		Ok(signature.map(|signature| signature.0))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		let transcript = VrfTranscript {
+			label: transcript_data.label.to_vec().into(),
+			items: transcript_data
+				.items
+				.into_iter()
+				.map(|(name, value)| {
+					let value = match value {
+						VRFTranscriptValue::Bytes(bytes) => VrfTranscriptValue::Bytes(bytes.into()),
+						VRFTranscriptValue::U64(number) => VrfTranscriptValue::U64(number),
+					};
+					(name.into(), value)
+				})
+				.collect(),
+		};
+		let signature: Option<VrfSignature> = self.call(
+			"signer_vrfSign",
+			json!([key_type_name(key_type), Bytes(public.0.to_vec()), transcript]),
+		)?;
+
+		signature
+			.map(|signature| {
+				let invalid = |_| Error::ValidationError("Invalid VRF signature.".into());
+				Ok(VRFSignature {
+					output: VRFOutput::from_bytes(&signature.output).map_err(invalid)?,
+					proof: VRFProof::from_bytes(&signature.proof).map_err(invalid)?,
+				})
+			})
+			.transpose()
+	}
+}
+
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::sr25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::sr25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::ed25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::ed25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::ecdsa_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::ecdsa_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let (suri, public) = (suri.to_owned(), public.to_vec());
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::insert_unknown(keystore, id, &suri, &public)
+		})
+		.await
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::supported_keys(keystore, id, keys))
+			.await
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.spawn_blocking(move |keystore| SyncCryptoStore::keys(keystore, id)).await
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys.to_vec();
+		self.spawn_blocking(move |keystore| SyncCryptoStore::has_keys(keystore, &public_keys))
+			.await
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let (key, msg) = (key.clone(), msg.to_vec());
+		self.spawn_blocking(move |keystore| SyncCryptoStore::sign_with(keystore, id, &key, &msg))
+			.await
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		let public = *public;
+		self.spawn_blocking(move |keystore| {
+			SyncCryptoStore::sr25519_vrf_sign(keystore, key_type, &public, transcript_data)
+		})
+		.await
+	}
+}
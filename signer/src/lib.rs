@@ -0,0 +1,135 @@
+//! Signing with keys held outside the node.
+//!
+//! [`RemoteKeystore`] implements the node's keystore traits by forwarding every request to a
+//! signing service over JSON-RPC on HTTP, so BABE and GRANDPA keys never touch the validator host.
+//! The node uses it when started with `--keystore-uri`.
+//!
+//! The service side is [`SignerApi`], served here by [`Signer`] over any keystore. The
+//! `remote-signer` binary serves a local keystore with it, as a reference implementation and as a
+//! stand-in for the real signing host in tests. Both sides can share a token, which the client
+//! sends as a bearer token and [`require_token`] checks.
+
+use std::{
+	convert::{TryFrom, TryInto},
+	path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519, Bytes,
+};
+
+mod client;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+pub use client::RemoteKeystore;
+pub use server::{require_token, Signer, SignerApi};
+
+/// The signature schemes keys can belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Crypto {
+	/// Schnorr signatures on Ristretto, used by BABE.
+	Sr25519,
+	/// Ed25519 signatures, used by GRANDPA.
+	Ed25519,
+	/// ECDSA signatures on secp256k1.
+	Ecdsa,
+}
+
+impl Crypto {
+	fn id(self) -> CryptoTypeId {
+		match self {
+			Self::Sr25519 => sr25519::CRYPTO_ID,
+			Self::Ed25519 => ed25519::CRYPTO_ID,
+			Self::Ecdsa => ecdsa::CRYPTO_ID,
+		}
+	}
+
+	fn from_id(id: CryptoTypeId) -> Option<Self> {
+		[Self::Sr25519, Self::Ed25519, Self::Ecdsa]
+			.iter()
+			.copied()
+			.find(|c| c.id() == id)
+	}
+}
+
+/// A public key together with its signature scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKey {
+	/// The signature scheme.
+	pub crypto: Crypto,
+	/// The raw public key.
+	pub public: Bytes,
+}
+
+impl TryFrom<CryptoTypePublicPair> for PublicKey {
+	type Error = CryptoTypeId;
+
+	fn try_from(
+		CryptoTypePublicPair(id, public): CryptoTypePublicPair,
+	) -> Result<Self, Self::Error> {
+		Ok(Self { crypto: Crypto::from_id(id).ok_or(id)?, public: public.into() })
+	}
+}
+
+impl From<PublicKey> for CryptoTypePublicPair {
+	fn from(key: PublicKey) -> Self {
+		Self(key.crypto.id(), key.public.0)
+	}
+}
+
+/// Owned counterpart of `sp_keystore::vrf::VRFTranscriptData`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VrfTranscript {
+	/// The transcript label.
+	pub label: Bytes,
+	/// Named values appended to the transcript, in order.
+	pub items: Vec<(String, VrfTranscriptValue)>,
+}
+
+/// A value appended to a VRF transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VrfTranscriptValue {
+	/// Raw bytes.
+	Bytes(Bytes),
+	/// A number.
+	U64(u64),
+}
+
+/// A VRF output and the proof it was computed with the key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VrfSignature {
+	/// The 32-byte VRF output.
+	pub output: Bytes,
+	/// The 64-byte proof.
+	pub proof: Bytes,
+}
+
+/// Key types travel as their four-character names, e.g. `babe` or `gran`.
+fn key_type_name(id: KeyTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+fn key_type_id(name: &str) -> Result<KeyTypeId, String> {
+	name.try_into().map_err(|_| format!("Invalid key type `{}`.", name))
+}
+
+/// Read a token shared by the node and the signing service from the file at `path`, ignoring
+/// surrounding whitespace.
+pub fn read_token(path: &Path) -> Result<String, String> {
+	let token = std::fs::read_to_string(path)
+		.map_err(|e| format!("Failed to read token from {}: {}", path.display(), e))?;
+	match token.trim() {
+		"" => Err(format!("Token file {} is empty.", path.display())),
+		token => Ok(token.into()),
+	}
+}
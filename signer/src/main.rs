@@ -0,0 +1,83 @@
+//! Reference signing service for `--keystore-uri`.
+//!
+//! Serves the keys of a local keystore over JSON-RPC on HTTP. It stands in for a real signing
+//! host in tests and shows what one has to implement; production deployments should put the
+//! keys behind an HSM or similar and restrict who can reach the service. Without a token it
+//! only listens on loopback addresses, as anyone who can reach it can sign with its keys.
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use jsonrpc_http_server::ServerBuilder;
+use remote_signer::{read_token, require_token, Signer, SignerApi};
+use sc_keystore::LocalKeystore;
+use sp_core::crypto::{key_types, SecretString};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+	/// Address to serve requests on.
+	#[structopt(long, default_value = "127.0.0.1:9955")]
+	listen: SocketAddr,
+
+	/// Directory of the keystore. Keys are kept in memory if none is given.
+	#[structopt(long, parse(from_os_str))]
+	keystore_path: Option<PathBuf>,
+
+	/// Password of the keystore at `--keystore-path`.
+	#[structopt(long)]
+	password: Option<String>,
+
+	/// Add Alice's BABE and GRANDPA keys, to sign for a `--dev` chain.
+	#[structopt(long)]
+	dev: bool,
+
+	/// File holding the token requests must carry, as `Authorization: Bearer <token>`. Required
+	/// to listen on addresses other than loopback ones.
+	#[structopt(long, parse(from_os_str))]
+	token_file: Option<PathBuf>,
+}
+
+fn main() -> Result<(), String> {
+	let opt = Opt::from_args();
+
+	let token = opt.token_file.as_deref().map(read_token).transpose()?;
+	if token.is_none() && !opt.listen.ip().is_loopback() {
+		return Err(format!("Refusing to listen on {} without --token-file.", opt.listen))
+	}
+
+	let keystore: SyncCryptoStorePtr = match &opt.keystore_path {
+		Some(path) => Arc::new(
+			LocalKeystore::open(path, opt.password.map(SecretString::new))
+				.map_err(|e| format!("Failed to open keystore {}: {}", path.display(), e))?,
+		),
+		None => Arc::new(LocalKeystore::in_memory()),
+	};
+
+	if opt.dev {
+		SyncCryptoStore::sr25519_generate_new(&*keystore, key_types::BABE, Some("//Alice"))
+			.and_then(|_| {
+				SyncCryptoStore::ed25519_generate_new(
+					&*keystore,
+					key_types::GRANDPA,
+					Some("//Alice"),
+				)
+			})
+			.map_err(|e| format!("Failed to add development keys: {}", e))?;
+	}
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	io.extend_with(SignerApi::to_delegate(Signer::new(keystore)));
+
+	let mut builder = ServerBuilder::new(io);
+	if let Some(token) = &token {
+		builder = builder.request_middleware(require_token(token));
+	}
+	let server = builder
+		.start_http(&opt.listen)
+		.map_err(|e| format!("Failed to listen on {}: {}", opt.listen, e))?;
+	println!("Serving signing requests on http://{}", server.address());
+	server.wait();
+
+	Ok(())
+}
@@ -0,0 +1,213 @@
+//! The signing service API and its implementation over a local keystore.
+
+use std::{collections::HashSet, convert::TryFrom, sync::Mutex};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_http_server::{
+	hyper::{
+		header::{HeaderValue, AUTHORIZATION},
+		Body, Request, StatusCode,
+	},
+	RequestMiddleware, RequestMiddlewareAction, Response,
+};
+use sp_core::{sr25519, Bytes};
+use sp_keystore::{
+	vrf::{VRFTranscriptData, VRFTranscriptValue},
+	SyncCryptoStore, SyncCryptoStorePtr,
+};
+
+use crate::{key_type_id, Crypto, PublicKey, VrfSignature, VrfTranscript, VrfTranscriptValue};
+
+/// Error code returned when the keystore fails to perform a request.
+const KEYSTORE_ERROR: i64 = 1;
+
+/// Signing service methods. Key types are passed by name, e.g. `babe`.
+#[rpc(server)]
+pub trait SignerApi {
+	/// All keys of the given key type.
+	#[rpc(name = "signer_keys")]
+	fn keys(&self, key_type: String) -> Result<Vec<PublicKey>>;
+
+	/// Generate a new key, from `seed` if given, and return its public key.
+	#[rpc(name = "signer_generate")]
+	fn generate(&self, key_type: String, crypto: Crypto, seed: Option<String>) -> Result<Bytes>;
+
+	/// Insert the key of the given secret URI under its public key.
+	#[rpc(name = "signer_insert")]
+	fn insert(&self, key_type: String, suri: String, public: Bytes) -> Result<()>;
+
+	/// Whether the keystore holds all the given public keys, each with its key type.
+	#[rpc(name = "signer_hasKeys")]
+	fn has_keys(&self, keys: Vec<(Bytes, String)>) -> Result<bool>;
+
+	/// Sign `message` with the given key, or return `null` if the keystore doesn't hold it.
+	#[rpc(name = "signer_sign")]
+	fn sign(&self, key_type: String, key: PublicKey, message: Bytes) -> Result<Option<Bytes>>;
+
+	/// Compute a VRF output over `transcript` with the given sr25519 key, or return `null` if
+	/// the keystore doesn't hold it.
+	#[rpc(name = "signer_vrfSign")]
+	fn vrf_sign(
+		&self,
+		key_type: String,
+		public: Bytes,
+		transcript: VrfTranscript,
+	) -> Result<Option<VrfSignature>>;
+}
+
+/// Implements the [`SignerApi`] RPC trait over a keystore.
+pub struct Signer {
+	keystore: SyncCryptoStorePtr,
+	/// Labels and item names of VRF transcripts, see [`Signer::intern`].
+	interned: Mutex<HashSet<&'static [u8]>>,
+}
+
+impl Signer {
+	/// Create a new `Signer` serving the keys of `keystore`.
+	pub fn new(keystore: SyncCryptoStorePtr) -> Self {
+		Self { keystore, interned: Default::default() }
+	}
+
+	/// `VRFTranscriptData` only holds static strings. Labels and item names are a handful of
+	/// protocol constants, so each distinct one is leaked once and then reused.
+	fn intern(&self, bytes: &[u8]) -> &'static [u8] {
+		let mut interned = self.interned.lock().expect("Interned set is never poisoned; qed");
+		if let Some(interned) = interned.get(bytes) {
+			return interned
+		}
+		let leaked: &'static [u8] = Box::leak(bytes.to_vec().into_boxed_slice());
+		interned.insert(leaked);
+		leaked
+	}
+}
+
+/// HTTP middleware answering requests without the header `Authorization: Bearer <token>` with
+/// `401 Unauthorized`, for `ServerBuilder::request_middleware`.
+pub fn require_token(token: &str) -> impl RequestMiddleware {
+	let expected = format!("Bearer {}", token).into_bytes();
+	move |request: Request<Body>| -> RequestMiddlewareAction {
+		let authorized = request
+			.headers()
+			.get(AUTHORIZATION)
+			.map_or(false, |given| constant_time_eq(given.as_bytes(), &expected));
+		if authorized {
+			request.into()
+		} else {
+			Response {
+				code: StatusCode::UNAUTHORIZED,
+				content_type: HeaderValue::from_static("text/plain; charset=utf-8"),
+				content: "Missing or invalid token.\n".into(),
+			}
+			.into()
+		}
+	}
+}
+
+/// Compare without returning early, so the time taken doesn't reveal how much of a token is
+/// right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn keystore_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(KEYSTORE_ERROR),
+		message: "Keystore request failed.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn parse_key_type(name: &str) -> Result<sp_core::crypto::KeyTypeId> {
+	key_type_id(name).map_err(RpcError::invalid_params)
+}
+
+impl SignerApi for Signer {
+	fn keys(&self, key_type: String) -> Result<Vec<PublicKey>> {
+		let keys = SyncCryptoStore::keys(&*self.keystore, parse_key_type(&key_type)?)
+			.map_err(keystore_error)?;
+
+		Ok(keys.into_iter().filter_map(|key| PublicKey::try_from(key).ok()).collect())
+	}
+
+	fn generate(&self, key_type: String, crypto: Crypto, seed: Option<String>) -> Result<Bytes> {
+		let id = parse_key_type(&key_type)?;
+		let seed = seed.as_deref();
+		let keystore = &*self.keystore;
+
+		let public = match crypto {
+			Crypto::Sr25519 => SyncCryptoStore::sr25519_generate_new(keystore, id, seed)
+				.map(|public| public.0.to_vec()),
+			Crypto::Ed25519 => SyncCryptoStore::ed25519_generate_new(keystore, id, seed)
+				.map(|public| public.0.to_vec()),
+			Crypto::Ecdsa => SyncCryptoStore::ecdsa_generate_new(keystore, id, seed)
+				.map(|public| public.0.to_vec()),
+		};
+
+		public.map(Into::into).map_err(keystore_error)
+	}
+
+	fn insert(&self, key_type: String, suri: String, public: Bytes) -> Result<()> {
+		SyncCryptoStore::insert_unknown(&*self.keystore, parse_key_type(&key_type)?, &suri, &public)
+			.map_err(keystore_error)
+	}
+
+	fn has_keys(&self, keys: Vec<(Bytes, String)>) -> Result<bool> {
+		let keys = keys
+			.into_iter()
+			.map(|(public, key_type)| Ok((public.0, parse_key_type(&key_type)?)))
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(SyncCryptoStore::has_keys(&*self.keystore, &keys))
+	}
+
+	fn sign(&self, key_type: String, key: PublicKey, message: Bytes) -> Result<Option<Bytes>> {
+		SyncCryptoStore::sign_with(
+			&*self.keystore,
+			parse_key_type(&key_type)?,
+			&key.into(),
+			&message,
+		)
+		.map(|signature| signature.map(Into::into))
+		.map_err(keystore_error)
+	}
+
+	fn vrf_sign(
+		&self,
+		key_type: String,
+		public: Bytes,
+		transcript: VrfTranscript,
+	) -> Result<Option<VrfSignature>> {
+		let public = sr25519::Public::try_from(&public[..])
+			.map_err(|_| RpcError::invalid_params("Invalid sr25519 public key."))?;
+		let transcript = VRFTranscriptData {
+			label: self.intern(&transcript.label),
+			items: transcript
+				.items
+				.into_iter()
+				.map(|(name, value)| {
+					let name = std::str::from_utf8(self.intern(name.as_bytes()))
+						.expect("Interned from a `str`; qed");
+					let value = match value {
+						VrfTranscriptValue::Bytes(bytes) => VRFTranscriptValue::Bytes(bytes.0),
+						VrfTranscriptValue::U64(number) => VRFTranscriptValue::U64(number),
+					};
+					(name, value)
+				})
+				.collect(),
+		};
+
+		let signature = SyncCryptoStore::sr25519_vrf_sign(
+			&*self.keystore,
+			parse_key_type(&key_type)?,
+			&public,
+			transcript,
+		)
+		.map_err(keystore_error)?;
+
+		Ok(signature.map(|signature| VrfSignature {
+			output: signature.output.to_bytes().to_vec().into(),
+			proof: signature.proof.to_bytes().to_vec().into(),
+		}))
+	}
+}
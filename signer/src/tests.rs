@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use jsonrpc_http_server::{Server, ServerBuilder};
+use sc_keystore::LocalKeystore;
+use sp_core::{
+	crypto::{key_types::AURA, CryptoTypePublicPair, Pair},
+	ed25519, sr25519,
+};
+use sp_keystore::{
+	vrf::{make_transcript, VRFTranscriptData, VRFTranscriptValue},
+	CryptoStore, SyncCryptoStore,
+};
+
+use crate::{require_token, RemoteKeystore, Signer, SignerApi};
+
+const TOKEN: &str = "secret";
+
+fn serve() -> (Server, RemoteKeystore) {
+	let mut io = jsonrpc_core::IoHandler::default();
+	io.extend_with(SignerApi::to_delegate(Signer::new(Arc::new(LocalKeystore::in_memory()))));
+	let server = ServerBuilder::new(io)
+		.request_middleware(require_token(TOKEN))
+		.start_http(&"127.0.0.1:0".parse().unwrap())
+		.unwrap();
+	let keystore = RemoteKeystore::open(&url(&server), Some(TOKEN)).unwrap();
+	(server, keystore)
+}
+
+fn url(server: &Server) -> String {
+	format!("http://{}", server.address())
+}
+
+#[test]
+fn requests_need_the_token() {
+	let (server, _) = serve();
+
+	assert!(RemoteKeystore::open(&url(&server), None).is_err());
+	assert!(RemoteKeystore::open(&url(&server), Some("secreT")).is_err());
+	assert!(RemoteKeystore::open(&url(&server), Some("secret2")).is_err());
+}
+
+#[test]
+fn async_requests_are_sent_from_the_blocking_pool() {
+	let (_server, keystore) = serve();
+	let mut runtime = tokio::runtime::Builder::new().basic_scheduler().build().unwrap();
+
+	let public = runtime
+		.block_on(CryptoStore::sr25519_generate_new(&keystore, AURA, None))
+		.unwrap();
+	assert_eq!(runtime.block_on(CryptoStore::sr25519_public_keys(&keystore, AURA)), vec![public]);
+	let key = CryptoTypePublicPair::from(&public);
+	let signature = runtime
+		.block_on(CryptoStore::sign_with(&keystore, AURA, &key, b"message"))
+		.unwrap();
+	let signature = sr25519::Signature::from_slice(&signature.unwrap());
+	assert!(sr25519::Pair::verify(&signature, b"message", &public));
+}
+
+#[test]
+fn generates_and_signs_with_remote_keys() {
+	let (_server, keystore) = serve();
+
+	let sr25519 = SyncCryptoStore::sr25519_generate_new(&keystore, AURA, Some("//Alice")).unwrap();
+	let ed25519 = SyncCryptoStore::ed25519_generate_new(&keystore, AURA, None).unwrap();
+	assert_eq!(sr25519, sr25519::Pair::from_string("//Alice", None).unwrap().public());
+	assert_eq!(SyncCryptoStore::sr25519_public_keys(&keystore, AURA), vec![sr25519]);
+	assert_eq!(SyncCryptoStore::ed25519_public_keys(&keystore, AURA), vec![ed25519]);
+	assert!(SyncCryptoStore::has_keys(&keystore, &[(sr25519.0.to_vec(), AURA)]));
+
+	let key = CryptoTypePublicPair::from(&ed25519);
+	let signature = SyncCryptoStore::sign_with(&keystore, AURA, &key, b"message").unwrap().unwrap();
+	let signature = ed25519::Signature::from_slice(&signature);
+	assert!(ed25519::Pair::verify(&signature, b"message", &ed25519));
+}
+
+#[test]
+fn unknown_keys_are_reported_as_missing() {
+	let (_server, keystore) = serve();
+	let public = sr25519::Pair::from_string("//Bob", None).unwrap().public();
+
+	assert!(!SyncCryptoStore::has_keys(&keystore, &[(public.0.to_vec(), AURA)]));
+	let key = CryptoTypePublicPair::from(&public);
+	assert_eq!(SyncCryptoStore::sign_with(&keystore, AURA, &key, b"message").unwrap(), None);
+}
+
+#[test]
+fn computes_verifiable_vrf_outputs() {
+	let (_server, keystore) = serve();
+	let public = SyncCryptoStore::sr25519_generate_new(&keystore, AURA, None).unwrap();
+	let transcript = || VRFTranscriptData {
+		label: b"test",
+		items: vec![
+			("slot", VRFTranscriptValue::U64(42)),
+			("epoch", VRFTranscriptValue::Bytes(vec![1, 2])),
+		],
+	};
+
+	let signature = SyncCryptoStore::sr25519_vrf_sign(&keystore, AURA, &public, transcript())
+		.unwrap()
+		.unwrap();
+
+	let public = schnorrkel::PublicKey::from_bytes(&public.0).unwrap();
+	assert!(public
+		.vrf_verify(make_transcript(transcript()), &signature.output, &signature.proof)
+		.is_ok());
+}